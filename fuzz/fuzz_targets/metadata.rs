@@ -0,0 +1,15 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use meteoritus::Metadata;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(value) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    /* Every input must produce either a valid Metadata or a MetadataError */
+    if let Ok(metadata) = Metadata::try_from(value) {
+        let _ = metadata.len();
+    }
+});
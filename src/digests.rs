@@ -0,0 +1,67 @@
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::fs::to_hex;
+
+/// SHA-256 state of an upload, as `(hashed bytes, hasher)`.
+pub(crate) type DigestState = (u64, Sha256);
+
+/// Hashes the uploads verified against a full file checksum as their chunks arrive.
+///
+/// Uploads with a chunk that was not seen, like one written before a restart, are
+/// forgotten and hashed from the stored file on completion instead.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RollingDigests {
+    entries: Arc<Mutex<HashMap<String, DigestState>>>,
+}
+
+impl RollingDigests {
+    /// Feeds the chunk about to be written at `offset`.
+    ///
+    /// Returns the state the upload had before the chunk, to be restored when the
+    /// chunk can't be written.
+    pub(crate) fn update(
+        &self,
+        id: &str,
+        offset: u64,
+        chunk: &[u8],
+    ) -> Option<DigestState> {
+        let mut entries = self.entries.lock().ok()?;
+
+        let previous = match entries.remove(id) {
+            Some(state) if state.0 == offset => state,
+            None if offset == 0 => (0, Sha256::new()),
+            _ => return None,
+        };
+
+        let mut hasher = previous.1.clone();
+        hasher.update(chunk);
+
+        entries.insert(id.to_string(), (offset + chunk.len() as u64, hasher));
+
+        Some(previous)
+    }
+
+    /// Puts back the state of an upload before a chunk that was not written.
+    pub(crate) fn restore(&self, id: &str, state: DigestState) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(id.to_string(), state);
+        }
+    }
+
+    /// Returns the hex encoded digest of an upload whose `length` bytes were all hashed.
+    pub(crate) fn finish(&self, id: &str, length: u64) -> Option<String> {
+        let (hashed, hasher) = self.entries.lock().ok()?.remove(id)?;
+
+        (hashed == length).then(|| to_hex(&hasher.finalize()))
+    }
+
+    pub(crate) fn forget(&self, id: &str) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(id);
+        }
+    }
+}
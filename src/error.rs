@@ -0,0 +1,130 @@
+use rocket::http::Status;
+
+/// Represents the tus protocol errors reported by [`Meteoritus`](crate::Meteoritus) routes.
+///
+/// Every variant is answered with a default [`Status`] following the tus protocol recommendation.
+/// It can be overridden with [`Meteoritus::with_error_status()`](crate::Meteoritus::with_error_status).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MeteoritusError {
+    /// Missing or invalid `Tus-Resumable` header, defaults to `400 Bad Request`.
+    InvalidTusResumable,
+    /// A single-valued tus header was sent with conflicting values, defaults to `400 Bad Request`.
+    ConflictingHeaders,
+    /// Missing or invalid `Upload-Length` header, defaults to `400 Bad Request`.
+    InvalidUploadLength,
+    /// The `Upload-Length` exceeds the `Tus-Max-Size`, defaults to `413 Payload Too Large`.
+    UploadTooLarge,
+    /// Empty uploads are not allowed, defaults to `400 Bad Request`.
+    EmptyUpload,
+    /// Invalid `Upload-Metadata` header, defaults to `400 Bad Request`.
+    InvalidMetadata,
+    /// The `Upload-Metadata` header exceeds the metadata budget, defaults to `413 Payload Too Large`.
+    MetadataTooLarge,
+    /// The `Upload-Key-Id` is unknown to the key provider, defaults to `400 Bad Request`.
+    UnknownKeyId,
+    /// The `on_creation` callback rejected the upload, defaults to `422 Unprocessable Entity`.
+    CreationRejected,
+    /// The `Idempotency-Key` was used for a creation with another `Upload-Length` or `Upload-Metadata`, defaults to `422 Unprocessable Entity`.
+    IdempotencyKeyMismatch,
+    /// A creation with the same `Idempotency-Key` is still in flight, defaults to `409 Conflict`.
+    IdempotencyKeyInFlight,
+    /// A `PATCH` request carries an `Upload-Length` different from the stored one, defaults to `400 Bad Request`.
+    UploadLengthMismatch,
+    /// Missing or invalid `Upload-Offset` header, defaults to `400 Bad Request`.
+    InvalidUploadOffset,
+    /// The `Content-Range` header disagrees with the chunk, defaults to `400 Bad Request`.
+    InvalidContentRange,
+    /// Missing `Content-Type` header, defaults to `400 Bad Request`.
+    MissingContentType,
+    /// The `Content-Type` is not `application/offset+octet-stream`, defaults to `415 Unsupported Media Type`.
+    InvalidContentType,
+    /// The uploaded content does not match its declared `filetype`, defaults to `415 Unsupported Media Type`.
+    ContentTypeMismatch,
+    /// The `Upload-Offset` does not match the current upload offset, defaults to `409 Conflict`.
+    OffsetMismatch,
+    /// The completed upload, or a chunk, does not match its expected checksum, defaults to `460 Checksum Mismatch`.
+    ChecksumMismatch,
+    /// Invalid `Upload-Checksum` or `Upload-Checksum-Algorithm` header, defaults to `400 Bad Request`.
+    InvalidChecksum,
+    /// A chunk not completing the upload is below the minimum chunk size, defaults to `400 Bad Request`.
+    ChunkTooSmall,
+    /// The request carries an unexpected body, defaults to `400 Bad Request`.
+    UnexpectedBody,
+    /// The `Upload-Metadata-Hash` does not match the stored metadata, defaults to `412 Precondition Failed`.
+    MetadataHashMismatch,
+    /// A download was requested before all bytes were received, defaults to `409 Conflict`.
+    UploadIncomplete,
+    /// A download timed out waiting for the upload scan to release it, defaults to `423 Locked`.
+    UploadScanning,
+    /// A download was requested for an upload found infected by the scan, defaults to `403 Forbidden`.
+    UploadInfected,
+    /// The `Range` of a download is outside of the file, defaults to `416 Range Not Satisfiable`.
+    RangeNotSatisfiable,
+    /// The upload does not exist, defaults to `404 Not Found`.
+    NotFound,
+    /// The upload could not be terminated, defaults to `410 Gone`.
+    Gone,
+    /// The uploaded chunk could not be processed, defaults to `422 Unprocessable Entity`.
+    UnprocessableChunk,
+    /// An internal error occurred, defaults to `500 Internal Server Error`.
+    Internal,
+    /// The storage kept failing with transient errors, defaults to `503 Service Unavailable`.
+    StorageUnavailable,
+    /// The storage did not look up the upload in time, defaults to `504 Gateway Timeout`.
+    LookupTimeout,
+    /// The maximum number of storage lookups running at once is reached, defaults to `503 Service Unavailable`.
+    TooManyLookups,
+    /// The node is draining and refuses new uploads, defaults to `503 Service Unavailable`.
+    Draining,
+    /// Too few inodes are left to store a new upload, defaults to `507 Insufficient Storage`.
+    InsufficientStorage,
+    /// The maximum number of streamed `PATCH` bodies is reached, defaults to `503 Service Unavailable`.
+    TooManyStreams,
+}
+
+impl MeteoritusError {
+    /// Returns the default [`Status`] reported for this error.
+    pub fn default_status(&self) -> Status {
+        match self {
+            Self::InvalidTusResumable
+            | Self::ConflictingHeaders
+            | Self::InvalidUploadLength
+            | Self::EmptyUpload
+            | Self::InvalidMetadata
+            | Self::UnknownKeyId
+            | Self::UploadLengthMismatch
+            | Self::InvalidUploadOffset
+            | Self::InvalidContentRange
+            | Self::MissingContentType
+            | Self::InvalidChecksum
+            | Self::ChunkTooSmall
+            | Self::UnexpectedBody => Status::BadRequest,
+            Self::UploadTooLarge | Self::MetadataTooLarge => {
+                Status::PayloadTooLarge
+            }
+            Self::CreationRejected
+            | Self::IdempotencyKeyMismatch
+            | Self::UnprocessableChunk => Status::UnprocessableEntity,
+            Self::InvalidContentType | Self::ContentTypeMismatch => {
+                Status::UnsupportedMediaType
+            }
+            Self::OffsetMismatch
+            | Self::IdempotencyKeyInFlight
+            | Self::UploadIncomplete => Status::Conflict,
+            Self::RangeNotSatisfiable => Status::RangeNotSatisfiable,
+            Self::UploadScanning => Status::Locked,
+            Self::UploadInfected => Status::Forbidden,
+            Self::ChecksumMismatch => Status::new(460),
+            Self::MetadataHashMismatch => Status::PreconditionFailed,
+            Self::NotFound => Status::NotFound,
+            Self::Gone => Status::Gone,
+            Self::Internal => Status::InternalServerError,
+            Self::LookupTimeout => Status::GatewayTimeout,
+            Self::InsufficientStorage => Status::InsufficientStorage,
+            Self::StorageUnavailable
+            | Self::TooManyStreams
+            | Self::TooManyLookups
+            | Self::Draining => Status::ServiceUnavailable,
+        }
+    }
+}
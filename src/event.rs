@@ -0,0 +1,148 @@
+use std::{collections::HashMap, sync::Arc, time::SystemTime};
+
+use rocket::serde::Serialize;
+use rocket::tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender};
+
+use crate::fs::{FileInfo, Metadata};
+
+/// Represents the upload lifecycle stage reported by a [`MeteoritusEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(crate = "rocket::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// The upload has been created.
+    Created,
+    /// All bytes of the upload have been received.
+    Completed,
+    /// A completed upload is being copied to its final destination.
+    Moving,
+    /// The upload has been terminated by a client request.
+    Terminated,
+    /// The upload has been removed by the sweeper once its expiration or retention elapsed.
+    Expired,
+    /// The scanner found the completed upload clean, it can be downloaded.
+    Released,
+    /// The scanner found the completed upload infected, its data has been deleted.
+    Quarantined,
+}
+
+/// Represents an upload lifecycle event published to an [`EventSink`].
+#[derive(Debug, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct MeteoritusEvent {
+    pub kind: EventKind,
+    pub id: String,
+    pub length: u64,
+    pub metadata: Option<Metadata>,
+    pub created_at: Option<SystemTime>,
+    pub completed_at: Option<SystemTime>,
+    /// Bytes copied so far, only set on [`EventKind::Moving`] events.
+    pub moved_bytes: Option<u64>,
+    /// Request headers recorded on creation, only set on [`EventKind::Created`] events.
+    pub request_headers: Option<HashMap<String, String>>,
+    pub emitted_at: SystemTime,
+}
+
+impl MeteoritusEvent {
+    pub(crate) fn new<State>(kind: EventKind, file: &FileInfo<State>) -> Self {
+        Self {
+            kind,
+            id: file.id().to_string(),
+            length: *file.length(),
+            metadata: file.metadata().to_owned(),
+            created_at: *file.created_at(),
+            completed_at: *file.completed_at(),
+            moved_bytes: None,
+            request_headers: match kind {
+                EventKind::Created => file.request_headers().to_owned(),
+                _ => None,
+            },
+            emitted_at: SystemTime::now(),
+        }
+    }
+
+    pub(crate) fn with_moved_bytes(mut self, moved_bytes: u64) -> Self {
+        self.moved_bytes = Some(moved_bytes);
+        self
+    }
+}
+
+/// A destination for [`MeteoritusEvent`]s, like a message broker.
+///
+/// Events are handed over from a bounded buffer in a background task, so a slow sink never
+/// blocks the upload requests. When the buffer is full new events are dropped and logged.
+///
+/// # Example
+///
+///   ```rust,no_run
+///   use meteoritus::{EventSink, MeteoritusEvent};
+///
+///   struct LogSink;
+///
+///   #[rocket::async_trait]
+///   impl EventSink for LogSink {
+///       async fn publish(&self, event: MeteoritusEvent) {
+///           println!("{:?}: {}", event.kind, event.id);
+///       }
+///   }
+///   ```
+#[rocket::async_trait]
+pub trait EventSink: Send + Sync {
+    async fn publish(&self, event: MeteoritusEvent);
+}
+
+/// Non-blocking handle used by the routes to emit events.
+#[derive(Clone)]
+pub(crate) struct EventBus {
+    sender: Sender<MeteoritusEvent>,
+}
+
+impl EventBus {
+    /// Spawns the task forwarding buffered events to the given sink.
+    pub(crate) fn spawn(sink: Arc<dyn EventSink>, capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity.max(1));
+
+        rocket::tokio::spawn(Self::forward(sink, receiver));
+
+        Self { sender }
+    }
+
+    async fn forward(
+        sink: Arc<dyn EventSink>,
+        mut receiver: Receiver<MeteoritusEvent>,
+    ) {
+        while let Some(event) = receiver.recv().await {
+            let (kind, id) = (event.kind, event.id.to_owned());
+            let sink = sink.to_owned();
+
+            /* A panicking sink loses this event only, the next ones are still published */
+            let published =
+                rocket::tokio::spawn(async move { sink.publish(event).await });
+
+            if published.await.is_err() {
+                error!(
+                    "Event sink panicked publishing {:?} event for {}",
+                    kind, id
+                );
+            }
+        }
+    }
+
+    pub(crate) fn emit(&self, event: MeteoritusEvent) {
+        match self.sender.try_send(event) {
+            Ok(()) => {}
+            Err(TrySendError::Full(event)) => {
+                warn!(
+                    "Event buffer is full, dropping {:?} event for {}",
+                    event.kind, event.id
+                );
+            }
+            Err(TrySendError::Closed(event)) => {
+                error!(
+                    "Event sink is closed, dropping {:?} event for {}",
+                    event.kind, event.id
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+/// Completed uploads as `(completed at, id)`, ordered by completion time.
+type CompletedEntries = BTreeSet<(SystemTime, String)>;
+
+/// Completed uploads retained in staging, ordered by completion time.
+///
+/// The queue is seeded from the stored uploads on its first use and kept up to date as
+/// uploads complete afterwards. Uploads removed by other means are left in the queue, they
+/// are dropped once they are the oldest.
+#[derive(Debug, Clone)]
+pub(crate) struct CompletedQueue {
+    max_completed: usize,
+    entries: Arc<Mutex<Option<CompletedEntries>>>,
+}
+
+impl CompletedQueue {
+    pub(crate) fn new(max_completed: usize) -> Self {
+        Self {
+            max_completed,
+            entries: Default::default(),
+        }
+    }
+
+    /// Records a completed upload, returns the ids exceeding the maximum count, oldest first.
+    ///
+    /// `stored` lists the completed uploads already retained, it is only called once.
+    pub(crate) fn push<I>(
+        &self,
+        completed_at: SystemTime,
+        id: &str,
+        stored: impl FnOnce() -> I,
+    ) -> Vec<String>
+    where
+        I: IntoIterator<Item = (SystemTime, String)>,
+    {
+        let Ok(mut entries) = self.entries.lock() else {
+            return Vec::new();
+        };
+
+        let entries =
+            entries.get_or_insert_with(|| stored().into_iter().collect());
+        entries.insert((completed_at, id.to_string()));
+
+        let mut evicted = Vec::new();
+
+        while entries.len() > self.max_completed {
+            match entries.pop_first() {
+                Some((_, id)) => evicted.push(id),
+                None => break,
+            }
+        }
+
+        evicted
+    }
+}
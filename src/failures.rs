@@ -0,0 +1,123 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// How long failures and aborted uploads are remembered, to keep answering their requests.
+const RETENTION: Duration = Duration::from_secs(60 * 60);
+
+/// Consecutive failures of an upload, as `(failed chunks, last failed at, aborted at)`.
+type FailureRecord = (u32, Instant, Option<Instant>);
+
+/// Counts the consecutive failed `PATCH` requests of every upload.
+///
+/// Uploads whose last failure, or abort, is older than the retention are forgotten, so
+/// abandoned uploads don't pile up.
+#[derive(Debug, Clone)]
+pub(crate) struct FailureCounter {
+    max_failures: u32,
+    retention: Duration,
+    entries: Arc<Mutex<HashMap<String, FailureRecord>>>,
+}
+
+impl FailureCounter {
+    pub(crate) fn new(max_failures: u32) -> Self {
+        Self {
+            max_failures,
+            retention: RETENTION,
+            entries: Default::default(),
+        }
+    }
+
+    /// Records a failed chunk, returns `true` once the upload has to be aborted.
+    pub(crate) fn record(&self, id: &str) -> bool {
+        let Ok(mut entries) = self.entries.lock() else {
+            return false;
+        };
+
+        entries.retain(|_, (_, failed_at, aborted_at)| {
+            aborted_at.unwrap_or(*failed_at).elapsed() < self.retention
+        });
+
+        let now = Instant::now();
+        let (failures, failed_at, aborted_at) =
+            entries.entry(id.to_string()).or_insert((0, now, None));
+
+        *failures += 1;
+        *failed_at = now;
+
+        if *failures > self.max_failures && aborted_at.is_none() {
+            *aborted_at = Some(now);
+            return true;
+        }
+
+        false
+    }
+
+    /// Resets the failures of an upload after a successful chunk.
+    pub(crate) fn reset(&self, id: &str) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.remove(id);
+        }
+    }
+
+    /// Indicates if the upload was aborted for too many failures.
+    pub(crate) fn is_aborted(&self, id: &str) -> bool {
+        let Ok(entries) = self.entries.lock() else {
+            return false;
+        };
+
+        matches!(entries.get(id), Some((_, _, Some(at))) if at.elapsed() < self.retention)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread::sleep;
+
+    fn counter(max_failures: u32, retention: Duration) -> FailureCounter {
+        FailureCounter {
+            retention,
+            ..FailureCounter::new(max_failures)
+        }
+    }
+
+    #[test]
+    fn uploads_are_aborted_past_the_maximum() {
+        let failures = counter(1, RETENTION);
+
+        assert!(!failures.record("a"));
+        assert!(failures.record("a"));
+        assert!(failures.is_aborted("a"));
+
+        /* Only the failure crossing the maximum aborts the upload */
+        assert!(!failures.record("a"));
+    }
+
+    #[test]
+    fn stale_failures_are_evicted() {
+        let failures = counter(5, Duration::from_millis(20));
+
+        failures.record("never-aborted");
+        sleep(Duration::from_millis(30));
+        failures.record("other");
+
+        let entries = failures.entries.lock().unwrap();
+        assert!(!entries.contains_key("never-aborted"));
+        assert!(entries.contains_key("other"));
+    }
+
+    #[test]
+    fn aborted_uploads_are_forgotten_after_the_retention() {
+        let failures = counter(0, Duration::from_millis(20));
+
+        assert!(failures.record("a"));
+        sleep(Duration::from_millis(30));
+
+        assert!(!failures.is_aborted("a"));
+        failures.record("other");
+        assert!(!failures.entries.lock().unwrap().contains_key("a"));
+    }
+}
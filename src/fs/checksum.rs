@@ -0,0 +1,42 @@
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+use std::{
+    fs::File,
+    io::{self, Result},
+    path::Path,
+};
+
+/// Computes the hex encoded SHA-256 digest of the file located at `path`.
+pub(crate) fn sha256_digest<P: AsRef<Path>>(path: P) -> Result<String> {
+    let mut hasher = Sha256::new();
+    let mut file = File::open(path)?;
+
+    io::copy(&mut file, &mut hasher)?;
+
+    Ok(to_hex(&hasher.finalize()))
+}
+
+/// Computes the hex encoded SHA-256 digest of `bytes`.
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    to_hex(&Sha256::digest(bytes))
+}
+
+/// Algorithms accepted in the `Upload-Checksum` header of `PATCH` requests.
+pub(crate) const CHECKSUM_ALGORITHMS: [&str; 3] = ["sha1", "sha256", "sha512"];
+
+/// Computes the raw digest of `bytes` with the named algorithm, if it is supported.
+pub(crate) fn checksum_digest(
+    algorithm: &str,
+    bytes: &[u8],
+) -> Option<Vec<u8>> {
+    match algorithm {
+        "sha1" => Some(Sha1::digest(bytes).to_vec()),
+        "sha256" => Some(Sha256::digest(bytes).to_vec()),
+        "sha512" => Some(Sha512::digest(bytes).to_vec()),
+        _ => None,
+    }
+}
+
+pub(crate) fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
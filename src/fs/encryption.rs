@@ -0,0 +1,177 @@
+use aes::{
+    cipher::{KeyIvInit, StreamCipher, StreamCipherSeek},
+    Aes256,
+};
+#[cfg(feature = "download")]
+use rocket::tokio::io::{AsyncRead, ReadBuf};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+#[cfg(feature = "download")]
+use std::{
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use super::checksum::{sha256_hex, to_hex};
+
+type Aes256Ctr = ctr::Ctr128BE<Aes256>;
+
+/// Size of the buffer used when a completed file is hashed.
+const DECRYPT_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// A source of data-encryption keys, like a key management service.
+///
+/// Clients select a key by sending its id in the `Upload-Key-Id` header on creation.
+/// Only the key id is stored along with the upload, the key is fetched again whenever
+/// a chunk is written and whenever the completed upload is read.
+///
+/// # Example
+///
+///   ```rust,no_run
+///   use meteoritus::KeyProvider;
+///
+///   struct StaticKeyProvider;
+///
+///   impl KeyProvider for StaticKeyProvider {
+///       fn key(&self, key_id: &str) -> Option<[u8; 32]> {
+///           match key_id {
+///               "tenant-1" => Some(*b"an example very very secret key."),
+///               _ => None,
+///           }
+///       }
+///   }
+///   ```
+pub trait KeyProvider: Send + Sync {
+    /// Returns the 256 bit key with the given id, if it exists.
+    fn key(&self, key_id: &str) -> Option<[u8; 32]>;
+}
+
+/// A [`KeyProvider`] holding its keys in memory, mostly useful for tests.
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryKeyProvider(HashMap<String, [u8; 32]>);
+
+impl InMemoryKeyProvider {
+    /// Creates a provider without keys.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a key with the given id.
+    pub fn with_key(mut self, key_id: &str, key: [u8; 32]) -> Self {
+        self.0.insert(key_id.to_string(), key);
+        self
+    }
+}
+
+impl KeyProvider for InMemoryKeyProvider {
+    fn key(&self, key_id: &str) -> Option<[u8; 32]> {
+        self.0.get(key_id).copied()
+    }
+}
+
+/// AES-256 in counter mode, keyed per upload.
+///
+/// The counter mode keeps the ciphertext as long as the plaintext, so the chunks of an
+/// upload can be encrypted at their offset without changing the upload length.
+#[derive(Clone)]
+pub(crate) struct UploadCipher {
+    key: [u8; 32],
+    iv: [u8; 16],
+}
+
+impl UploadCipher {
+    /// Derives the cipher of an upload, the iv is unique per upload id.
+    pub(crate) fn new(key: [u8; 32], file_id: &str) -> Self {
+        let digest = Sha256::digest(file_id.as_bytes());
+
+        let mut iv = [0; 16];
+        iv.copy_from_slice(&digest[..16]);
+
+        Self { key, iv }
+    }
+
+    /// Returns a digest identifying the key, used to detect a provider answering another key.
+    pub(crate) fn key_check(key: &[u8; 32]) -> String {
+        sha256_hex(&[b"meteoritus-key-check:".as_slice(), key].concat())
+    }
+
+    /// Encrypts, or decrypts, the bytes located at `offset` of the upload in place.
+    pub(crate) fn apply(&self, offset: u64, buf: &mut [u8]) {
+        let mut cipher = Aes256Ctr::new(&self.key.into(), &self.iv.into());
+
+        cipher.seek(offset);
+        cipher.apply_keystream(buf);
+    }
+
+    /// Computes the hex encoded SHA-256 digest of the plaintext of an encrypted upload.
+    pub(crate) fn plaintext_digest(&self, path: &Path) -> io::Result<String> {
+        let mut file = File::open(path)?;
+        let mut hasher = Sha256::new();
+
+        let mut buf = vec![0; DECRYPT_BUFFER_SIZE];
+        let mut offset = 0;
+
+        loop {
+            let read = match file.read(&mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            self.apply(offset, &mut buf[..read]);
+            hasher.update(&buf[..read]);
+
+            offset += read as u64;
+        }
+
+        Ok(to_hex(&hasher.finalize()))
+    }
+
+    /// Decrypts the upload bytes read from `reader`, which starts at `offset` of the upload.
+    #[cfg(feature = "download")]
+    pub(crate) fn decrypting<R>(
+        self,
+        reader: R,
+        offset: u64,
+    ) -> DecryptingReader<R> {
+        DecryptingReader {
+            reader,
+            cipher: self,
+            offset,
+        }
+    }
+}
+
+/// Reads the plaintext of an encrypted upload, which stays encrypted at rest.
+#[cfg(feature = "download")]
+pub(crate) struct DecryptingReader<R> {
+    reader: R,
+    cipher: UploadCipher,
+    offset: u64,
+}
+
+#[cfg(feature = "download")]
+impl<R: AsyncRead + Unpin> AsyncRead for DecryptingReader<R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+
+        ready!(Pin::new(&mut this.reader).poll_read(cx, buf))?;
+
+        let read = &mut buf.filled_mut()[filled..];
+        this.cipher.apply(this.offset, read);
+        this.offset += read.len() as u64;
+
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,53 @@
+use std::time::{Duration, SystemTime};
+
+/// Represents the expiration windows applied to uploads.
+///
+/// An upload can expire a fixed time after its creation (`absolute`) and/or after a period
+/// without any `PATCH` activity (`idle`). When both are set the earliest one wins.
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Expiration {
+    absolute: Option<Duration>,
+    idle: Option<Duration>,
+}
+
+impl Expiration {
+    pub(crate) fn with_absolute(mut self, duration: Duration) -> Self {
+        self.absolute = Some(duration);
+        self
+    }
+
+    pub(crate) fn with_idle(mut self, duration: Duration) -> Self {
+        self.idle = Some(duration);
+        self
+    }
+
+    /// Returns the maximum lifetime of an upload counted from its creation.
+    pub fn absolute(&self) -> Option<Duration> {
+        self.absolute
+    }
+
+    /// Returns the maximum time an upload can stay without activity.
+    pub fn idle(&self) -> Option<Duration> {
+        self.idle
+    }
+
+    /// Indicates if any expiration window has been configured.
+    pub fn is_enabled(&self) -> bool {
+        self.absolute.is_some() || self.idle.is_some()
+    }
+
+    /// Computes when an upload expires from its creation and last activity times.
+    pub fn expires_at(
+        &self,
+        created_at: SystemTime,
+        last_activity: SystemTime,
+    ) -> Option<SystemTime> {
+        let absolute = self.absolute.map(|duration| created_at + duration);
+        let idle = self.idle.map(|duration| last_activity + duration);
+
+        match (absolute, idle) {
+            (Some(absolute), Some(idle)) => Some(absolute.min(idle)),
+            (absolute, idle) => absolute.or(idle),
+        }
+    }
+}
@@ -1,10 +1,16 @@
 use rocket::serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::fs::metadata::Metadata;
+use crate::fs::{
+    expiration::Expiration, metadata::Metadata, namespace::NAMESPACE_SEPARATOR,
+};
 use std::{
+    collections::HashMap,
     io::{Error, ErrorKind, Result},
     marker::PhantomData,
+    net::IpAddr,
+    path::Path,
+    time::{Duration, SystemTime},
 };
 
 /// Indicates the [`FileInfo`] `Created` state.
@@ -27,6 +33,69 @@ pub struct Completed;
 #[derive(Default, Debug)]
 pub struct Terminated;
 
+/// Statistics about the `PATCH` requests that wrote an upload.
+///
+/// They are accumulated as chunks arrive and persisted along with the file information,
+/// so they survive restarts.
+#[derive(
+    Default, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(crate = "rocket::serde")]
+pub struct UploadStats {
+    patch_requests: u64,
+    received_bytes: u64,
+    peak_chunk_size: u64,
+}
+
+impl UploadStats {
+    /// Returns the number of `PATCH` requests that wrote a chunk.
+    pub fn patch_requests(&self) -> u64 {
+        self.patch_requests
+    }
+
+    /// Returns the largest chunk written by a single request.
+    pub fn peak_chunk_size(&self) -> u64 {
+        self.peak_chunk_size
+    }
+
+    /// Returns the average chunk size, `0` before the first chunk.
+    pub fn average_chunk_size(&self) -> u64 {
+        self.received_bytes
+            .checked_div(self.patch_requests)
+            .unwrap_or_default()
+    }
+
+    fn record_chunk(&mut self, size: u64) {
+        self.patch_requests += 1;
+        self.received_bytes += size;
+        self.peak_chunk_size = self.peak_chunk_size.max(size);
+    }
+}
+
+/// Represents the progress of the scan gating the download of a completed upload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum ScanState {
+    /// The scan is running, the file is not released yet.
+    Scanning,
+    /// The file has been found clean and released.
+    Clean,
+    /// The file has been found infected and its data deleted.
+    Infected,
+}
+
+impl ScanState {
+    /// Returns the state as reported in the `X-Upload-Scan` header.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Scanning => "scanning",
+            Self::Clean => "clean",
+            Self::Infected => "infected",
+        }
+    }
+}
+
 /// A struct representing a file and its metadata during various stages of processing.
 ///
 /// The struct has four possible states: [`Built`], [`Created`], [`Completed`] and [`Terminated`].
@@ -42,6 +111,25 @@ pub struct FileInfo<State = Building> {
     length: u64,
     offset: u64,
     metadata: Option<Metadata>,
+    created_at: Option<SystemTime>,
+    expires_at: Option<SystemTime>,
+    last_activity: Option<SystemTime>,
+    completed_at: Option<SystemTime>,
+    content_type: Option<String>,
+    remote_addr: Option<IpAddr>,
+    request_headers: Option<HashMap<String, String>>,
+    terminated_at: Option<SystemTime>,
+    chunk_size: Option<u64>,
+    checksum_algorithm: Option<String>,
+    key_id: Option<String>,
+    key_check: Option<String>,
+    #[serde(default)]
+    stats: UploadStats,
+    #[serde(default)]
+    scan_state: Option<ScanState>,
+    /// Indicates if the stored `file_name` is relative to the storage root.
+    #[serde(default)]
+    root_relative: bool,
 
     #[serde(skip)]
     state: PhantomData<State>,
@@ -59,12 +147,179 @@ impl<State> FileInfo<State> {
     pub fn metadata(&self) -> &Option<Metadata> {
         &self.metadata
     }
+
+    /// Returns when the file was created.
+    pub fn created_at(&self) -> &Option<SystemTime> {
+        &self.created_at
+    }
+
+    /// Returns when the file expires, if an expiration has been configured.
+    ///
+    /// The value is persisted along with the file information, so external schedulers
+    /// can rely on it to drive cleanup. It is renewed on every `PATCH` when an idle
+    /// expiration is configured.
+    pub fn expires_at(&self) -> &Option<SystemTime> {
+        &self.expires_at
+    }
+
+    /// Returns when the last `PATCH` request wrote to the file, if any did.
+    pub fn last_activity(&self) -> &Option<SystemTime> {
+        &self.last_activity
+    }
+
+    /// Computes when the file expires under the given windows.
+    ///
+    /// The idle window is counted from the persisted [`FileInfo::last_activity()`], or the
+    /// creation without any activity, so the result survives restarts and window changes.
+    /// Files without creation time keep their stored [`FileInfo::expires_at()`].
+    pub fn expires_with(&self, expiration: &Expiration) -> Option<SystemTime> {
+        let Some(created_at) = self.created_at else {
+            return self.expires_at;
+        };
+
+        let last_activity = self.last_activity.unwrap_or(created_at);
+
+        expiration.expires_at(created_at, last_activity)
+    }
+
+    /// Returns when the file upload was completed.
+    pub fn completed_at(&self) -> &Option<SystemTime> {
+        &self.completed_at
+    }
+
+    /// Returns the media type of the file.
+    ///
+    /// The type detected from the file content is preferred, when detection is enabled,
+    /// otherwise the client declared `filetype` metadata is used.
+    pub fn content_type(&self) -> Option<String> {
+        if let Some(content_type) = &self.content_type {
+            return Some(content_type.to_owned());
+        }
+
+        self.metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get_raw("filetype").ok())
+            .and_then(|raw| String::from_utf8(raw).ok())
+    }
+
+    /// Returns the media type detected from the file content, if detection is enabled.
+    #[cfg(feature = "download")]
+    pub(crate) fn detected_content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Returns the namespace the upload was created in, if any.
+    pub fn namespace(&self) -> Option<&str> {
+        self.id
+            .split_once(NAMESPACE_SEPARATOR)
+            .map(|(namespace, _)| namespace)
+    }
+
+    /// Returns the address of the client that created the upload.
+    pub fn remote_addr(&self) -> &Option<IpAddr> {
+        &self.remote_addr
+    }
+
+    /// Returns the request headers recorded on creation, see
+    /// [`Meteoritus::with_captured_headers()`](crate::Meteoritus::with_captured_headers).
+    pub fn request_headers(&self) -> &Option<HashMap<String, String>> {
+        &self.request_headers
+    }
+
+    /// Returns when the file was soft terminated, its data is kept until purged.
+    pub fn terminated_at(&self) -> &Option<SystemTime> {
+        &self.terminated_at
+    }
+
+    /// Returns the chunk size hinted by the client on creation, once bounded.
+    pub fn chunk_size(&self) -> &Option<u64> {
+        &self.chunk_size
+    }
+
+    /// Returns the `Upload-Checksum` algorithm declared by the client on creation.
+    pub fn checksum_algorithm(&self) -> &Option<String> {
+        &self.checksum_algorithm
+    }
+
+    /// Returns the id of the key encrypting the file, if it is encrypted.
+    pub fn key_id(&self) -> &Option<String> {
+        &self.key_id
+    }
+
+    /// Returns the state of the scan of the completed file, when a scanner is configured.
+    pub fn scan_state(&self) -> &Option<ScanState> {
+        &self.scan_state
+    }
+
+    /// Returns the statistics of the requests that wrote the file.
+    pub fn stats(&self) -> &UploadStats {
+        &self.stats
+    }
+
+    /// Returns the time elapsed from the creation to the completion of the upload.
+    pub fn upload_duration(&self) -> Option<Duration> {
+        let created_at = self.created_at?;
+        self.completed_at?.duration_since(created_at).ok()
+    }
+
+    pub(crate) fn key_check(&self) -> &Option<String> {
+        &self.key_check
+    }
+
+    pub(super) fn set_scan_state(&mut self, state: ScanState) {
+        self.scan_state = Some(state);
+    }
+
+    pub(super) fn set_terminated_at(&mut self, at: Option<SystemTime>) {
+        self.terminated_at = at;
+    }
+
+    pub(super) fn relocate(&mut self, file_name: &str) {
+        self.file_name = file_name.to_string();
+    }
+
+    /// Expresses the file location relative to `root`, if it is stored below it.
+    ///
+    /// Locations elsewhere, like a relative completed path, are kept as they are.
+    pub(super) fn strip_root(&mut self, root: &Path) {
+        let relative = Path::new(&self.file_name).strip_prefix(root);
+
+        if let Some(relative) = relative.ok().and_then(|path| path.to_str()) {
+            self.file_name = relative.to_string();
+            self.root_relative = true;
+        }
+    }
+
+    /// Resolves a file location stripped by [`FileInfo::strip_root()`] against `root`.
+    ///
+    /// Other locations, including those stored before locations were made relative, are
+    /// kept as they are.
+    pub(super) fn join_root(&mut self, root: &Path) {
+        if !self.root_relative {
+            return;
+        }
+
+        if let Some(path) = root.join(&self.file_name).to_str() {
+            self.file_name = path.to_string();
+            self.root_relative = false;
+        }
+    }
+
+    /// Sets when the file expires, overriding the configured expiration.
+    ///
+    /// The value is persisted along with the file information and reported by
+    /// [`FileInfo::expires_at()`], so external schedulers can drive cleanup.
+    pub fn with_expiry(mut self, expires_at: SystemTime) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
 }
 
 impl FileInfo<Building> {
     pub(super) fn new(length: u64) -> Self {
         Self {
             length,
+            created_at: Some(SystemTime::now()),
             ..Default::default()
         }
     }
@@ -90,6 +345,21 @@ impl FileInfo<Building> {
             length: self.length,
             offset: self.offset,
             metadata: self.metadata,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            last_activity: self.last_activity,
+            completed_at: self.completed_at,
+            content_type: self.content_type,
+            remote_addr: self.remote_addr,
+            request_headers: self.request_headers,
+            terminated_at: self.terminated_at,
+            chunk_size: self.chunk_size,
+            checksum_algorithm: self.checksum_algorithm,
+            key_id: self.key_id,
+            key_check: self.key_check,
+            stats: self.stats,
+            scan_state: self.scan_state,
+            root_relative: self.root_relative,
             file_name: self.file_name,
         }
         /* Consider Update to: #![feature(type_changing_struct_update)]
@@ -101,6 +371,41 @@ impl FileInfo<Building> {
 }
 
 impl FileInfo<Built> {
+    pub(crate) fn with_remote_addr(mut self, remote_addr: IpAddr) -> Self {
+        self.remote_addr = Some(remote_addr);
+        self
+    }
+
+    pub(crate) fn with_request_headers(
+        mut self,
+        headers: HashMap<String, String>,
+    ) -> Self {
+        self.request_headers = Some(headers);
+        self
+    }
+
+    pub(crate) fn with_key(mut self, key_id: &str, key_check: String) -> Self {
+        self.key_id = Some(key_id.to_string());
+        self.key_check = Some(key_check);
+        self
+    }
+
+    /// Prefixes the id with the namespace, which must be path-safe.
+    pub(crate) fn with_namespace(mut self, namespace: &str) -> Self {
+        self.id = format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, self.id);
+        self
+    }
+
+    pub(crate) fn with_chunk_size(mut self, chunk_size: u64) -> Self {
+        self.chunk_size = Some(chunk_size);
+        self
+    }
+
+    pub(crate) fn with_checksum_algorithm(mut self, algorithm: &str) -> Self {
+        self.checksum_algorithm = Some(algorithm.to_string());
+        self
+    }
+
     pub(super) fn mark_as_created(self, file_name: &str) -> FileInfo<Created> {
         FileInfo::<Created> {
             file_name: file_name.to_string(),
@@ -109,6 +414,21 @@ impl FileInfo<Built> {
             length: self.length,
             offset: self.offset,
             metadata: self.metadata,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            last_activity: self.last_activity,
+            completed_at: self.completed_at,
+            content_type: self.content_type,
+            remote_addr: self.remote_addr,
+            request_headers: self.request_headers,
+            terminated_at: self.terminated_at,
+            chunk_size: self.chunk_size,
+            checksum_algorithm: self.checksum_algorithm,
+            key_id: self.key_id,
+            key_check: self.key_check,
+            stats: self.stats,
+            scan_state: self.scan_state,
+            root_relative: self.root_relative,
         }
         /* Consider Update to: #![feature(type_changing_struct_update)]
         FileInfo::<Created> {
@@ -129,11 +449,32 @@ impl FileInfo<Created> {
             return Err(Error::from(ErrorKind::OutOfMemory));
         }
 
+        self.stats.record_chunk(offset.saturating_sub(self.offset));
         self.offset = offset;
+        self.last_activity = Some(SystemTime::now());
+
+        if self.offset == self.length {
+            self.completed_at = Some(SystemTime::now());
+        }
 
         Ok(())
     }
 
+    #[cfg(feature = "sniff")]
+    pub(super) fn set_content_type(&mut self, content_type: &str) {
+        self.content_type = Some(content_type.to_string());
+    }
+
+    /// Returns where the upload data is located while it is still in progress.
+    pub(super) fn location(&self) -> &str {
+        &self.file_name
+    }
+
+    /// Indicates if all bytes of the file have been received.
+    pub fn is_completed(&self) -> bool {
+        self.offset == self.length
+    }
+
     pub(crate) fn check_completion(self) -> Option<FileInfo<Completed>> {
         if self.offset != self.length {
             return None;
@@ -145,6 +486,21 @@ impl FileInfo<Created> {
             length: self.length,
             offset: self.offset,
             metadata: self.metadata,
+            created_at: self.created_at,
+            expires_at: self.expires_at,
+            last_activity: self.last_activity,
+            completed_at: self.completed_at,
+            content_type: self.content_type,
+            remote_addr: self.remote_addr,
+            request_headers: self.request_headers,
+            terminated_at: self.terminated_at,
+            chunk_size: self.chunk_size,
+            checksum_algorithm: self.checksum_algorithm,
+            key_id: self.key_id,
+            key_check: self.key_check,
+            stats: self.stats,
+            scan_state: self.scan_state,
+            root_relative: self.root_relative,
             file_name: self.file_name,
         })
         /* Consider Update to: #![feature(type_changing_struct_update)]
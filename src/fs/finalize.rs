@@ -0,0 +1,116 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::Path,
+};
+
+/// Size of the buffer used when the file has to be copied across filesystems.
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Moves a file to its final destination, never replacing an existing file.
+///
+/// The file is linked to the destination first, which fails atomically with
+/// [`io::ErrorKind::AlreadyExists`] if it already exists. When linking is not possible,
+/// e.g. because the destination is on another filesystem, the file is streamed through a
+/// bounded buffer and `progress` is called with the amount of bytes copied so far. The
+/// source is only removed after the copy has been fully written and synced, a failed copy
+/// leaves it intact.
+pub(crate) fn move_file(
+    from: &Path,
+    to: &Path,
+    progress: &mut dyn FnMut(u64),
+) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    match fs::hard_link(from, to) {
+        Ok(()) => return fs::remove_file(from),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Err(e),
+        Err(_) => {}
+    }
+
+    let destination = File::options().write(true).create_new(true).open(to)?;
+
+    if let Err(e) = copy_file(from, destination, progress) {
+        let _ = fs::remove_file(to);
+        return Err(e);
+    }
+
+    fs::remove_file(from)
+}
+
+/// Makes a file available at another path, leaving the original in place.
+///
+/// A hard link is created, falling back to a copy when linking is not possible, e.g.
+/// because the destination is on another filesystem. The copy is written to a hidden
+/// name next to the destination and only linked to the destination once complete, so
+/// a partial copy is never seen there. An existing destination is never replaced.
+pub(crate) fn link_file(from: &Path, to: &Path) -> io::Result<()> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    match fs::hard_link(from, to) {
+        Ok(()) => return Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => return Err(e),
+        Err(_) => {}
+    }
+
+    let Some(name) = to.file_name().and_then(|name| name.to_str()) else {
+        return Err(io::ErrorKind::InvalidInput.into());
+    };
+
+    let partial = to.with_file_name(format!(".{}.partial", name));
+    let destination = File::options()
+        .write(true)
+        .create_new(true)
+        .open(&partial)?;
+
+    let linked = copy_file(from, destination, &mut |_| {})
+        .and_then(|_| fs::hard_link(&partial, to));
+
+    let _ = fs::remove_file(&partial);
+
+    linked
+}
+
+fn copy_file(
+    from: &Path,
+    mut destination: File,
+    progress: &mut dyn FnMut(u64),
+) -> io::Result<()> {
+    let mut source = File::open(from)?;
+
+    let mut buf = vec![0; COPY_BUFFER_SIZE];
+    let mut copied = 0;
+
+    loop {
+        let read = match source.read(&mut buf) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+
+        destination.write_all(&buf[..read])?;
+
+        copied += read as u64;
+        progress(copied);
+    }
+
+    destination.sync_all()
+}
+
+/// Syncs a directory, so the renames and creations of its entries survive a crash.
+///
+/// Directories can only be opened on Unix platforms, elsewhere it is a no-op.
+pub(crate) fn sync_dir(dir: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    File::open(dir)?.sync_all()?;
+
+    #[cfg(not(unix))]
+    let _ = dir;
+
+    Ok(())
+}
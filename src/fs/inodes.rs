@@ -0,0 +1,59 @@
+use std::path::Path;
+
+/// A source of inode statistics, like the operating system or a remote filesystem.
+///
+/// # Example
+///
+///   ```rust,no_run
+///   use std::path::Path;
+///   use meteoritus::InodeStats;
+///
+///   struct FixedInodes(u64);
+///
+///   impl InodeStats for FixedInodes {
+///       fn available_inodes(&self, _path: &Path) -> Option<u64> {
+///           Some(self.0)
+///       }
+///   }
+///   ```
+pub trait InodeStats: Send + Sync {
+    /// Returns the number of inodes left on the filesystem holding `path`, if known.
+    fn available_inodes(&self, path: &Path) -> Option<u64>;
+}
+
+/// The [`InodeStats`] of the local filesystems, read with `statvfs` on Unix platforms.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FilesystemInodes;
+
+impl InodeStats for FilesystemInodes {
+    fn available_inodes(&self, path: &Path) -> Option<u64> {
+        available_inodes(path)
+    }
+}
+
+/// Returns the number of inodes left to unprivileged users on the filesystem holding `path`.
+///
+/// Returns `None` when the path can't be inspected, or on platforms without inode stats.
+#[cfg(unix)]
+fn available_inodes(path: &Path) -> Option<u64> {
+    use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stats = MaybeUninit::<libc::statvfs>::uninit();
+
+    /* Safety: the path is nul terminated and statvfs only writes into stats */
+    let stats = unsafe {
+        if libc::statvfs(path.as_ptr(), stats.as_mut_ptr()) != 0 {
+            return None;
+        }
+
+        stats.assume_init()
+    };
+
+    Some(stats.f_favail as u64)
+}
+
+#[cfg(not(unix))]
+fn available_inodes(_path: &Path) -> Option<u64> {
+    None
+}
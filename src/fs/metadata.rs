@@ -1,11 +1,16 @@
-use base64::Engine as _;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
 use rocket::serde::{Deserialize, Serialize};
-use std::{collections::HashMap, error::Error, fmt::Display};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::Display,
+    time::{Duration, Instant},
+};
 
 /// A struct representing the metadata associated with an uploaded file.
 ///
 /// Metadata is a wrapper around a `HashMap` that holds metadata for a tus upload.
-#[derive(Default, Debug, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 #[serde(crate = "rocket::serde")]
 pub struct Metadata(HashMap<String, String>);
 
@@ -18,6 +23,16 @@ pub enum MetadataError {
     DecodeError(String),
     /// An error indicating the metadata string was invalid.
     InvalidMetadataFormat,
+    /// An error indicating a decoded value exceeds the configured maximum length.
+    ValueTooLarge,
+    /// An error indicating the metadata string exceeds the configured processing budget.
+    BudgetExceeded,
+    /// An error indicating a key outside the configured allowlist was used.
+    KeyNotAllowed,
+    /// An error indicating a key exceeds the configured maximum length.
+    KeyTooLong,
+    /// An error indicating two keys are the same once canonicalized.
+    KeyCollision,
 }
 
 impl Error for MetadataError {}
@@ -28,6 +43,144 @@ impl Display for MetadataError {
     }
 }
 
+/// What to do when two metadata keys are the same once canonicalized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCollision {
+    /// Fails with [`MetadataError::KeyCollision`].
+    Reject,
+    /// Keeps the value of the last key.
+    LastWins,
+}
+
+/// Limits applied while parsing the metadata of an upload.
+#[derive(Debug, Clone, Copy)]
+pub struct MetadataOptions {
+    max_key_length: usize,
+    max_value_length: usize,
+    max_length: Option<usize>,
+    decode_budget: Option<Duration>,
+    allowed_keys: Option<&'static [&'static str]>,
+    drop_disallowed_keys: bool,
+    canonical_keys: Option<KeyCollision>,
+}
+
+impl Default for MetadataOptions {
+    fn default() -> Self {
+        Self {
+            max_key_length: 256,
+            max_value_length: 4096,
+            max_length: None,
+            decode_budget: None,
+            allowed_keys: None,
+            drop_disallowed_keys: false,
+            canonical_keys: None,
+        }
+    }
+}
+
+impl MetadataOptions {
+    /// Creates the default options, it allows keys up to 256 bytes and decoded values up to 4 KiB.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Sets the maximum length in bytes of any single metadata key.
+    pub fn with_max_key_length(mut self, max_key_length: usize) -> Self {
+        self.max_key_length = max_key_length;
+        self
+    }
+
+    /// Returns the maximum length in bytes of any single metadata key.
+    pub fn max_key_length(&self) -> usize {
+        self.max_key_length
+    }
+
+    /// Sets the maximum decoded length in bytes of any single metadata value.
+    pub fn with_max_value_length(mut self, max_value_length: usize) -> Self {
+        self.max_value_length = max_value_length;
+        self
+    }
+
+    /// Returns the maximum decoded length in bytes of any single metadata value.
+    pub fn max_value_length(&self) -> usize {
+        self.max_value_length
+    }
+
+    /// Aborts parsing metadata strings longer than `max_length` bytes, disabled by default.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Returns the maximum length of a metadata string.
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    /// Aborts parsing metadata once it took longer than `budget`, disabled by default.
+    pub fn with_decode_budget(mut self, budget: Duration) -> Self {
+        self.decode_budget = Some(budget);
+        self
+    }
+
+    /// Returns the maximum time spent parsing a metadata string.
+    pub fn decode_budget(&self) -> Option<Duration> {
+        self.decode_budget
+    }
+
+    /// Only accepts the given metadata keys, others fail with [`MetadataError::KeyNotAllowed`].
+    pub fn with_allowed_keys(mut self, keys: &'static [&'static str]) -> Self {
+        self.allowed_keys = Some(keys);
+        self
+    }
+
+    /// Silently drops keys outside the allowlist instead of failing.
+    pub fn drop_disallowed_keys(mut self) -> Self {
+        self.drop_disallowed_keys = true;
+        self
+    }
+
+    /// Returns the allowed metadata keys, if any.
+    pub fn allowed_keys(&self) -> Option<&'static [&'static str]> {
+        self.allowed_keys
+    }
+
+    /// Indicates if keys outside the allowlist are dropped instead of failing.
+    pub fn drops_disallowed_keys(&self) -> bool {
+        self.drop_disallowed_keys
+    }
+
+    /// Trims and lowercases keys before they are checked and stored, disabled by default.
+    ///
+    /// Keys differing only by case, such as `Filename` and `filename`, then collide,
+    /// which is handled according to `collision`.
+    pub fn with_canonical_keys(mut self, collision: KeyCollision) -> Self {
+        self.canonical_keys = Some(collision);
+        self
+    }
+
+    /// Returns how colliding canonical keys are handled, if keys are canonicalized.
+    pub fn canonical_keys(&self) -> Option<KeyCollision> {
+        self.canonical_keys
+    }
+
+    /// Options without length limits, as applied by [`Metadata::try_from()`].
+    fn unbounded() -> Self {
+        Self {
+            max_key_length: usize::MAX,
+            max_value_length: usize::MAX,
+            ..Default::default()
+        }
+    }
+
+    fn is_allowed(&self, key: &str) -> bool {
+        match self.allowed_keys {
+            Some(keys) => keys.contains(&key),
+            None => true,
+        }
+    }
+}
+
 impl Metadata {
     /// Creates a new empty metadata.
     pub fn new() -> Self {
@@ -58,49 +211,90 @@ impl Metadata {
             None => return Err(MetadataError::InvalidKey),
         };
 
-        match base64::engine::general_purpose::STANDARD.decode(value) {
+        match STANDARD.decode(value) {
             Ok(decoded) => Ok(decoded),
             Err(e) => Err(MetadataError::DecodeError(e.to_string())),
         }
     }
 
-    /// Returns the number of elements in the metadata.
-    pub fn len(&self) -> usize {
-        self.0.len()
-    }
-}
-
-impl TryFrom<&str> for Metadata {
-    type Error = MetadataError;
-
-    /// Attempts to parse a metadata string into a [`Metadata`] instance.
+    /// Parses a metadata string into a [`Metadata`] instance, applying the given limits.
     ///
-    /// The given string should follow the tus [`Upload-Metadata`](https://tus.io/protocols/resumable-upload.html#upload-metadata) definition .
-    ///
-    /// # Examples
+    /// # Example
     ///
     /// ```
-    /// use meteoritus::{Metadata, MetadataError};
+    /// use meteoritus::{KeyCollision, Metadata, MetadataError, MetadataOptions};
     ///
-    ///let metadata = Metadata::try_from("relativePath bnVsbA==, filetype dmlkZW8vbXA0,is_confidential").unwrap();
-    ///assert_eq!(metadata.len(), 3);
+    /// let options = MetadataOptions::new().with_max_value_length(10);
     ///
-    ///let metadata = Metadata::try_from("");
-    ///assert!(metadata.is_err());
-    ///assert_eq!(metadata.err(), Some(MetadataError::InvalidMetadataFormat));
+    /// let metadata = Metadata::parse("filetype dmlkZW8vbXA0, filename bXlfdmlkZW8ubXA0", &options);
+    /// assert_eq!(metadata.err(), Some(MetadataError::ValueTooLarge));
     ///
-    ///let metadata = Metadata::try_from("foobar, fas bars foo bar, ");
-    ///assert!(metadata.is_err());
-    ///assert_eq!(metadata.err(), Some(MetadataError::InvalidMetadataFormat));
+    /// let metadata = Metadata::parse("filetype dmlkZW8vbXA0, is_confidential", &options);
+    /// assert_eq!(metadata.map(|m| m.len()), Ok(2));
+    ///
+    /// let options = MetadataOptions::new().with_max_key_length(8);
+    ///
+    /// let metadata = Metadata::parse("filetype dmlkZW8vbXA0", &options);
+    /// assert_eq!(metadata.map(|m| m.len()), Ok(1));
+    ///
+    /// let metadata = Metadata::parse("a_very_long_key dmlkZW8vbXA0", &options);
+    /// assert_eq!(metadata.err(), Some(MetadataError::KeyTooLong));
+    ///
+    /// let long_key = format!("{} dmlkZW8vbXA0", "k".repeat(300));
+    ///
+    /// let metadata = Metadata::parse(&long_key, &MetadataOptions::new());
+    /// assert_eq!(metadata.err(), Some(MetadataError::KeyTooLong));
+    ///
+    /// let options = MetadataOptions::new().with_max_length(16);
+    ///
+    /// let metadata = Metadata::parse("filetype dmlkZW8vbXA0", &options);
+    /// assert_eq!(metadata.err(), Some(MetadataError::BudgetExceeded));
+    ///
+    /// let options = MetadataOptions::new().with_allowed_keys(&["filetype"]);
+    ///
+    /// let metadata = Metadata::parse("filetype dmlkZW8vbXA0, filename bXlfdmlkZW8ubXA0", &options);
+    /// assert_eq!(metadata.err(), Some(MetadataError::KeyNotAllowed));
+    ///
+    /// let metadata = Metadata::parse("filetype dmlkZW8vbXA0, filename bXlfdmlkZW8ubXA0", &options.drop_disallowed_keys());
+    /// assert_eq!(metadata.map(|m| m.len()), Ok(1));
+    ///
+    /// let options = MetadataOptions::new().with_canonical_keys(KeyCollision::LastWins);
+    ///
+    /// let metadata = Metadata::parse("FileType dmlkZW8vbXA0, filetype aW1hZ2UvcG5n", &options).unwrap();
+    /// assert_eq!(metadata.get_raw("filetype"), Ok(b"image/png".to_vec()));
+    ///
+    /// let options = MetadataOptions::new().with_canonical_keys(KeyCollision::Reject);
+    ///
+    /// let metadata = Metadata::parse("FileType dmlkZW8vbXA0, filetype aW1hZ2UvcG5n", &options);
+    /// assert_eq!(metadata.err(), Some(MetadataError::KeyCollision));
     /// ```
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
+    pub fn parse(
+        value: &str,
+        options: &MetadataOptions,
+    ) -> Result<Self, MetadataError> {
         if value.is_empty() {
             return Err(MetadataError::InvalidMetadataFormat);
         }
 
+        if options.max_length().is_some_and(|max| value.len() > max) {
+            return Err(MetadataError::BudgetExceeded);
+        }
+
+        let started_at = Instant::now();
+        let over_budget = || {
+            options
+                .decode_budget()
+                .is_some_and(|budget| started_at.elapsed() > budget)
+        };
+
         let mut metadata = Metadata::new();
+        let mut keys = HashSet::new();
 
         for pair in value.split(',') {
+            if over_budget() {
+                return Err(MetadataError::BudgetExceeded);
+            }
+
             let pair = pair.trim();
 
             if pair.is_empty() {
@@ -113,20 +307,140 @@ impl TryFrom<&str> for Metadata {
                 return Err(MetadataError::InvalidMetadataFormat);
             }
 
-            if parts[0].is_empty() {
+            if parts[0].is_empty() || parts[0].contains(char::is_control) {
                 return Err(MetadataError::InvalidKey);
             }
 
-            if let (Some(key), value) = (parts.get(0), parts.get(1)) {
+            if parts[0].len() > options.max_key_length() {
+                return Err(MetadataError::KeyTooLong);
+            }
+
+            /* Keys must be unique as sent, before any canonicalization */
+            if !keys.insert(parts[0]) {
+                return Err(MetadataError::InvalidMetadataFormat);
+            }
+
+            if let (Some(key), value) = (parts.first(), parts.get(1)) {
+                let key = match options.canonical_keys() {
+                    Some(_) => key.trim().to_lowercase(),
+                    None => key.to_string(),
+                };
+
                 let value = match value {
                     Some(v) => v.to_string(),
                     None => String::default(),
                 };
 
-                metadata.0.insert(key.to_string(), value);
+                /* Oversized values are rejected before spending time decoding them */
+                if decoded_length(&value) > options.max_value_length() {
+                    return Err(MetadataError::ValueTooLarge);
+                }
+
+                /* Values must be Base64 encoded */
+                if let Err(e) = STANDARD.decode(&value) {
+                    return Err(MetadataError::DecodeError(e.to_string()));
+                }
+
+                /* A single huge value may exhaust the budget on its own */
+                if over_budget() {
+                    return Err(MetadataError::BudgetExceeded);
+                }
+
+                if !options.is_allowed(&key) {
+                    match options.drops_disallowed_keys() {
+                        true => continue,
+                        false => return Err(MetadataError::KeyNotAllowed),
+                    }
+                }
+
+                if metadata.0.insert(key, value).is_some()
+                    && options.canonical_keys() == Some(KeyCollision::Reject)
+                {
+                    return Err(MetadataError::KeyCollision);
+                }
             }
         }
 
         Ok(metadata)
     }
+
+    /// Returns the canonical `Upload-Metadata` string of this metadata.
+    ///
+    /// Pairs are sorted by key and joined by `,`, a key and its Base64 value are separated by
+    /// a single space and keys without value are written alone. Clients can compute the same
+    /// string to compare metadata, regardless of the order they were sent in.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use meteoritus::Metadata;
+    ///
+    /// let metadata = Metadata::try_from("filetype dmlkZW8vbXA0,is_confidential, filename bXlfdmlkZW8ubXA0").unwrap();
+    /// assert_eq!(metadata.canonical(), "filename bXlfdmlkZW8ubXA0,filetype dmlkZW8vbXA0,is_confidential");
+    /// ```
+    pub fn canonical(&self) -> String {
+        let mut pairs: Vec<(&String, &String)> = self.0.iter().collect();
+        pairs.sort();
+
+        pairs
+            .into_iter()
+            .map(|(key, value)| match value.is_empty() {
+                true => key.to_string(),
+                false => format!("{} {}", key, value),
+            })
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    /// Returns the number of elements in the metadata.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Returns the length of a padded Base64 value once decoded, without decoding it.
+fn decoded_length(value: &str) -> usize {
+    let padding = value.bytes().rev().take_while(|&b| b == b'=').count();
+
+    (value.len() / 4 * 3).saturating_sub(padding)
+}
+
+impl TryFrom<&str> for Metadata {
+    type Error = MetadataError;
+
+    /// Attempts to parse a metadata string into a [`Metadata`] instance.
+    ///
+    /// The given string should follow the tus [`Upload-Metadata`](https://tus.io/protocols/resumable-upload.html#upload-metadata) definition .
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use meteoritus::{Metadata, MetadataError};
+    ///
+    ///let metadata = Metadata::try_from("relativePath bnVsbA==, filetype dmlkZW8vbXA0,is_confidential").unwrap();
+    ///assert_eq!(metadata.len(), 3);
+    ///
+    ///let metadata = Metadata::try_from("");
+    ///assert!(metadata.is_err());
+    ///assert_eq!(metadata.err(), Some(MetadataError::InvalidMetadataFormat));
+    ///
+    ///let metadata = Metadata::try_from("foobar, fas bars foo bar, ");
+    ///assert!(metadata.is_err());
+    ///assert_eq!(metadata.err(), Some(MetadataError::InvalidMetadataFormat));
+    ///
+    ///let metadata = Metadata::try_from("file\0name bXlfdmlkZW8ubXA0");
+    ///assert_eq!(metadata.err(), Some(MetadataError::InvalidKey));
+    ///
+    ///let metadata = Metadata::try_from("filename bXlfdmlkZW8ubXA0, filename dmlkZW8vbXA0");
+    ///assert_eq!(metadata.err(), Some(MetadataError::InvalidMetadataFormat));
+    ///
+    ///let metadata = Metadata::try_from("filename not*base64");
+    ///assert!(matches!(metadata.err(), Some(MetadataError::DecodeError(_))));
+    ///
+    ///let metadata = Metadata::try_from(format!("{} dmlkZW8vbXA0", "k".repeat(300)).as_str());
+    ///assert_eq!(metadata.map(|m| m.len()), Ok(1));
+    /// ```
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        Metadata::parse(value, &MetadataOptions::unbounded())
+    }
 }
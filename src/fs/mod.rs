@@ -1,7 +1,32 @@
+mod checksum;
+mod encryption;
+mod expiration;
 mod file_info;
+mod finalize;
+mod inodes;
 mod metadata;
+mod namespace;
+mod retry;
+#[cfg(feature = "ring")]
+mod ring;
+#[cfg(feature = "sniff")]
+mod sniff;
 mod vault;
 
-pub use file_info::{Built, Completed, Created, FileInfo, Terminated};
-pub use metadata::{Metadata, MetadataError};
-pub use vault::{LocalVault, PatchOption, Vault};
+pub(crate) use checksum::{
+    checksum_digest, sha256_digest, sha256_hex, to_hex, CHECKSUM_ALGORITHMS,
+};
+pub(crate) use encryption::UploadCipher;
+pub use encryption::{InMemoryKeyProvider, KeyProvider};
+pub use expiration::Expiration;
+pub use file_info::{
+    Built, Completed, Created, FileInfo, ScanState, Terminated, UploadStats,
+};
+pub(crate) use finalize::link_file;
+pub use inodes::{FilesystemInodes, InodeStats};
+pub use metadata::{KeyCollision, Metadata, MetadataError, MetadataOptions};
+pub(crate) use namespace::is_valid_namespace;
+pub(crate) use retry::RetryVault;
+#[cfg(feature = "sniff")]
+pub(crate) use sniff::sniff_content_type;
+pub use vault::{LocalVault, PatchOption, Vault, VaultError};
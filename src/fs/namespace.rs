@@ -0,0 +1,17 @@
+/// Separates the namespace from the rest of a namespaced upload id, like `acme.<uuid>`.
+pub(crate) const NAMESPACE_SEPARATOR: char = '.';
+
+/// Maximum length in bytes of a namespace.
+const MAX_NAMESPACE_LENGTH: usize = 64;
+
+/// Indicates if `namespace` can be used as a single directory name and id prefix.
+///
+/// Only ASCII alphanumerics, `-` and `_` are allowed, which rules out path separators,
+/// `.` and `..` components as well as the id separator itself.
+pub(crate) fn is_valid_namespace(namespace: &str) -> bool {
+    !namespace.is_empty()
+        && namespace.len() <= MAX_NAMESPACE_LENGTH
+        && namespace
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
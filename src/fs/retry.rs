@@ -0,0 +1,273 @@
+use rocket::tokio::{
+    io::AsyncRead,
+    runtime::{Handle, RuntimeFlavor},
+    task,
+};
+use std::{path::Path, pin::Pin, sync::Arc, thread, time::Duration};
+
+use super::{
+    expiration::Expiration,
+    file_info::{Built, Completed, Created, FileInfo, ScanState, Terminated},
+    metadata::Metadata,
+    vault::{PatchOption, Vault, VaultError},
+};
+
+/// A [`Vault`] retrying the operations of another one on [`VaultError::Transient`] errors.
+///
+/// Every retry waits twice as long as the previous one, starting at `backoff`. The error
+/// is returned once `attempts` retries have failed. Storage operations are synchronous,
+/// so the wait blocks the calling thread, without stalling the runtime it runs on.
+pub(crate) struct RetryVault {
+    inner: Arc<dyn Vault>,
+    attempts: u32,
+    backoff: Duration,
+}
+
+impl RetryVault {
+    pub(crate) fn new(
+        inner: Arc<dyn Vault>,
+        attempts: u32,
+        backoff: Duration,
+    ) -> Self {
+        Self {
+            inner,
+            attempts,
+            backoff,
+        }
+    }
+
+    fn retry<T>(
+        &self,
+        operation: &str,
+        mut f: impl FnMut() -> Result<T, VaultError>,
+    ) -> Result<T, VaultError> {
+        let mut attempt = 0;
+
+        loop {
+            match f() {
+                Err(VaultError::Transient(e)) if attempt < self.attempts => {
+                    let delay = self.backoff * 2u32.saturating_pow(attempt);
+
+                    warn!(
+                        "Transient storage error on {}: {}, retrying in {:?}",
+                        operation, e, delay
+                    );
+
+                    backoff(delay);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Waits for `delay`, handing the other tasks of a runtime worker over to another worker.
+///
+/// Requests are served from the workers of a multi-threaded runtime, a plain sleep would
+/// stall every request queued on the same worker.
+fn backoff(delay: Duration) {
+    let on_worker = Handle::try_current().is_ok_and(|handle| {
+        handle.runtime_flavor() == RuntimeFlavor::MultiThread
+    });
+
+    match on_worker {
+        true => task::block_in_place(|| thread::sleep(delay)),
+        false => thread::sleep(delay),
+    }
+}
+
+impl Vault for RetryVault {
+    fn build_file(
+        &self,
+        length: u64,
+        metadata: Option<Metadata>,
+        expiration: &Expiration,
+    ) -> Result<FileInfo<Built>, VaultError> {
+        self.retry("build", || {
+            self.inner
+                .build_file(length, metadata.to_owned(), expiration)
+        })
+    }
+
+    fn create_file(
+        &self,
+        file: FileInfo<Built>,
+    ) -> Result<FileInfo<Created>, VaultError> {
+        /* The file is moved into the first attempt, so creation is not retried */
+        self.inner.create_file(file)
+    }
+
+    fn exists(&self, file_id: &str) -> bool {
+        self.inner.exists(file_id)
+    }
+
+    fn get_file(&self, file_id: &str) -> Result<FileInfo<Created>, VaultError> {
+        self.retry("read", || self.inner.get_file(file_id))
+    }
+
+    fn list_files(
+        &self,
+    ) -> Result<Box<dyn Iterator<Item = FileInfo<Created>> + '_>, VaultError>
+    {
+        self.retry("list", || self.inner.list_files())
+    }
+
+    fn list_namespace(
+        &self,
+        namespace: &str,
+    ) -> Result<Box<dyn Iterator<Item = FileInfo<Created>> + '_>, VaultError>
+    {
+        self.retry("list", || self.inner.list_namespace(namespace))
+    }
+
+    fn open_read(
+        &self,
+        file_id: &str,
+        offset: u64,
+    ) -> Result<Pin<Box<dyn AsyncRead + Send>>, VaultError> {
+        self.retry("open", || self.inner.open_read(file_id, offset))
+    }
+
+    fn patch_file(
+        &self,
+        file_id: &str,
+        buf: &mut [u8],
+        offset: u64,
+        expiration: &Expiration,
+    ) -> Result<PatchOption, VaultError> {
+        self.retry("write", || {
+            self.inner.patch_file(file_id, buf, offset, expiration)
+        })
+    }
+
+    fn finalize_file(
+        &self,
+        file_id: &str,
+        destination: &Path,
+        progress: &mut dyn FnMut(u64),
+    ) -> Result<FileInfo<Completed>, VaultError> {
+        self.retry("finalize", || {
+            self.inner.finalize_file(file_id, destination, progress)
+        })
+    }
+
+    fn terminate_file(
+        &self,
+        file_id: &str,
+    ) -> Result<FileInfo<Terminated>, VaultError> {
+        self.retry("terminate", || self.inner.terminate_file(file_id))
+    }
+
+    fn tombstone_file(
+        &self,
+        file_id: &str,
+    ) -> Result<FileInfo<Terminated>, VaultError> {
+        self.retry("terminate", || self.inner.tombstone_file(file_id))
+    }
+
+    fn restore_file(
+        &self,
+        file_id: &str,
+    ) -> Result<FileInfo<Created>, VaultError> {
+        self.retry("restore", || self.inner.restore_file(file_id))
+    }
+
+    fn list_tombstones(
+        &self,
+    ) -> Result<Box<dyn Iterator<Item = FileInfo<Terminated>> + '_>, VaultError>
+    {
+        self.retry("list", || self.inner.list_tombstones())
+    }
+
+    fn set_scan_state(
+        &self,
+        file_id: &str,
+        state: ScanState,
+    ) -> Result<FileInfo<Completed>, VaultError> {
+        self.retry("scan", || self.inner.set_scan_state(file_id, state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::LocalVault;
+    use rocket::tokio::{runtime::Builder, time};
+    use std::{io, time::Instant};
+
+    fn retrying(attempts: u32, backoff: Duration) -> RetryVault {
+        let path = std::env::temp_dir().join("meteoritus-retry");
+        let path =
+            Box::leak(path.to_string_lossy().into_owned().into_boxed_str());
+
+        RetryVault::new(Arc::new(LocalVault::new(path)), attempts, backoff)
+    }
+
+    /// Fails with a transient error the first `failures` times it is called.
+    fn flaky(failures: u32) -> impl FnMut() -> Result<u32, VaultError> {
+        let mut calls = 0;
+
+        move || {
+            calls += 1;
+
+            match calls <= failures {
+                true => Err(VaultError::Transient(
+                    io::Error::from(io::ErrorKind::TimedOut).into(),
+                )),
+                false => Ok(calls),
+            }
+        }
+    }
+
+    #[test]
+    fn transient_errors_are_retried_within_the_budget() {
+        let vault = retrying(3, Duration::from_millis(1));
+
+        assert!(matches!(vault.retry("patch", flaky(3)), Ok(4)));
+        assert!(matches!(
+            vault.retry("patch", flaky(4)),
+            Err(VaultError::Transient(_))
+        ));
+    }
+
+    #[test]
+    fn permanent_errors_are_not_retried() {
+        let vault = retrying(3, Duration::from_millis(1));
+        let mut calls = 0;
+
+        let result = vault.retry("patch", || -> Result<(), VaultError> {
+            calls += 1;
+            Err(VaultError::Error)
+        });
+
+        assert!(matches!(result, Err(VaultError::Error)));
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn backoff_does_not_stall_the_runtime_worker() {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_time()
+            .build()
+            .unwrap();
+
+        runtime.block_on(async {
+            let started = Instant::now();
+
+            let retried = task::spawn(async {
+                let vault = retrying(1, Duration::from_millis(500));
+                vault.retry("patch", flaky(1)).is_ok()
+            });
+
+            let other = task::spawn(async move {
+                time::sleep(Duration::from_millis(10)).await;
+                started.elapsed()
+            });
+
+            assert!(other.await.unwrap() < Duration::from_millis(400));
+            assert!(retried.await.unwrap());
+        });
+    }
+}
@@ -0,0 +1,59 @@
+use std::{
+    fs::File,
+    io::{Read, Result, Seek, SeekFrom, Write},
+};
+
+/// Writes a chunk received at `offset` into a ring file of `capacity` bytes.
+///
+/// The byte at offset `n` of the upload is stored at position `n % capacity`, so the file
+/// always holds the last `capacity` bytes received. Returns the length of the chunk, even
+/// when older bytes of it were discarded right away.
+pub(crate) fn write_ring(
+    file: &mut File,
+    capacity: u64,
+    offset: u64,
+    buf: &[u8],
+) -> Result<usize> {
+    let length = buf.len() as u64;
+
+    /* Only the tail of a chunk larger than the ring is retained */
+    let skipped = length.saturating_sub(capacity);
+    let mut position = (offset + skipped) % capacity;
+    let mut tail = &buf[skipped as usize..];
+
+    while !tail.is_empty() {
+        let (head, rest) =
+            tail.split_at(tail.len().min((capacity - position) as usize));
+
+        file.seek(SeekFrom::Start(position))?;
+        file.write_all(head)?;
+
+        position = 0;
+        tail = rest;
+    }
+
+    Ok(buf.len())
+}
+
+/// Reorders a completed ring file of `capacity` bytes, so it starts with its oldest byte.
+///
+/// The ring is read in memory at once, which is bounded by its capacity.
+pub(crate) fn unwind_ring(
+    file: &mut File,
+    capacity: u64,
+    length: u64,
+) -> Result<()> {
+    if length <= capacity {
+        return Ok(());
+    }
+
+    let mut content = Vec::with_capacity(capacity as usize);
+
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut content)?;
+
+    content.rotate_left((length % capacity) as usize);
+
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(&content)
+}
@@ -0,0 +1,45 @@
+use std::{fs::File, io::Read, path::Path};
+
+/// Amount of leading bytes needed to match every known signature.
+const SNIFF_LENGTH: u64 = 64;
+
+/// Known file signatures, as `(offset, magic bytes, media type)`.
+const SIGNATURES: [(usize, &[u8], &str); 12] = [
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (0, b"\xff\xd8\xff", "image/jpeg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (8, b"WEBP", "image/webp"),
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"PK\x03\x04", "application/zip"),
+    (0, b"\x1f\x8b", "application/gzip"),
+    (0, b"MZ", "application/vnd.microsoft.portable-executable"),
+    (0, b"\x7fELF", "application/x-elf"),
+    (4, b"ftyp", "video/mp4"),
+    (0, b"OggS", "audio/ogg"),
+];
+
+/// Detects the media type of a file from its first bytes.
+///
+/// Returns `None` when the content does not match any known signature.
+pub(crate) fn sniff_content_type(bytes: &[u8]) -> Option<&'static str> {
+    SIGNATURES
+        .iter()
+        .find(|(offset, magic, _)| {
+            bytes.get(*offset..offset + magic.len()) == Some(*magic)
+        })
+        .map(|(_, _, media_type)| *media_type)
+}
+
+/// Detects the media type of a stored file from its first bytes.
+pub(crate) fn sniff_file_type(path: &Path) -> Option<&'static str> {
+    let mut head = Vec::new();
+
+    File::open(path)
+        .ok()?
+        .take(SNIFF_LENGTH)
+        .read_to_end(&mut head)
+        .ok()?;
+
+    sniff_content_type(&head)
+}
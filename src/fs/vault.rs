@@ -1,34 +1,70 @@
+use rocket::tokio::{fs::File as AsyncFile, io::AsyncRead};
 use std::{
     error::Error,
     fs::{self, File},
     io::{BufReader, ErrorKind, Seek, SeekFrom, Write},
-    path::Path,
+    path::{Path, PathBuf},
+    pin::Pin,
+    time::SystemTime,
 };
+use uuid::Uuid;
 
 use super::{
-    file_info::{Built, Completed, Created, FileInfo, Terminated},
+    expiration::Expiration,
+    file_info::{Built, Completed, Created, FileInfo, ScanState, Terminated},
+    finalize::{move_file, sync_dir},
     metadata::Metadata,
+    namespace::{is_valid_namespace, NAMESPACE_SEPARATOR},
 };
 
+#[cfg(feature = "ring")]
+use super::ring::{unwind_ring, write_ring};
+#[cfg(feature = "sniff")]
+use super::sniff::sniff_file_type;
+
+/// Directory below the root holding randomly named in-progress data, so the
+/// data location does not reveal the upload id.
+const PARTIAL_DIR: &str = ".partial";
+
+#[doc(hidden)]
 pub enum PatchOption {
     Patched(u64),
-    Completed(FileInfo<Completed>),
+    Completed(Box<FileInfo<Completed>>),
 }
 
+#[doc(hidden)]
 #[derive(Debug)]
 pub enum VaultError {
     CreationError(Box<dyn Error>),
     ReadError(Box<dyn Error>),
     TerminationError(Box<dyn Error>),
+    FinalizationError(Box<dyn Error>),
+    DestinationExists,
+    Tombstoned,
+    /// A retryable storage failure, like throttling or a timeout.
+    Transient(Box<dyn Error>),
+    OffsetMismatch(u64),
+    Incomplete,
     Error,
 }
 
+/// Converts a storage io error, reporting the kinds worth retrying as transient.
+fn storage_error(e: std::io::Error) -> VaultError {
+    match e.kind() {
+        ErrorKind::TimedOut
+        | ErrorKind::Interrupted
+        | ErrorKind::WouldBlock => VaultError::Transient(e.into()),
+        _ => VaultError::Error,
+    }
+}
+
 #[doc(hidden)]
 pub trait Vault: Send + Sync {
     fn build_file(
         &self,
         length: u64,
-        metadata: Option<&str>,
+        metadata: Option<Metadata>,
+        expiration: &Expiration,
     ) -> Result<FileInfo<Built>, VaultError>;
 
     fn create_file(
@@ -40,33 +76,262 @@ pub trait Vault: Send + Sync {
 
     fn get_file(&self, file_id: &str) -> Result<FileInfo<Created>, VaultError>;
 
+    fn list_files(
+        &self,
+    ) -> Result<Box<dyn Iterator<Item = FileInfo<Created>> + '_>, VaultError>;
+
+    /// Iterates over the uploads that have not received all their bytes yet.
+    ///
+    /// Uploads are read lazily while iterating, like [`Vault::list_files()`].
+    fn list_incomplete(
+        &self,
+    ) -> Result<Box<dyn Iterator<Item = FileInfo<Created>> + '_>, VaultError>
+    {
+        Ok(Box::new(
+            self.list_files()?.filter(|file| !file.is_completed()),
+        ))
+    }
+
+    /// Iterates over the uploads stored in `namespace`.
+    ///
+    /// The default implementation filters [`Vault::list_files()`], a vault storing
+    /// namespaces apart should only list the given one.
+    fn list_namespace(
+        &self,
+        namespace: &str,
+    ) -> Result<Box<dyn Iterator<Item = FileInfo<Created>> + '_>, VaultError>
+    {
+        let namespace = namespace.to_string();
+
+        Ok(Box::new(self.list_files()?.filter(move |file| {
+            file.namespace() == Some(namespace.as_str())
+        })))
+    }
+
+    /// Opens a completed upload for reading from `offset`, regardless of where it is stored.
+    ///
+    /// Fails with [`VaultError::Incomplete`] if not all bytes have been received yet.
+    fn open_read(
+        &self,
+        file_id: &str,
+        offset: u64,
+    ) -> Result<Pin<Box<dyn AsyncRead + Send>>, VaultError>;
+
     fn patch_file(
         &self,
         file_id: &str,
         buf: &mut [u8],
         offset: u64,
+        expiration: &Expiration,
     ) -> Result<PatchOption, VaultError>;
 
+    /// Moves a completed upload to `destination`.
+    ///
+    /// Fails with [`VaultError::DestinationExists`] without moving the file if `destination`
+    /// already exists. When the file has to be copied, `progress` is called with the amount of bytes copied so far.
+    fn finalize_file(
+        &self,
+        file_id: &str,
+        destination: &Path,
+        progress: &mut dyn FnMut(u64),
+    ) -> Result<FileInfo<Completed>, VaultError>;
+
     fn terminate_file(
         &self,
         file_id: &str,
     ) -> Result<FileInfo<Terminated>, VaultError>;
+
+    /// Marks an upload as terminated while keeping its data until it is purged.
+    ///
+    /// Tombstoned uploads are reported as [`VaultError::Tombstoned`] by [`Vault::get_file()`]
+    /// and are left out of [`Vault::list_files()`].
+    fn tombstone_file(
+        &self,
+        file_id: &str,
+    ) -> Result<FileInfo<Terminated>, VaultError>;
+
+    /// Restores a tombstoned upload.
+    fn restore_file(
+        &self,
+        file_id: &str,
+    ) -> Result<FileInfo<Created>, VaultError>;
+
+    /// Iterates over the tombstoned uploads awaiting their purge.
+    fn list_tombstones(
+        &self,
+    ) -> Result<Box<dyn Iterator<Item = FileInfo<Terminated>> + '_>, VaultError>;
+
+    /// Records the scan state of a completed upload.
+    ///
+    /// The data of an [`ScanState::Infected`] upload is deleted, wherever it is stored, while
+    /// its file information is kept to report the verdict.
+    fn set_scan_state(
+        &self,
+        file_id: &str,
+        state: ScanState,
+    ) -> Result<FileInfo<Completed>, VaultError>;
 }
 
+#[doc(hidden)]
 pub struct LocalVault {
     save_path: &'static str,
+    random_file_names: bool,
+    durable_writes: bool,
+    #[cfg(feature = "sniff")]
+    detect_content_type: bool,
+    #[cfg(feature = "ring")]
+    ring_capacity: Option<u64>,
 }
 
 impl LocalVault {
     pub fn new(save_path: &'static str) -> Self {
-        Self { save_path }
+        Self {
+            save_path,
+            random_file_names: false,
+            durable_writes: false,
+            #[cfg(feature = "sniff")]
+            detect_content_type: false,
+            #[cfg(feature = "ring")]
+            ring_capacity: None,
+        }
+    }
+
+    /// Stores in-progress uploads under a random name, distinct from the upload id.
+    ///
+    /// The data lives in a shared `.partial` directory of the root rather than in
+    /// the upload directory, the name is kept in the upload manifest and the data
+    /// is moved to its final name once the upload is completed.
+    pub fn with_random_file_names(mut self) -> Self {
+        self.random_file_names = true;
+        self
+    }
+
+    /// Syncs every patched chunk to disk before its offset is recorded in the manifest.
+    ///
+    /// After a crash the manifest never claims bytes that were not persisted, so clients
+    /// resume from an offset the data really reached.
+    pub fn with_durable_writes(mut self) -> Self {
+        self.durable_writes = true;
+        self
+    }
+
+    /// Detects the media type of completed uploads from their first bytes.
+    ///
+    /// The detected type is kept in the upload manifest, see [`FileInfo::content_type()`].
+    #[cfg(feature = "sniff")]
+    pub fn with_content_detection(mut self) -> Self {
+        self.detect_content_type = true;
+        self
+    }
+
+    /// Keeps only the last `capacity` bytes of every upload, for bounded live streams.
+    ///
+    /// Each upload is stored in a ring of at most `capacity` bytes, older bytes are
+    /// overwritten as new chunks arrive while the upload offset still counts every byte
+    /// received. Once completed, the file holds the tail of the upload in order.
+    ///
+    /// Only appending at the current offset is supported, the discarded bytes can't be read
+    /// back or resumed from. Checksums and encryption apply to the whole upload, so they
+    /// don't work with uploads longer than the ring, and reading the upload back fails.
+    #[cfg(feature = "ring")]
+    pub fn with_ring_capacity(mut self, capacity: u64) -> Self {
+        self.ring_capacity = Some(capacity.max(1));
+        self
+    }
+
+    /// Returns the amount of bytes stored for an upload of the given length.
+    fn stored_length(&self, length: u64) -> u64 {
+        #[cfg(feature = "ring")]
+        if let Some(capacity) = self.ring_capacity {
+            return length.min(capacity);
+        }
+
+        length
+    }
+
+    /// Writes a chunk at its offset, or into the ring when one is configured.
+    fn write_chunk(
+        &self,
+        file: &mut File,
+        offset: u64,
+        buf: &[u8],
+    ) -> std::io::Result<usize> {
+        #[cfg(feature = "ring")]
+        if let Some(capacity) = self.ring_capacity {
+            return write_ring(file, capacity, offset, buf);
+        }
+
+        file.seek(SeekFrom::Start(offset))?;
+        file.write(buf)
+    }
+
+    /// Truncates the data of an upload back to the offset a failed chunk was written at,
+    /// the space is then allocated again like on creation.
+    ///
+    /// A ring buffer wraps its writes around, so its data is left as is.
+    fn discard_chunk(&self, file: &File, offset: u64, length: u64) {
+        #[cfg(feature = "ring")]
+        if self.ring_capacity.is_some() {
+            return;
+        }
+
+        let discarded = file
+            .set_len(offset)
+            .and_then(|_| file.set_len(self.stored_length(length)));
+
+        if let Err(e) = discarded {
+            error!("Failed to discard a partially written chunk: {}", e);
+        }
+    }
+
+    /// Returns the directory of an upload, below its namespace if any, refusing ids that would escape it.
+    fn file_dir(&self, file_id: &str) -> std::io::Result<PathBuf> {
+        let root = Path::new(self.save_path);
+
+        let (dir, id) = match file_id.split_once(NAMESPACE_SEPARATOR) {
+            Some((namespace, id)) if is_valid_namespace(namespace) => {
+                (root.join(namespace), id)
+            }
+            Some(_) => return Err(ErrorKind::InvalidInput.into()),
+            None => (root.to_path_buf(), file_id),
+        };
+
+        let is_plain = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+        match is_plain {
+            true => Ok(dir.join(id)),
+            false => Err(ErrorKind::InvalidInput.into()),
+        }
+    }
+
+    /// Lists the upload ids stored below an entry of the root, an upload or a namespace.
+    fn entry_ids(&self, name: String) -> Vec<String> {
+        let dir = Path::new(self.save_path).join(&name);
+
+        if !is_valid_namespace(&name) || dir.join("info.json").exists() {
+            return vec![name];
+        }
+
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Vec::new();
+        };
+
+        entries
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .map(|id| format!("{}{}{}", name, NAMESPACE_SEPARATOR, id))
+            .collect()
     }
 
     fn read_file<State>(
         &self,
         file_id: &str,
     ) -> Result<FileInfo<State>, VaultError> {
-        let file_dir = Path::new(self.save_path).join(file_id);
+        let file_dir = self
+            .file_dir(file_id)
+            .map_err(|e| VaultError::ReadError(e.into()))?;
 
         let info_path = file_dir.join("info").with_extension("json");
 
@@ -77,8 +342,103 @@ impl LocalVault {
 
         let reader = BufReader::new(file);
 
-        serde_json::from_reader(reader)
-            .map_err(|e| VaultError::ReadError(e.into()))
+        let mut file_info: FileInfo<State> = serde_json::from_reader(reader)
+            .map_err(|e| VaultError::ReadError(e.into()))?;
+
+        file_info.join_root(Path::new(self.save_path));
+
+        Ok(file_info)
+    }
+
+    /// Reads the file info of every upload stored under the root.
+    fn read_all<State: 'static>(
+        &self,
+    ) -> Result<Box<dyn Iterator<Item = FileInfo<State>> + '_>, VaultError>
+    {
+        let entries = match fs::read_dir(self.save_path) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                return Ok(Box::new(std::iter::empty()))
+            }
+            Err(e) => return Err(VaultError::ReadError(e.into())),
+        };
+
+        Ok(Box::new(
+            entries
+                .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                .flat_map(|name| self.entry_ids(name))
+                .filter_map(|file_id| self.read_file(&file_id).ok()),
+        ))
+    }
+
+    /// Replaces the stored file info of an upload.
+    fn store_file<State>(
+        &self,
+        file_info: &mut FileInfo<State>,
+    ) -> Result<(), Box<dyn Error>> {
+        let file_dir = self.file_dir(file_info.id())?;
+        let file_info_path = file_dir.join("info").with_extension("json");
+
+        let info = File::options()
+            .write(true)
+            .truncate(true)
+            .open(file_info_path)?;
+
+        Ok(self.write_file(info, file_info)?)
+    }
+
+    /// Atomically replaces the stored file info of an upload in `file_dir`.
+    ///
+    /// The file info is staged next to the manifest and renamed over it, so a crash leaves
+    /// either the previous or the new manifest. Durable writes sync both to disk.
+    fn replace_file<State>(
+        &self,
+        file_dir: &Path,
+        file_info: &mut FileInfo<State>,
+    ) -> Result<(), Box<dyn Error>> {
+        let file_info_path = file_dir.join("info").with_extension("json");
+        let staged_path = file_dir.join("info").with_extension("json.tmp");
+
+        let staged: Result<(), Box<dyn Error>> = File::create(&staged_path)
+            .map_err(|e| e.into())
+            .and_then(|mut info| {
+                self.write_file(&mut info, file_info)?;
+
+                if self.durable_writes {
+                    info.sync_all()?;
+                }
+
+                Ok(())
+            });
+
+        if let Err(e) = staged.and_then(|_| {
+            fs::rename(&staged_path, file_info_path).map_err(|e| e.into())
+        }) {
+            let _ = fs::remove_file(&staged_path);
+            return Err(e);
+        }
+
+        if self.durable_writes {
+            sync_dir(file_dir)?;
+        }
+
+        Ok(())
+    }
+
+    /// Stores the file info, keeping its location relative to the storage root
+    /// so the root can be moved without breaking stored uploads.
+    fn write_file<State, W: Write>(
+        &self,
+        writer: W,
+        file_info: &mut FileInfo<State>,
+    ) -> serde_json::Result<()> {
+        let root = Path::new(self.save_path);
+
+        file_info.strip_root(root);
+        let result = serde_json::to_writer(writer, file_info);
+        file_info.join_root(root);
+
+        result
     }
 }
 
@@ -86,21 +446,22 @@ impl Vault for LocalVault {
     fn build_file(
         &self,
         length: u64,
-        metadata: Option<&str>,
+        metadata: Option<Metadata>,
+        expiration: &Expiration,
     ) -> Result<FileInfo<Built>, VaultError> {
-        let metadata = match metadata {
-            Some(metadata) => match Metadata::try_from(metadata) {
-                Ok(m) => m,
-                Err(e) => return Err(VaultError::CreationError(Box::new(e))),
-            },
+        let mut file_info = FileInfo::new(length)
+            .with_uuid()
+            .with_metadata(metadata.unwrap_or_default());
 
-            None => Metadata::default(),
-        };
+        if let Some(created_at) = *file_info.created_at() {
+            if let Some(expires_at) =
+                expiration.expires_at(created_at, created_at)
+            {
+                file_info = file_info.with_expiry(expires_at);
+            }
+        }
 
-        let file_info = FileInfo::new(length)
-            .with_uuid()
-            .with_metadata(metadata)
-            .build();
+        let file_info = file_info.build();
 
         Ok(file_info)
     }
@@ -109,7 +470,9 @@ impl Vault for LocalVault {
         &self,
         file_info: FileInfo<Built>,
     ) -> Result<FileInfo<Created>, VaultError> {
-        let file_dir = Path::new(self.save_path).join(&file_info.id());
+        let file_dir = self
+            .file_dir(file_info.id())
+            .map_err(|e| VaultError::CreationError(e.into()))?;
 
         if !file_dir.exists() {
             if let Err(e) = fs::create_dir_all(&file_dir).map_err(|e| e.into())
@@ -118,7 +481,18 @@ impl Vault for LocalVault {
             };
         }
 
-        let file_name = file_dir.join("file");
+        let file_name = match self.random_file_names {
+            true => {
+                let partial_dir = Path::new(self.save_path).join(PARTIAL_DIR);
+
+                if let Err(e) = fs::create_dir_all(&partial_dir) {
+                    return Err(VaultError::CreationError(e.into()));
+                }
+
+                partial_dir.join(Uuid::new_v4().simple().to_string())
+            }
+            false => file_dir.join("file"),
+        };
 
         /* Creating file for upload */
         if let Err(e) = match File::options()
@@ -127,7 +501,9 @@ impl Vault for LocalVault {
             .create_new(true)
             .open(&file_name)
         {
-            Ok(file) => file.set_len(*file_info.length()).map_err(|e| e.into()),
+            Ok(file) => file
+                .set_len(self.stored_length(*file_info.length()))
+                .map_err(|e| e.into()),
             Err(e) => Err(e.into()),
         } {
             return Err(VaultError::CreationError(e));
@@ -140,7 +516,7 @@ impl Vault for LocalVault {
             )))
         };
 
-        let file_info = file_info.mark_as_created(file_name);
+        let mut file_info = file_info.mark_as_created(file_name);
 
         /* Storing file info */
         if let Err(e) = match File::options()
@@ -150,7 +526,7 @@ impl Vault for LocalVault {
             .open(file_dir.join("info").with_extension("json"))
         {
             Ok(info) => {
-                serde_json::to_writer(info, &file_info).map_err(|e| e.into())
+                self.write_file(info, &mut file_info).map_err(|e| e.into())
             }
             Err(e) => Err(e.into()),
         } {
@@ -161,15 +537,87 @@ impl Vault for LocalVault {
     }
 
     fn exists(&self, file_id: &str) -> bool {
-        let file_dir = Path::new(self.save_path).join(file_id);
-        let file_path = file_dir.join("file");
-        let file_info_path = file_dir.join("info").with_extension("json");
-
-        file_dir.exists() && file_path.exists() && file_info_path.exists()
+        match self.get_file(file_id) {
+            Ok(file_info) => Path::new(file_info.location()).exists(),
+            Err(_) => false,
+        }
     }
 
     fn get_file(&self, file_id: &str) -> Result<FileInfo<Created>, VaultError> {
-        self.read_file(file_id)
+        let file_info: FileInfo<Created> = self.read_file(file_id)?;
+
+        match file_info.terminated_at() {
+            Some(_) => Err(VaultError::Tombstoned),
+            None => Ok(file_info),
+        }
+    }
+
+    fn list_files(
+        &self,
+    ) -> Result<Box<dyn Iterator<Item = FileInfo<Created>> + '_>, VaultError>
+    {
+        Ok(Box::new(
+            self.read_all::<Created>()?
+                .filter(|file| file.terminated_at().is_none()),
+        ))
+    }
+
+    fn list_namespace(
+        &self,
+        namespace: &str,
+    ) -> Result<Box<dyn Iterator<Item = FileInfo<Created>> + '_>, VaultError>
+    {
+        if !is_valid_namespace(namespace) {
+            return Ok(Box::new(std::iter::empty()));
+        }
+
+        /* Only the namespace directory is read, not the whole root */
+        let entries =
+            match fs::read_dir(Path::new(self.save_path).join(namespace)) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == ErrorKind::NotFound => {
+                    return Ok(Box::new(std::iter::empty()))
+                }
+                Err(e) => return Err(VaultError::ReadError(e.into())),
+            };
+
+        let namespace = namespace.to_string();
+
+        Ok(Box::new(
+            entries
+                .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+                .map(move |id| {
+                    format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, id)
+                })
+                .filter_map(|file_id| self.read_file::<Created>(&file_id).ok())
+                .filter(|file| file.terminated_at().is_none()),
+        ))
+    }
+
+    fn open_read(
+        &self,
+        file_id: &str,
+        offset: u64,
+    ) -> Result<Pin<Box<dyn AsyncRead + Send>>, VaultError> {
+        /* A ring only holds the tail of the upload, its offsets don't match the file */
+        #[cfg(feature = "ring")]
+        if self.ring_capacity.is_some() {
+            return Err(VaultError::ReadError(
+                "ring buffered uploads can't be read back".into(),
+            ));
+        }
+
+        let Some(file_info) = self.get_file(file_id)?.check_completion() else {
+            return Err(VaultError::Incomplete);
+        };
+
+        let mut file = File::open(file_info.file_name())
+            .map_err(|e| VaultError::ReadError(e.into()))?;
+
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|e| VaultError::ReadError(e.into()))?;
+
+        Ok(Box::pin(AsyncFile::from_std(file)))
     }
 
     fn patch_file(
@@ -177,23 +625,39 @@ impl Vault for LocalVault {
         file_id: &str,
         buf: &mut [u8],
         offset: u64,
+        expiration: &Expiration,
     ) -> Result<PatchOption, VaultError> {
         let mut file = self.get_file(file_id)?;
 
         if *file.offset() != offset {
-            return Err(VaultError::Error);
+            return Err(VaultError::OffsetMismatch(*file.offset()));
         }
 
-        let file_dir = Path::new(self.save_path).join(file_id);
+        let file_dir = self
+            .file_dir(file_id)
+            .map_err(|e| VaultError::ReadError(e.into()))?;
 
-        let file_path = file_dir.join("file");
-
-        let mut file_content =
-            File::options().write(true).open(file_path).unwrap();
-
-        file_content.seek(SeekFrom::Start(offset)).unwrap();
-
-        let written_bytes = file_content.write(buf).unwrap();
+        let mut file_content = File::options()
+            .read(true)
+            .write(true)
+            .open(file.location())
+            .map_err(storage_error)?;
+
+        let written = self
+            .write_chunk(&mut file_content, offset, buf)
+            .and_then(|written_bytes| match self.durable_writes {
+                true => file_content.sync_data().map(|_| written_bytes),
+                false => Ok(written_bytes),
+            });
+
+        /* The chunk is discarded, so a transient failure can be retried at the same offset */
+        let written_bytes = match written {
+            Ok(written_bytes) => written_bytes,
+            Err(e) => {
+                self.discard_chunk(&file_content, offset, *file.length());
+                return Err(storage_error(e));
+            }
+        };
 
         if written_bytes >= u64::MAX as usize {
             return Err(VaultError::Error);
@@ -202,30 +666,221 @@ impl Vault for LocalVault {
         let offset = offset + written_bytes as u64;
         file.set_offset(offset).unwrap();
 
-        let file_info_path = file_dir.join("info").with_extension("json");
+        #[cfg(feature = "ring")]
+        if let (true, Some(capacity)) =
+            (file.is_completed(), self.ring_capacity)
+        {
+            unwind_ring(&mut file_content, capacity, *file.length())
+                .map_err(|_| VaultError::Error)?;
+        }
 
-        let mut file_info =
-            File::options().write(true).open(file_info_path).unwrap();
+        /* Moving randomly named data to its final name */
+        if file.is_completed() && self.random_file_names {
+            let final_path = file_dir.join("file");
+
+            let Some(final_name) = final_path.to_str() else {
+                return Err(VaultError::Error);
+            };
 
-        serde_json::to_writer(&mut file_info, &file).unwrap();
+            fs::rename(file.location(), final_name)
+                .map_err(|_| VaultError::Error)?;
+
+            file.relocate(final_name);
+        }
+
+        #[cfg(feature = "sniff")]
+        /* Encrypted uploads are only stored as ciphertext */
+        if file.is_completed()
+            && self.detect_content_type
+            && file.key_id().is_none()
+        {
+            if let Some(content_type) =
+                sniff_file_type(Path::new(file.location()))
+            {
+                file.set_content_type(content_type);
+            }
+        }
+
+        /* Renewing expiration based on this activity */
+        if expiration.idle().is_some() {
+            if let Some(expires_at) = file.expires_with(expiration) {
+                file = file.with_expiry(expires_at);
+            }
+        }
+
+        self.replace_file(&file_dir, &mut file)
+            .map_err(|_| VaultError::Error)?;
 
         match file.check_completion() {
-            Some(file) => Ok(PatchOption::Completed(file)),
+            Some(file) => Ok(PatchOption::Completed(Box::new(file))),
             None => Ok(PatchOption::Patched(offset)),
         }
     }
 
+    fn finalize_file(
+        &self,
+        file_id: &str,
+        destination: &Path,
+        progress: &mut dyn FnMut(u64),
+    ) -> Result<FileInfo<Completed>, VaultError> {
+        let Some(mut file_info) = self.get_file(file_id)?.check_completion()
+        else {
+            return Err(VaultError::Incomplete);
+        };
+
+        let Some(destination_name) = destination.to_str() else {
+            return Err(VaultError::FinalizationError(Box::new(
+                std::io::Error::from(ErrorKind::InvalidInput),
+            )));
+        };
+
+        let source = file_info.file_name().to_owned();
+        file_info.relocate(destination_name);
+
+        /* The manifest is staged first, so it never points to data that did not move */
+        let file_dir = self
+            .file_dir(file_id)
+            .map_err(|e| VaultError::FinalizationError(e.into()))?;
+        let file_info_path = file_dir.join("info").with_extension("json");
+        let staged_path = file_dir.join("info").with_extension("json.tmp");
+
+        let staged: Result<(), Box<dyn Error>> = File::create(&staged_path)
+            .map_err(|e| e.into())
+            .and_then(|mut info| {
+                self.write_file(&mut info, &mut file_info)?;
+
+                if self.durable_writes {
+                    info.sync_all()?;
+                }
+
+                Ok(())
+            });
+
+        if let Err(e) = staged {
+            let _ = fs::remove_file(&staged_path);
+            return Err(VaultError::FinalizationError(e));
+        }
+
+        if let Err(e) = move_file(Path::new(&source), destination, progress) {
+            let _ = fs::remove_file(&staged_path);
+
+            return Err(match e.kind() {
+                ErrorKind::AlreadyExists => VaultError::DestinationExists,
+                _ => VaultError::FinalizationError(e.into()),
+            });
+        }
+
+        /* The data is moved back if the manifest can not be replaced */
+        if let Err(e) = fs::rename(&staged_path, file_info_path) {
+            let _ = fs::remove_file(&staged_path);
+
+            if let Err(e) =
+                move_file(destination, Path::new(&source), &mut |_| {})
+            {
+                error!("Failed to restore {} to {}: {}", file_id, source, e);
+            }
+
+            return Err(VaultError::FinalizationError(e.into()));
+        }
+
+        if self.durable_writes {
+            sync_dir(&file_dir)
+                .map_err(|e| VaultError::FinalizationError(e.into()))?;
+        }
+
+        Ok(file_info)
+    }
+
     fn terminate_file(
         &self,
         file_id: &str,
     ) -> Result<FileInfo<Terminated>, VaultError> {
         let file_info = self.read_file::<Terminated>(file_id)?;
 
-        let file_dir = Path::new(self.save_path).join(file_id);
+        let file_dir = self
+            .file_dir(file_id)
+            .map_err(|e| VaultError::TerminationError(e.into()))?;
+
+        /* Randomly named data is stored outside of the upload directory */
+        let location = Path::new(file_info.file_name());
+        if location.starts_with(Path::new(self.save_path).join(PARTIAL_DIR)) {
+            match fs::remove_file(location) {
+                Err(e) if e.kind() != ErrorKind::NotFound => {
+                    return Err(VaultError::TerminationError(e.into()))
+                }
+                _ => {}
+            }
+        }
 
         fs::remove_dir_all(file_dir)
             .map_err(|e| VaultError::TerminationError(e.into()))?;
 
         Ok(file_info)
     }
+
+    fn tombstone_file(
+        &self,
+        file_id: &str,
+    ) -> Result<FileInfo<Terminated>, VaultError> {
+        let mut file_info = self.read_file::<Terminated>(file_id)?;
+
+        if file_info.terminated_at().is_some() {
+            return Err(VaultError::Tombstoned);
+        }
+
+        file_info.set_terminated_at(Some(SystemTime::now()));
+        self.store_file(&mut file_info)
+            .map_err(VaultError::TerminationError)?;
+
+        Ok(file_info)
+    }
+
+    fn restore_file(
+        &self,
+        file_id: &str,
+    ) -> Result<FileInfo<Created>, VaultError> {
+        let mut file_info = self.read_file::<Created>(file_id)?;
+
+        file_info.set_terminated_at(None);
+        self.store_file(&mut file_info)
+            .map_err(|_| VaultError::Error)?;
+
+        Ok(file_info)
+    }
+
+    fn list_tombstones(
+        &self,
+    ) -> Result<Box<dyn Iterator<Item = FileInfo<Terminated>> + '_>, VaultError>
+    {
+        Ok(Box::new(
+            self.read_all::<Terminated>()?
+                .filter(|file| file.terminated_at().is_some()),
+        ))
+    }
+
+    fn set_scan_state(
+        &self,
+        file_id: &str,
+        state: ScanState,
+    ) -> Result<FileInfo<Completed>, VaultError> {
+        let Some(mut file_info) = self.get_file(file_id)?.check_completion()
+        else {
+            return Err(VaultError::Incomplete);
+        };
+
+        if state == ScanState::Infected {
+            match fs::remove_file(file_info.file_name()) {
+                Err(e) if e.kind() != ErrorKind::NotFound => {
+                    return Err(VaultError::TerminationError(e.into()))
+                }
+                _ => {}
+            }
+        }
+
+        file_info.set_scan_state(state);
+        self.store_file(&mut file_info)
+            .map_err(|_| VaultError::Error)?;
+
+        Ok(file_info)
+    }
 }
@@ -7,12 +7,23 @@ use rocket::{
     response::{self, Responder},
     Orbit, Request, Response, Rocket, State,
 };
-use std::{io::Cursor, sync::Arc};
+use std::{
+    collections::HashMap, io::Cursor, net::IpAddr, sync::Arc, time::SystemTime,
+};
 
 use crate::meteoritus::Meteoritus;
-use crate::{handlers::upload::*, Vault};
+use crate::{
+    fs::{VaultError, CHECKSUM_ALGORITHMS},
+    handlers::upload::*,
+    idempotency::Reservation,
+    EventKind, Metadata, MetadataError, MeteoritusError, MeteoritusHeaders,
+    Vault,
+};
 
-use super::HandlerContext;
+use super::{
+    captured_headers, client_addr, guard_callback, has_conflicting_headers,
+    HandlerContext,
+};
 
 #[post("/")]
 pub fn creation_handler(
@@ -20,64 +31,299 @@ pub fn creation_handler(
     meteoritus: &State<Meteoritus<Orbit>>,
     vault: &State<Arc<dyn Vault>>,
 ) -> CreationResponder {
-    let file = match vault.build_file(req.upload_length, req.metadata) {
-        Ok(file) => file,
+    let Some(key) = req.idempotency_key else {
+        return create_upload(req, meteoritus, vault);
+    };
+
+    let client = req.remote_addr;
+    let fingerprint =
+        format!("{} {}", req.upload_length, req.raw_metadata.unwrap_or(""));
+
+    /* A retried creation gets the upload created by the first attempt */
+    match meteoritus.reserve_creation(client, key, &fingerprint, |id| {
+        vault.get_file(id).is_ok()
+    }) {
+        Reservation::Reserved => {}
+        Reservation::Created(id) => {
+            return match (vault.get_file(&id), upload_location(meteoritus, &id))
+            {
+                (Ok(file), Some(location)) => {
+                    CreationResponder::Success(location, *file.expires_at())
+                }
+                _ => CreationResponder::Failure(
+                    MeteoritusError::Internal,
+                    "some error".to_string(),
+                ),
+            };
+        }
+        Reservation::Pending => {
+            return CreationResponder::Failure(
+                MeteoritusError::IdempotencyKeyInFlight,
+                "creation in flight".to_string(),
+            )
+        }
+        Reservation::Mismatch => {
+            return CreationResponder::Failure(
+                MeteoritusError::IdempotencyKeyMismatch,
+                "idempotency key reused".to_string(),
+            )
+        }
+    }
+
+    let responder = create_upload(req, meteoritus, vault);
+
+    /* Only a created upload keeps the key, a failed creation can be retried */
+    if !matches!(responder, CreationResponder::Success(..)) {
+        meteoritus.release_creation(client, key);
+    }
+
+    responder
+}
+
+fn create_upload(
+    req: CreationRequest,
+    meteoritus: &Meteoritus<Orbit>,
+    vault: &Arc<dyn Vault>,
+) -> CreationResponder {
+    /* A draining node only completes the uploads already created */
+    if meteoritus.is_draining() {
+        return CreationResponder::Failure(
+            MeteoritusError::Draining,
+            "draining".to_string(),
+        );
+    }
+
+    /* Uploads without a destination are rejected before any byte is sent */
+    if let Some(None) = meteoritus.route_destination(&req.metadata) {
+        return CreationResponder::Failure(
+            MeteoritusError::CreationRejected,
+            "unknown destination".to_string(),
+        );
+    }
+
+    /* A full-file digest that could never match is refused upfront */
+    if let Err(error) = expected_digest(meteoritus, &req.metadata) {
+        return CreationResponder::Failure(
+            error,
+            "invalid checksum".to_string(),
+        );
+    }
+
+    let namespace = match meteoritus.upload_namespace(&req.metadata) {
+        Ok(namespace) => namespace,
+        Err(error) => {
+            return CreationResponder::Failure(
+                error,
+                "invalid namespace".to_string(),
+            )
+        }
+    };
+
+    let file = match vault.build_file(
+        req.upload_length,
+        req.metadata,
+        meteoritus.expiration(),
+    ) {
+        Ok(file) => {
+            let file = match req.remote_addr {
+                Some(remote_addr) => file.with_remote_addr(remote_addr),
+                None => file,
+            };
+
+            let file = match &namespace {
+                Some(namespace) => file.with_namespace(namespace),
+                None => file,
+            };
+
+            let file = match req.request_headers {
+                Some(headers) => file.with_request_headers(headers),
+                None => file,
+            };
+
+            match req.chunk_size {
+                Some(chunk_size) => file.with_chunk_size(chunk_size),
+                None => file,
+            }
+        }
         Err(_) => {
             return CreationResponder::Failure(
-                Status::InternalServerError,
+                MeteoritusError::Internal,
                 "creation error".to_string(),
             )
         }
     };
 
-    let base_uri = match Origin::parse(meteoritus.base_route()) {
-        Ok(base) => base,
-        Err(_) => {
+    let file = match req.key_id {
+        Some(key_id) => match meteoritus.key_check(key_id) {
+            Some(key_check) => file.with_key(key_id, key_check),
+            None => {
+                return CreationResponder::Failure(
+                    MeteoritusError::UnknownKeyId,
+                    "unknown key id".to_string(),
+                )
+            }
+        },
+        None => file,
+    };
+
+    let file = match req.checksum_algorithm {
+        Some(algorithm) if CHECKSUM_ALGORITHMS.contains(&algorithm) => {
+            file.with_checksum_algorithm(algorithm)
+        }
+        Some(_) => {
             return CreationResponder::Failure(
-                Status::InternalServerError,
+                MeteoritusError::InvalidChecksum,
+                "unsupported checksum algorithm".to_string(),
+            )
+        }
+        None => file,
+    };
+
+    /* A dry run reports the creation as it would be answered */
+    if !meteoritus.has_free_inodes() {
+        return CreationResponder::Failure(
+            MeteoritusError::InsufficientStorage,
+            "insufficient storage".to_string(),
+        );
+    }
+
+    if req.dry_run {
+        return CreationResponder::DryRun;
+    }
+
+    let location = match upload_location(meteoritus, file.id()) {
+        Some(location) => location,
+        None => {
+            return CreationResponder::Failure(
+                MeteoritusError::Internal,
                 "some error".to_string(),
             );
         }
     };
 
-    let uri = uri!(base_uri, upload_handler(id = file.id()));
-    let uri: Reference = uri.into();
-
     if let Some(callback) = &meteoritus.on_creation() {
-        if let Err(error) = callback(HandlerContext {
-            rocket: req.rocket,
-            file_info: &file,
-        }) {
+        let checked = guard_callback("on_creation", || {
+            callback(HandlerContext {
+                rocket: req.rocket,
+                file_info: &file,
+            })
+        });
+
+        /* A panicking callback rejects the upload, it couldn't approve it */
+        let rejection = match checked {
+            Some(Ok(())) => None,
+            Some(Err(error)) => Some(error.to_string()),
+            None => Some("creation callback failed".to_string()),
+        };
+
+        if let Some(message) = rejection {
             return CreationResponder::Failure(
-                Status::UnprocessableEntity,
-                error.to_string(),
+                MeteoritusError::CreationRejected,
+                message,
             );
         }
     }
 
     match vault.create_file(file) {
         Ok(file) => {
+            if let Some(key) = req.idempotency_key {
+                meteoritus.complete_creation(req.remote_addr, key, file.id());
+            }
+
+            meteoritus.emit(EventKind::Created, &file);
+
             if let Some(callback) = &meteoritus.on_created() {
-                callback(HandlerContext {
-                    rocket: req.rocket,
-                    file_info: &file,
+                guard_callback("on_created", || {
+                    callback(HandlerContext {
+                        rocket: req.rocket,
+                        file_info: &file,
+                    })
                 });
             }
 
-            CreationResponder::Success(uri.to_string())
+            CreationResponder::Success(location, *file.expires_at())
         }
+        Err(VaultError::Transient(_)) => CreationResponder::Failure(
+            MeteoritusError::StorageUnavailable,
+            "storage unavailable".to_string(),
+        ),
         Err(_) => CreationResponder::Failure(
-            Status::InternalServerError,
+            MeteoritusError::Internal,
             "some vault error".to_string(),
         ),
     }
 }
 
+/// Returns the `Location` header value of the upload with the given id.
+fn upload_location(
+    meteoritus: &Meteoritus<Orbit>,
+    file_id: &str,
+) -> Option<String> {
+    let base_uri = Origin::parse(meteoritus.base_route()).ok()?;
+
+    let uri = uri!(base_uri, upload_handler(id = file_id));
+    let uri: Reference = uri.into();
+
+    Some(meteoritus.location_format().format(&uri, file_id))
+}
+
+/// Represents how the `Location` header of a created upload is written.
+///
+/// Relative values are resolved by clients against the creation request url, following
+/// [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#section-5.2).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LocationFormat {
+    /// A path with a leading slash, like `/files/<id>`.
+    ///
+    /// It only replaces the path of the request url, so it resolves the same way
+    /// regardless of the creation url.
+    #[default]
+    Path,
+    /// A path without the leading slash, like `files/<id>`.
+    ///
+    /// It replaces the last segment of the request url, so it resolves to `/files/<id>`
+    /// for a creation url with a single segment like `/files`, but to
+    /// `/files/files/<id>` for `/files/`.
+    RelativePath,
+    /// Only the upload id, like `<id>`.
+    ///
+    /// It replaces the last segment of the request url, so a creation url `/files/`
+    /// resolves to `/files/<id>` while `/files` resolves to `/<id>`.
+    Id,
+    /// An absolute url with the given origin, like `https://example.com/files/<id>`.
+    ///
+    /// It is used as is by clients.
+    Url(&'static str),
+}
+
+impl LocationFormat {
+    fn format(&self, uri: &Reference, id: &str) -> String {
+        let path = uri.to_string();
+
+        match self {
+            Self::Path => path,
+            Self::RelativePath => path.trim_start_matches('/').to_string(),
+            Self::Id => id.to_string(),
+            Self::Url(origin) => {
+                format!("{}{}", origin.trim_end_matches('/'), path)
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct CreationRequest<'r> {
     rocket: &'r Rocket<Orbit>,
     upload_length: u64,
-    metadata: Option<&'r str>,
+    metadata: Option<Metadata>,
+    raw_metadata: Option<&'r str>,
+    remote_addr: Option<IpAddr>,
+    request_headers: Option<HashMap<String, String>>,
+    chunk_size: Option<u64>,
+    key_id: Option<&'r str>,
+    checksum_algorithm: Option<&'r str>,
+    idempotency_key: Option<&'r str>,
+    dry_run: bool,
 }
 
 #[rocket::async_trait]
@@ -89,12 +335,19 @@ impl<'r> FromRequest<'r> for CreationRequest<'r> {
     ) -> request::Outcome<Self, Self::Error> {
         let meteoritus = req.rocket().state::<Meteoritus<Orbit>>().unwrap();
 
+        if has_conflicting_headers(req) {
+            return Outcome::Failure((
+                meteoritus.error_status(MeteoritusError::ConflictingHeaders),
+                "Conflicting tus headers",
+            ));
+        }
+
         let tus_resumable_header = req.headers().get_one("Tus-Resumable");
         if tus_resumable_header.is_none()
             || tus_resumable_header.unwrap() != "1.0.0"
         {
             return Outcome::Failure((
-                Status::BadRequest,
+                meteoritus.error_status(MeteoritusError::InvalidTusResumable),
                 "Missing or invalid Tus-Resumable header",
             ));
         }
@@ -104,22 +357,31 @@ impl<'r> FromRequest<'r> for CreationRequest<'r> {
                 Ok(value) => value,
                 Err(_) => {
                     return Outcome::Failure((
-                        Status::BadRequest,
+                        meteoritus
+                            .error_status(MeteoritusError::InvalidUploadLength),
                         "Invalid Upload-Length header",
                     ))
                 }
             },
             None => {
                 return Outcome::Failure((
-                    Status::BadRequest,
+                    meteoritus
+                        .error_status(MeteoritusError::InvalidUploadLength),
                     "Missing Upload-Length header",
                 ))
             }
         };
 
+        if upload_length == 0 && !meteoritus.empty_uploads_allowed() {
+            return Outcome::Failure((
+                meteoritus.error_status(MeteoritusError::EmptyUpload),
+                "Empty uploads are not allowed",
+            ));
+        }
+
         if upload_length > meteoritus.max_size().as_u64() {
             return Outcome::Failure((
-                Status::PayloadTooLarge,
+                meteoritus.error_status(MeteoritusError::UploadTooLarge),
                 "Upload-Length exceeds the Tus-Max-Size",
             ));
         }
@@ -127,13 +389,59 @@ impl<'r> FromRequest<'r> for CreationRequest<'r> {
         let metadata = match req.headers().get_one("Upload-Metadata") {
             None => None,
             Some(metadata) if metadata.is_empty() => None,
-            Some(metadata) => Some(metadata),
+            Some(metadata) => {
+                match Metadata::parse(metadata, meteoritus.metadata_options()) {
+                    Ok(metadata) => Some(metadata),
+                    Err(MetadataError::BudgetExceeded) => {
+                        return Outcome::Failure((
+                            meteoritus.error_status(
+                                MeteoritusError::MetadataTooLarge,
+                            ),
+                            "Upload-Metadata exceeds the metadata budget",
+                        ))
+                    }
+                    Err(_) => {
+                        return Outcome::Failure((
+                            meteoritus
+                                .error_status(MeteoritusError::InvalidMetadata),
+                            "Invalid Upload-Metadata header",
+                        ))
+                    }
+                }
+            }
+        };
+
+        /* An unparsable hint is ignored, it's only an optimization */
+        let chunk_size = req
+            .headers()
+            .get_one("X-Tus-Preferred-Chunk-Size")
+            .and_then(|value| value.parse::<u64>().ok())
+            .and_then(|hint| meteoritus.accept_chunk_size(hint));
+
+        /* The declared algorithm only matters once chunks are verified */
+        let checksum_algorithm = match meteoritus.chunk_checksums() {
+            true => req.headers().get_one("Upload-Checksum-Algorithm"),
+            false => None,
         };
 
+        let dry_run = meteoritus.dry_run_allowed()
+            && req.headers().get_one("Upload-Dry-Run") == Some("1");
+
         let creation_values = CreationRequest {
             rocket: req.rocket(),
             upload_length,
             metadata,
+            raw_metadata: req.headers().get_one("Upload-Metadata"),
+            remote_addr: client_addr(req, meteoritus.trusted_proxies()),
+            request_headers: captured_headers(
+                req,
+                meteoritus.captured_headers(),
+            ),
+            chunk_size,
+            key_id: req.headers().get_one("Upload-Key-Id"),
+            checksum_algorithm,
+            idempotency_key: req.headers().get_one("Idempotency-Key"),
+            dry_run,
         };
 
         Outcome::Success(creation_values)
@@ -141,8 +449,9 @@ impl<'r> FromRequest<'r> for CreationRequest<'r> {
 }
 
 pub enum CreationResponder {
-    Success(String),
-    Failure(Status, String),
+    Success(String, Option<SystemTime>),
+    DryRun,
+    Failure(MeteoritusError, String),
 }
 
 impl<'r> Responder<'r, 'static> for CreationResponder {
@@ -150,15 +459,27 @@ impl<'r> Responder<'r, 'static> for CreationResponder {
         let meteoritus = req.rocket().state::<Meteoritus<Orbit>>().unwrap();
 
         match self {
-            Self::Failure(status, error) => rocket::Response::build()
-                .status(status)
-                .sized_body(error.len(), Cursor::new(error))
+            Self::Failure(error, message) => rocket::Response::build()
+                .status(meteoritus.error_status(error))
+                .sized_body(message.len(), Cursor::new(message))
                 .ok(),
 
-            Self::Success(uri) => Response::build()
+            Self::Success(uri, expires_at) => {
+                let mut res = Response::build();
+
+                if let Some(expires_at) = expires_at {
+                    res.header(MeteoritusHeaders::Expires(expires_at));
+                }
+
+                res.header(meteoritus.get_protocol_resumable_version())
+                    .raw_header("Location", uri)
+                    .status(Status::Created)
+                    .ok()
+            }
+
+            Self::DryRun => Response::build()
                 .header(meteoritus.get_protocol_resumable_version())
-                .raw_header("Location", uri)
-                .status(Status::Created)
+                .status(Status::NoContent)
                 .ok(),
         }
     }
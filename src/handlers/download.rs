@@ -0,0 +1,291 @@
+use std::{pin::Pin, sync::Arc};
+
+use rocket::{
+    http::{ContentType, Status},
+    request::{self, FromRequest, Outcome},
+    response::{self, Responder},
+    tokio::io::{AsyncRead, AsyncReadExt},
+    Orbit, Request, Response, State,
+};
+
+use crate::{
+    fs::{Created, FileInfo, ScanState, VaultError},
+    meteoritus::Meteoritus,
+    MeteoritusError, Vault,
+};
+
+/// Serves a completed upload, or the byte range of it asked by the `Range` header.
+#[get("/<id>")]
+pub async fn download_handler(
+    req: DownloadRequest<'_>,
+    id: &str,
+    meteoritus: &State<Meteoritus<Orbit>>,
+    vault: &State<Arc<dyn Vault>>,
+) -> DownloadResponder {
+    let file = match vault.get_file(id) {
+        Ok(file) => file,
+        Err(VaultError::Tombstoned) => {
+            return DownloadResponder::Failure(MeteoritusError::Gone)
+        }
+        Err(VaultError::Transient(_)) => {
+            return DownloadResponder::Failure(
+                MeteoritusError::StorageUnavailable,
+            )
+        }
+        Err(_) => return DownloadResponder::Failure(MeteoritusError::NotFound),
+    };
+
+    if !file.is_completed() {
+        return DownloadResponder::Failure(MeteoritusError::UploadIncomplete);
+    }
+
+    /* Scanned uploads are only served once released, waiting a while for the verdict */
+    let file = match file.scan_state() {
+        Some(ScanState::Scanning) => {
+            meteoritus.await_verdict(vault, file).await
+        }
+        _ => file,
+    };
+
+    match file.scan_state() {
+        Some(ScanState::Scanning) => {
+            return DownloadResponder::Failure(MeteoritusError::UploadScanning)
+        }
+        Some(ScanState::Infected) => {
+            return DownloadResponder::Failure(MeteoritusError::UploadInfected)
+        }
+        Some(ScanState::Clean) | None => {}
+    }
+
+    let length = *file.length();
+
+    let presentation = Presentation::of(&file);
+
+    let range = match req.range.map(|range| parse_range(range, length)) {
+        Some(Ok(range)) => Some(range),
+        Some(Err(RangeError::Unsatisfiable)) => {
+            return DownloadResponder::Unsatisfiable(length)
+        }
+        /* An unparsable range is ignored, following RFC 9110 */
+        Some(Err(RangeError::Invalid)) | None => None,
+    };
+
+    /* Encrypted uploads stay encrypted at rest, they are decrypted as they are read */
+    let cipher = match meteoritus.upload_cipher(&file) {
+        Ok(cipher) => cipher,
+        Err(error) => return DownloadResponder::Failure(error),
+    };
+
+    let start = range.map_or(0, |(start, _)| start);
+
+    let reader = match vault.open_read(id, start) {
+        Ok(reader) => match cipher {
+            Some(cipher) => Box::pin(cipher.decrypting(reader, start)),
+            None => reader,
+        },
+        Err(VaultError::Transient(_)) => {
+            return DownloadResponder::Failure(
+                MeteoritusError::StorageUnavailable,
+            )
+        }
+        Err(_) => return DownloadResponder::Failure(MeteoritusError::Internal),
+    };
+
+    match range {
+        Some((start, end)) => {
+            DownloadResponder::Partial(reader, start, end, length, presentation)
+        }
+        None => DownloadResponder::Full(reader, length, presentation),
+    }
+}
+
+/// Media types rendered by browsers without running scripts in the origin of the server.
+const INLINE_CONTENT_TYPES: [&str; 9] = [
+    "text/plain",
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "video/mp4",
+    "video/webm",
+    "audio/ogg",
+    "audio/mpeg",
+];
+
+/// How a download is presented to browsers.
+///
+/// Uploads are served from the origin of the application, so their content must never be
+/// rendered as a document. Only the media types of [`INLINE_CONTENT_TYPES`] are displayed
+/// inline, any other one is served as an attachment, and browsers are told not to sniff it.
+pub struct Presentation {
+    content_type: ContentType,
+    inline: bool,
+}
+
+impl Presentation {
+    fn of(file: &FileInfo<Created>) -> Self {
+        let parse = |content_type: &str| {
+            ContentType::parse_flexible(content_type).map(|content_type| {
+                let inline = is_inline(&content_type);
+                (content_type, inline)
+            })
+        };
+
+        /* A detected type is trusted, a declared one only when it's displayed safely */
+        let presentation = match file.detected_content_type() {
+            Some(detected) => parse(detected),
+            None => file
+                .content_type()
+                .and_then(|declared| parse(&declared))
+                .filter(|(_, inline)| *inline),
+        };
+
+        let (content_type, inline) =
+            presentation.unwrap_or((ContentType::Binary, false));
+
+        Self {
+            content_type,
+            inline,
+        }
+    }
+
+    fn apply(self, res: &mut response::Builder<'_>) {
+        res.header(self.content_type)
+            .raw_header("X-Content-Type-Options", "nosniff");
+
+        if !self.inline {
+            res.raw_header("Content-Disposition", "attachment");
+        }
+    }
+}
+
+fn is_inline(content_type: &ContentType) -> bool {
+    let essence = format!("{}/{}", content_type.top(), content_type.sub())
+        .to_ascii_lowercase();
+
+    INLINE_CONTENT_TYPES.contains(&essence.as_str())
+}
+
+#[derive(Debug, PartialEq)]
+enum RangeError {
+    Invalid,
+    Unsatisfiable,
+}
+
+/// Parses a single `bytes` range into inclusive `(start, end)` offsets of a file.
+///
+/// Multiple ranges are not supported and reported as [`RangeError::Invalid`].
+fn parse_range(range: &str, length: u64) -> Result<(u64, u64), RangeError> {
+    let spec = range
+        .trim()
+        .strip_prefix("bytes=")
+        .ok_or(RangeError::Invalid)?;
+
+    if spec.contains(',') {
+        return Err(RangeError::Invalid);
+    }
+
+    let (start, end) = spec.split_once('-').ok_or(RangeError::Invalid)?;
+    let (start, end) = (start.trim(), end.trim());
+
+    let parse =
+        |value: &str| value.parse::<u64>().map_err(|_| RangeError::Invalid);
+
+    let (start, end) = match (start.is_empty(), end.is_empty()) {
+        /* A suffix range asks for the last bytes */
+        (true, false) => match parse(end)? {
+            0 => return Err(RangeError::Unsatisfiable),
+            suffix => (length.saturating_sub(suffix), length.saturating_sub(1)),
+        },
+        (false, true) => (parse(start)?, length.saturating_sub(1)),
+        (false, false) => {
+            let (start, end) = (parse(start)?, parse(end)?);
+
+            if end < start {
+                return Err(RangeError::Invalid);
+            }
+
+            (start, end.min(length.saturating_sub(1)))
+        }
+        (true, true) => return Err(RangeError::Invalid),
+    };
+
+    if start >= length {
+        return Err(RangeError::Unsatisfiable);
+    }
+
+    Ok((start, end))
+}
+
+#[derive(Debug)]
+pub struct DownloadRequest<'r> {
+    range: Option<&'r str>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for DownloadRequest<'r> {
+    type Error = &'static str;
+
+    async fn from_request(
+        req: &'r Request<'_>,
+    ) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(DownloadRequest {
+            range: req.headers().get_one("Range"),
+        })
+    }
+}
+
+type Reader = Pin<Box<dyn AsyncRead + Send>>;
+
+pub enum DownloadResponder {
+    Full(Reader, u64, Presentation),
+    Partial(Reader, u64, u64, u64, Presentation),
+    Unsatisfiable(u64),
+    Failure(MeteoritusError),
+}
+
+impl<'r> Responder<'r, 'static> for DownloadResponder {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        let meteoritus = req.rocket().state::<Meteoritus<Orbit>>().unwrap();
+
+        let mut res = Response::build();
+
+        res.header(meteoritus.get_protocol_resumable_version());
+
+        match self {
+            Self::Full(reader, length, presentation) => {
+                presentation.apply(&mut res);
+
+                res.status(Status::Ok)
+                    .raw_header("Accept-Ranges", "bytes")
+                    .raw_header("X-Download-Offset", length.to_string())
+                    .streamed_body(reader.take(length));
+            }
+            Self::Partial(reader, start, end, length, presentation) => {
+                presentation.apply(&mut res);
+
+                /* The offset to resume the download from, like Upload-Offset */
+                res.status(Status::PartialContent)
+                    .raw_header("Accept-Ranges", "bytes")
+                    .raw_header(
+                        "Content-Range",
+                        format!("bytes {}-{}/{}", start, end, length),
+                    )
+                    .raw_header("X-Download-Offset", (end + 1).to_string())
+                    .streamed_body(reader.take(end + 1 - start));
+            }
+            Self::Unsatisfiable(length) => {
+                res.status(
+                    meteoritus
+                        .error_status(MeteoritusError::RangeNotSatisfiable),
+                )
+                .raw_header("Content-Range", format!("bytes */{}", length));
+            }
+            Self::Failure(error) => {
+                res.status(meteoritus.error_status(error));
+            }
+        };
+
+        res.ok()
+    }
+}
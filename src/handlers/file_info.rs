@@ -2,30 +2,115 @@ use std::sync::Arc;
 
 use rocket::{
     http::Status,
+    request::{self, FromRequest, Outcome},
     response::{self, Responder},
+    tokio::{task, time},
     Orbit, Request, State,
 };
 
 use crate::{
-    fs::{Created, FileInfo},
+    fs::{sha256_hex, Created, FileInfo, VaultError},
     meteoritus::Meteoritus,
-    Vault,
+    MeteoritusError, MeteoritusHeaders, Vault,
 };
 
 #[head("/<id>")]
-pub fn file_info_handler(
+pub async fn file_info_handler(
+    req: FileInfoRequest<'_>,
     id: &str,
     vault: &State<Arc<dyn Vault>>,
+    meteoritus: &State<Meteoritus<Orbit>>,
 ) -> FileInfoResponder {
-    match vault.get_file(id) {
-        Ok(file) => FileInfoResponder::Success(file),
-        Err(_) => FileInfoResponder::Failure(Status::NotFound),
+    if meteoritus.is_aborted(id) {
+        return FileInfoResponder::Failure(MeteoritusError::Gone);
+    }
+
+    /* Held until the lookup finishes, even once its response timed out */
+    let Some(permit) = meteoritus.acquire_lookup() else {
+        return FileInfoResponder::Failure(MeteoritusError::TooManyLookups);
+    };
+
+    /* The lookup blocks, it runs aside so a stuck storage cannot hold the response */
+    let lookup = {
+        let (vault, file_id) = (Arc::clone(vault), id.to_string());
+        task::spawn_blocking(move || {
+            let _permit = permit;
+            lookup_file(&vault, &file_id)
+        })
+    };
+
+    let file = match time::timeout(meteoritus.lookup_timeout(), lookup).await {
+        Ok(Ok(Ok(file))) => file,
+        Ok(Ok(Err(e))) => return FileInfoResponder::Failure(e),
+        Ok(Err(_)) => {
+            return FileInfoResponder::Failure(MeteoritusError::Internal)
+        }
+        Err(_) => {
+            warn!("Lookup of upload {} timed out", id);
+            return FileInfoResponder::Failure(MeteoritusError::LookupTimeout);
+        }
+    };
+
+    /* Guards resuming clients against an id reused by another upload */
+    if let Some(expected) = req.metadata_hash {
+        if !expected.eq_ignore_ascii_case(&metadata_hash(&file)) {
+            return FileInfoResponder::Failure(
+                MeteoritusError::MetadataHashMismatch,
+            );
+        }
+    }
+
+    FileInfoResponder::Success(Box::new(file))
+}
+
+/// Reads the information of an upload, storage errors are answered as tus errors.
+fn lookup_file(
+    vault: &Arc<dyn Vault>,
+    file_id: &str,
+) -> Result<FileInfo<Created>, MeteoritusError> {
+    match vault.get_file(file_id) {
+        Ok(file) => Ok(file),
+        Err(VaultError::Tombstoned) => Err(MeteoritusError::Gone),
+        Err(VaultError::Transient(_)) => {
+            Err(MeteoritusError::StorageUnavailable)
+        }
+        Err(_) => Err(MeteoritusError::NotFound),
+    }
+}
+
+/// Computes the SHA-256 digest of the canonical metadata string of the file.
+///
+/// An upload without metadata is hashed as the empty string.
+fn metadata_hash(file: &FileInfo<Created>) -> String {
+    let canonical = match file.metadata() {
+        Some(metadata) => metadata.canonical(),
+        None => String::default(),
+    };
+
+    sha256_hex(canonical.as_bytes())
+}
+
+#[derive(Debug)]
+pub struct FileInfoRequest<'r> {
+    metadata_hash: Option<&'r str>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for FileInfoRequest<'r> {
+    type Error = &'static str;
+
+    async fn from_request(
+        req: &'r Request<'_>,
+    ) -> request::Outcome<Self, Self::Error> {
+        Outcome::Success(FileInfoRequest {
+            metadata_hash: req.headers().get_one("Upload-Metadata-Hash"),
+        })
     }
 }
 
 pub enum FileInfoResponder {
-    Success(FileInfo<Created>),
-    Failure(Status),
+    Success(Box<FileInfo<Created>>),
+    Failure(MeteoritusError),
 }
 
 impl<'r> Responder<'r, 'static> for FileInfoResponder {
@@ -39,12 +124,46 @@ impl<'r> Responder<'r, 'static> for FileInfoResponder {
         match self {
             Self::Success(file) => {
                 res.status(Status::NoContent);
+
+                /* Completed uploads report both as well, so clients can verify the final size */
                 res.raw_header("Upload-Length", file.length().to_string());
-                res.raw_header("Upload-Offset", file.offset().to_string())
+                res.raw_header("Upload-Offset", file.offset().to_string());
+
+                if let Some(expires_at) =
+                    file.expires_with(meteoritus.expiration())
+                {
+                    if !file.is_completed() {
+                        res.header(MeteoritusHeaders::Expires(expires_at));
+                    }
+                }
+
+                if meteoritus.downloads() && file.is_completed() {
+                    res.raw_header("Accept-Ranges", "bytes");
+                }
+
+                if meteoritus.progress_header() {
+                    res.raw_header("X-Upload-Progress", progress(&file));
+                }
+
+                if let Some(state) = file.scan_state() {
+                    res.raw_header("X-Upload-Scan", state.as_str());
+                }
+            }
+            Self::Failure(error) => {
+                res.status(meteoritus.error_status(error));
             }
-            Self::Failure(status) => res.status(status),
         };
 
         res.ok()
     }
 }
+
+/// Computes the upload progress as an integer percentage.
+fn progress(file: &FileInfo<Created>) -> String {
+    let percentage = match *file.length() {
+        0 => 100,
+        length => *file.offset() as u128 * 100 / length as u128,
+    };
+
+    percentage.to_string()
+}
@@ -1,24 +1,110 @@
 use rocket::{http::Status, response::Responder, Orbit, Request, Response};
 
-use crate::meteoritus::Meteoritus;
+use crate::{
+    fs::{Expiration, CHECKSUM_ALGORITHMS},
+    meteoritus::Meteoritus,
+};
 
 #[options("/")]
 pub fn info_handler() -> InfoResponder {
     InfoResponder {}
 }
 
+/// Answers CORS preflight requests sent by browsers before reaching an upload url.
+#[options("/<_>")]
+pub fn file_options_handler() -> InfoResponder {
+    InfoResponder {}
+}
+
 pub struct InfoResponder {}
 
 impl<'r> Responder<'r, 'static> for InfoResponder {
     fn respond_to(self, req: &'r Request<'_>) -> rocket::response::Result<'static> {
         let meteoritus = req.rocket().state::<Meteoritus<Orbit>>().unwrap();
 
-        Response::build()
-            .header(meteoritus.get_protocol_resumable_version())
+        let mut res = Response::build();
+        res.status(Status::NoContent);
+
+        /* CORS preflight requests must not be answered with capabilities */
+        if req.headers().contains("Access-Control-Request-Method") {
+            let methods = match meteoritus.downloads() {
+                true => CORS_DOWNLOAD_METHODS,
+                false => CORS_ALLOWED_METHODS,
+            };
+
+            return res
+                .raw_header(
+                    "Access-Control-Allow-Origin",
+                    meteoritus.cors_origin().to_string(),
+                )
+                .raw_header("Access-Control-Allow-Methods", methods)
+                .raw_header(
+                    "Access-Control-Allow-Headers",
+                    CORS_ALLOWED_HEADERS,
+                )
+                .raw_header("Access-Control-Max-Age", "86400")
+                .ok();
+        }
+
+        if let Some(max_age) = max_age(meteoritus.expiration()) {
+            res.raw_header("X-Tus-Max-Age", max_age);
+        }
+
+        if meteoritus.chunk_checksums() {
+            res.raw_header(
+                "Tus-Checksum-Algorithm",
+                CHECKSUM_ALGORITHMS.join(","),
+            );
+        }
+
+        if let Some(chunk_size) = meteoritus.preferred_chunk_size() {
+            res.raw_header(
+                "X-Tus-Preferred-Chunk-Size",
+                chunk_size.as_u64().to_string(),
+            );
+        }
+
+        res.header(meteoritus.get_protocol_resumable_version())
             .header(meteoritus.get_protocol_version())
             .header(meteoritus.get_protocol_extensions())
             .header(meteoritus.get_protocol_max_size())
-            .status(Status::NoContent)
             .ok()
     }
 }
+
+/// Describes the configured expiration windows in seconds, like `idle=3600, absolute=86400`.
+fn max_age(expiration: &Expiration) -> Option<String> {
+    let windows: Vec<String> = [
+        ("idle", expiration.idle()),
+        ("absolute", expiration.absolute()),
+    ]
+    .into_iter()
+    .filter_map(|(name, window)| {
+        Some(format!("{}={}", name, window?.as_secs()))
+    })
+    .collect();
+
+    match windows.is_empty() {
+        true => None,
+        false => Some(windows.join(", ")),
+    }
+}
+
+/// Response headers browsers expose to cross-origin upload clients.
+pub(crate) const CORS_EXPOSED_HEADERS: &str = "Location, Tus-Resumable, \
+    Tus-Version, Tus-Extension, Tus-Max-Size, Tus-Checksum-Algorithm, \
+    Upload-Offset, Upload-Length, Upload-Expires, X-Tus-Max-Age, \
+    X-Tus-Preferred-Chunk-Size, X-Upload-Progress, X-Upload-Scan, \
+    X-Upload-Node, X-Resource-Location, X-Download-Offset, Retry-After, \
+    Content-Range";
+
+const CORS_ALLOWED_METHODS: &str = "POST, HEAD, PATCH, DELETE, OPTIONS";
+
+/// Allowed methods when completed uploads can be downloaded.
+const CORS_DOWNLOAD_METHODS: &str = "POST, GET, HEAD, PATCH, DELETE, OPTIONS";
+
+const CORS_ALLOWED_HEADERS: &str = "Content-Type, Tus-Resumable, \
+    Upload-Length, Upload-Offset, Upload-Metadata, Upload-Dry-Run, \
+    X-Tus-Preferred-Chunk-Size, Upload-Key-Id, Upload-Checksum, \
+    Upload-Checksum-Algorithm, Idempotency-Key, Upload-Metadata-Hash, \
+    Content-Range, Range";
@@ -0,0 +1,107 @@
+use rocket::{
+    http::{Method, Status},
+    response::Responder,
+    route::{Handler, Outcome},
+    Data, Orbit, Request, Response, Route,
+};
+
+use crate::Meteoritus;
+
+/// Every method a route can be mounted for.
+const METHODS: [Method; 9] = [
+    Method::Options,
+    Method::Head,
+    Method::Get,
+    Method::Post,
+    Method::Patch,
+    Method::Put,
+    Method::Delete,
+    Method::Trace,
+    Method::Connect,
+];
+
+/// Methods served on the creation url.
+const CREATION_METHODS: [Method; 2] = [Method::Options, Method::Post];
+
+/// Methods served on an upload url.
+const UPLOAD_METHODS: [Method; 4] =
+    [Method::Options, Method::Head, Method::Patch, Method::Delete];
+
+/// Methods served on an upload url when completed uploads can be downloaded.
+const DOWNLOAD_METHODS: [Method; 5] = [
+    Method::Options,
+    Method::Head,
+    Method::Get,
+    Method::Patch,
+    Method::Delete,
+];
+
+/// Ranked after every other route, so application routes sharing the path still match.
+const METHOD_NOT_ALLOWED_RANK: isize = 100;
+
+/// Returns the routes answering `405 Method Not Allowed` to the methods not served on the
+/// creation and upload urls, `GET` is served on the latter only with `downloads`.
+pub(crate) fn method_not_allowed_routes(downloads: bool) -> Vec<Route> {
+    let mut routes = Vec::new();
+
+    let upload_methods = match downloads {
+        true => &DOWNLOAD_METHODS[..],
+        false => &UPLOAD_METHODS[..],
+    };
+
+    for (path, served) in
+        [("/", &CREATION_METHODS[..]), ("/<_>", upload_methods)]
+    {
+        let handler = MethodNotAllowed {
+            allow: served
+                .iter()
+                .map(|method| method.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        };
+
+        for method in METHODS.into_iter().filter(|m| !served.contains(m)) {
+            routes.push(Route::ranked(
+                METHOD_NOT_ALLOWED_RANK,
+                method,
+                path,
+                handler.clone(),
+            ));
+        }
+    }
+
+    routes
+}
+
+#[derive(Clone)]
+struct MethodNotAllowed {
+    allow: String,
+}
+
+#[rocket::async_trait]
+impl Handler for MethodNotAllowed {
+    async fn handle<'r>(
+        &self,
+        req: &'r Request<'_>,
+        _data: Data<'r>,
+    ) -> Outcome<'r> {
+        Outcome::from(req, MethodNotAllowedResponder(self.allow.to_owned()))
+    }
+}
+
+struct MethodNotAllowedResponder(String);
+
+impl<'r> Responder<'r, 'static> for MethodNotAllowedResponder {
+    fn respond_to(
+        self,
+        req: &'r Request<'_>,
+    ) -> rocket::response::Result<'static> {
+        let meteoritus = req.rocket().state::<Meteoritus<Orbit>>().unwrap();
+
+        Response::build()
+            .status(Status::MethodNotAllowed)
+            .raw_header("Allow", self.0)
+            .header(meteoritus.get_protocol_resumable_version())
+            .ok()
+    }
+}
@@ -1,15 +1,33 @@
 mod creation;
+#[cfg(feature = "download")]
+#[allow(unused_imports)]
+mod download;
 mod file_info;
+#[allow(unused_imports)]
 mod info;
+mod method;
+#[allow(unused_imports)]
+mod readiness;
 mod termination;
 mod upload;
 
-pub use creation::creation_handler;
+pub use creation::{creation_handler, LocationFormat};
+#[cfg(feature = "download")]
+pub use download::download_handler;
 pub use file_info::file_info_handler;
-pub use info::info_handler;
-use rocket::{Orbit, Rocket};
+pub(crate) use info::CORS_EXPOSED_HEADERS;
+pub use info::{file_options_handler, info_handler};
+pub(crate) use method::method_not_allowed_routes;
+pub use readiness::readiness_handler;
+use rocket::{Orbit, Request, Rocket};
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    panic::{self, AssertUnwindSafe},
+};
 pub use termination::termination_handler;
 pub use upload::upload_handler;
+pub(crate) use upload::{fan_out, unlink_fan_out};
 
 use crate::fs::FileInfo;
 
@@ -22,3 +40,96 @@ pub struct HandlerContext<'a, S> {
     pub rocket: &'a Rocket<Orbit>,
     pub file_info: &'a FileInfo<S>,
 }
+
+/// Tus headers that carry a single value.
+const SINGLE_VALUED_HEADERS: [&str; 4] = [
+    "Tus-Resumable",
+    "Upload-Length",
+    "Upload-Offset",
+    "Upload-Metadata",
+];
+
+/// Indicates if any single-valued tus header was sent multiple times with different values.
+pub(crate) fn has_conflicting_headers(req: &Request<'_>) -> bool {
+    SINGLE_VALUED_HEADERS.iter().any(|name| {
+        let mut values = req.headers().get(name);
+        let first = values.next();
+
+        values.any(|value| Some(value) != first)
+    })
+}
+
+/// Resolves the address of the uploading client.
+///
+/// With `trusted_proxies` hops in front of the server, the client is the entry appended by
+/// the outermost trusted proxy, counted from the right of `X-Forwarded-For`. Entries further
+/// left are client controlled and ignored. When the header has fewer entries than trusted
+/// hops, or none are trusted, the peer address is used.
+pub(crate) fn client_addr(
+    req: &Request<'_>,
+    trusted_proxies: usize,
+) -> Option<IpAddr> {
+    let peer = req.remote().map(|remote| remote.ip());
+
+    if trusted_proxies == 0 {
+        return peer;
+    }
+
+    let forwarded: Vec<&str> = req
+        .headers()
+        .get("X-Forwarded-For")
+        .flat_map(|value| value.split(','))
+        .map(|value| value.trim())
+        .collect();
+
+    match forwarded.len().checked_sub(trusted_proxies) {
+        Some(index) => forwarded[index].parse().ok(),
+        None => peer,
+    }
+}
+
+/// Returns the values of the given request headers, `None` when none are captured.
+///
+/// Repeated headers are joined by `, `, headers missing from the request are left out.
+pub(crate) fn captured_headers(
+    req: &Request<'_>,
+    names: &[&str],
+) -> Option<HashMap<String, String>> {
+    if names.is_empty() {
+        return None;
+    }
+
+    let headers = names
+        .iter()
+        .filter_map(|name| {
+            let values: Vec<&str> = req.headers().get(name).collect();
+
+            match values.is_empty() {
+                true => None,
+                false => Some((name.to_string(), values.join(", "))),
+            }
+        })
+        .collect();
+
+    Some(headers)
+}
+
+/// Runs a user callback, a panic is logged and reported as `None` instead of failing the request.
+pub(crate) fn guard_callback<T>(
+    name: &str,
+    callback: impl FnOnce() -> T,
+) -> Option<T> {
+    match panic::catch_unwind(AssertUnwindSafe(callback)) {
+        Ok(value) => Some(value),
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+
+            error!("The {} callback panicked: {}", name, message);
+            None
+        }
+    }
+}
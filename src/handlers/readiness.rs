@@ -0,0 +1,41 @@
+use std::sync::Arc;
+
+use rocket::{
+    http::Status, response::content::RawJson, tokio::task, Orbit, State,
+};
+
+use crate::{
+    meteoritus::Meteoritus,
+    readiness::{count_in_progress, Readiness},
+    ReadinessStatus, Vault,
+};
+
+/// Reports the readiness of the node, only a ready node answers `200 OK`.
+#[get("/")]
+pub async fn readiness_handler(
+    meteoritus: &State<Meteoritus<Orbit>>,
+    vault: &State<Arc<dyn Vault>>,
+) -> (Status, RawJson<String>) {
+    /* Listing the storage blocks, it runs aside from the request workers */
+    let in_progress = {
+        let vault = Arc::clone(vault);
+        task::spawn_blocking(move || count_in_progress(vault.as_ref()))
+            .await
+            .ok()
+            .flatten()
+    };
+
+    let readiness = Readiness::new(meteoritus.is_draining(), in_progress);
+
+    let status = match readiness.status {
+        ReadinessStatus::Ready => Status::Ok,
+        ReadinessStatus::Draining
+        | ReadinessStatus::Drained
+        | ReadinessStatus::Unavailable => Status::ServiceUnavailable,
+    };
+
+    match serde_json::to_string(&readiness) {
+        Ok(body) => (status, RawJson(body)),
+        Err(_) => (Status::InternalServerError, RawJson(String::default())),
+    }
+}
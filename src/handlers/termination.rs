@@ -1,29 +1,70 @@
 use std::sync::Arc;
 
 use rocket::{
-    http::Status,
+    data::ToByteUnit,
+    http::{Header, Status},
     outcome::Outcome,
     request::{self, FromRequest},
     response::{self, Responder},
-    Orbit, Request, Rocket, State,
+    tokio::io::sink,
+    Data, Orbit, Request, Rocket, State,
 };
 
-use crate::{HandlerContext, Meteoritus, Vault};
+use crate::{EventKind, HandlerContext, Meteoritus, MeteoritusError, Vault};
 
-#[delete("/<id>")]
-pub fn termination_handler(
+use super::guard_callback;
+
+/// Largest body drained from a lenient `DELETE` request.
+///
+/// Bigger bodies are not read any further, the request is rejected and the connection is
+/// closed since it can't be reused with unread bytes pending.
+pub(crate) const MAX_DRAINED_BODY: u64 = 64 * 1024;
+
+#[delete("/<id>", data = "<data>")]
+pub async fn termination_handler(
     id: &str,
-    req: TerminationRequest,
+    req: TerminationRequest<'_>,
     vault: &State<Arc<dyn Vault>>,
     meteoritus: &State<Meteoritus<Orbit>>,
+    mut data: Data<'_>,
 ) -> TerminationResponder {
-    match vault.terminate_file(id) {
-        Err(_) => TerminationResponder::Failure,
+    if meteoritus.strict_termination() {
+        /* The body is left unread, so the connection can't be reused */
+        if !data.peek(1).await.is_empty() {
+            return TerminationResponder::Abandoned(
+                MeteoritusError::UnexpectedBody,
+            );
+        }
+    } else {
+        /* Draining any unexpected body so the connection can be reused */
+        let drained =
+            data.open(MAX_DRAINED_BODY.bytes()).stream_to(sink()).await;
+
+        if !matches!(drained, Ok(drained) if drained.complete) {
+            return TerminationResponder::Abandoned(
+                MeteoritusError::UnexpectedBody,
+            );
+        }
+    }
+
+    meteoritus.rolling_digests().forget(id);
+
+    let terminated = match meteoritus.soft_termination() {
+        Some(_) => vault.tombstone_file(id),
+        None => vault.terminate_file(id),
+    };
+
+    match terminated {
+        Err(_) => TerminationResponder::Failure(MeteoritusError::Gone),
         Ok(file) => {
+            meteoritus.emit(EventKind::Terminated, &file);
+
             if let Some(callback) = &meteoritus.on_termination() {
-                callback(HandlerContext {
-                    rocket: req.rocket,
-                    file_info: &file,
+                guard_callback("on_termination", || {
+                    callback(HandlerContext {
+                        rocket: req.rocket,
+                        file_info: &file,
+                    })
                 });
             }
 
@@ -52,7 +93,9 @@ impl<'r> FromRequest<'r> for TerminationRequest<'r> {
 
 pub enum TerminationResponder {
     Success,
-    Failure,
+    Failure(MeteoritusError),
+    /// Failure leaving unread bytes on the connection, which must be closed.
+    Abandoned(MeteoritusError),
 }
 
 impl<'r> Responder<'r, 'static> for TerminationResponder {
@@ -65,7 +108,10 @@ impl<'r> Responder<'r, 'static> for TerminationResponder {
 
         match self {
             Self::Success => res.status(Status::NoContent),
-            Self::Failure => res.status(Status::Gone),
+            Self::Failure(error) => res.status(meteoritus.error_status(error)),
+            Self::Abandoned(error) => res
+                .status(meteoritus.error_status(error))
+                .header(Header::new("Connection", "close")),
         };
 
         res.ok()
@@ -1,15 +1,36 @@
-use std::sync::Arc;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 use rocket::{
+    data::ByteUnit,
     http::{ContentType, Status},
     request::{self, FromRequest, Outcome},
     response::{self, Responder},
+    tokio::{io::AsyncReadExt, task::spawn_blocking},
     Data, Orbit, Request, Rocket, State,
 };
 
-use crate::{fs::PatchOption, Meteoritus, Vault};
+use crate::{
+    fs::{
+        checksum_digest, link_file, sha256_digest, Completed, Created,
+        FileInfo, PatchOption, UploadCipher, VaultError,
+    },
+    EventKind, Metadata, MetadataError, Meteoritus, MeteoritusError,
+    MeteoritusHeaders, Vault,
+};
+
+#[cfg(feature = "sniff")]
+use crate::fs::sniff_content_type;
+
+use super::{guard_callback, has_conflicting_headers, HandlerContext};
 
-use super::HandlerContext;
+/// Amount of copied bytes between two [`EventKind::Moving`] events.
+const MOVING_REPORT_INTERVAL: u64 = 64 * 1024 * 1024;
 
 #[patch("/<id>", data = "<data>")]
 pub async fn upload_handler(
@@ -19,47 +40,691 @@ pub async fn upload_handler(
     data: Data<'_>,
     vault: &State<Arc<dyn Vault>>,
 ) -> UploadResponder {
+    if meteoritus.is_aborted(id) {
+        return UploadResponder::Failure(MeteoritusError::Gone);
+    }
+
     if !vault.exists(id) {
-        return UploadResponder::Failure(Status::NotFound);
+        return UploadResponder::Failure(match vault.get_file(id) {
+            Err(VaultError::Tombstoned) => MeteoritusError::Gone,
+            _ => MeteoritusError::NotFound,
+        });
+    }
+
+    /* A retry of an applied request is acknowledged without writing it again */
+    if let Some(key) = req.idempotency_key {
+        if let Some(offset) = meteoritus.replay_patch(id, key, req.offset) {
+            return UploadResponder::Success(offset, None);
+        }
+    }
+
+    /* Held until the chunk is written, dropped as well if the client disconnects */
+    let stream = match meteoritus.acquire_patch_stream() {
+        Ok(stream) => stream,
+        Err(retry_after) => return UploadResponder::Busy(retry_after),
+    };
+
+    /* A matching Upload-Length is tolerated, a different one is a client bug */
+    if let Some(upload_length) = req.upload_length {
+        let Ok(file) = vault.get_file(id) else {
+            return UploadResponder::Failure(MeteoritusError::NotFound);
+        };
+
+        if *file.length() != upload_length {
+            return UploadResponder::Failure(
+                MeteoritusError::UploadLengthMismatch,
+            );
+        }
     }
 
-    let Ok(mut data) = data.open(meteoritus.max_size()).into_bytes().await else {
-        return UploadResponder::Failure(Status::UnprocessableEntity);
+    let capacity = match meteoritus.preferred_chunk_size() {
+        Some(_) => vault
+            .get_file(id)
+            .ok()
+            .and_then(|file| buffer_capacity(&file)),
+        None => None,
     };
 
-    let Ok(result) = vault.patch_file(id, &mut data, req.offset,) else {
-        return UploadResponder::Failure(Status::UnprocessableEntity)
+    let Ok(mut data) = read_chunk(data, meteoritus.max_size(), capacity).await
+    else {
+        return UploadResponder::Failure(MeteoritusError::UnprocessableChunk);
+    };
+
+    if let Some(content_range) = req.content_range {
+        let Ok(file) = vault.get_file(id) else {
+            return UploadResponder::Failure(MeteoritusError::NotFound);
+        };
+
+        if !matches_content_range(content_range, req.offset, &data, &file) {
+            return reject_chunk(
+                req.rocket,
+                meteoritus,
+                vault,
+                id,
+                MeteoritusError::InvalidContentRange,
+            );
+        }
+    }
+
+    /* Checksums cover the bytes as sent, before any transform or encryption */
+    if let Some(checksum) = req.checksum {
+        let Ok(file) = vault.get_file(id) else {
+            return UploadResponder::Failure(MeteoritusError::NotFound);
+        };
+
+        if let Err(error) = verify_chunk_checksum(checksum, &file, &data) {
+            return reject_chunk(req.rocket, meteoritus, vault, id, error);
+        }
+    }
+
+    /* Only the final chunk may be smaller than the minimum */
+    if let Some(min_chunk_size) = meteoritus.min_chunk_size() {
+        if (data.len() as u64) < min_chunk_size.as_u64() {
+            let Ok(file) = vault.get_file(id) else {
+                return UploadResponder::Failure(MeteoritusError::NotFound);
+            };
+
+            if req.offset + (data.len() as u64) < *file.length() {
+                return UploadResponder::Failure(
+                    MeteoritusError::ChunkTooSmall,
+                );
+            }
+        }
+    }
+
+    if let Some(transform) = meteoritus.body_transform() {
+        let Ok(file) = vault.get_file(id) else {
+            return UploadResponder::Failure(MeteoritusError::NotFound);
+        };
+
+        /* Only a chunk written at the upload offset reaches the transform */
+        if *file.offset() != req.offset {
+            return offset_mismatch(
+                req.rocket, meteoritus, vault, id, req.offset,
+            );
+        }
+
+        let length = data.len();
+        data = transform.transform(req.offset, data).await;
+
+        /* Offsets are accounted in bytes sent by the client */
+        if data.len() != length {
+            error!(
+                "Body transform changed a chunk of {} from {} to {} bytes",
+                id,
+                length,
+                data.len()
+            );
+
+            return UploadResponder::Failure(
+                MeteoritusError::UnprocessableChunk,
+            );
+        }
+    }
+
+    #[cfg(feature = "sniff")]
+    if req.offset == 0 && meteoritus.sniff_content() {
+        let Ok(file) = vault.get_file(id) else {
+            return UploadResponder::Failure(MeteoritusError::NotFound);
+        };
+
+        /* A stale first chunk must not terminate an upload that progressed */
+        if *file.offset() != 0 {
+            return offset_mismatch(
+                req.rocket, meteoritus, vault, id, req.offset,
+            );
+        }
+
+        if !matches_declared_type(&file, &data) {
+            terminate_upload(req.rocket, meteoritus, vault, id);
+
+            return UploadResponder::Failure(
+                MeteoritusError::ContentTypeMismatch,
+            );
+        }
+    }
+
+    let Ok(file) = vault.get_file(id) else {
+        return UploadResponder::Failure(MeteoritusError::NotFound);
+    };
+
+    /* Chunks of encrypted uploads are stored as ciphertext */
+    let cipher = match meteoritus.upload_cipher(&file) {
+        Ok(cipher) => cipher,
+        Err(error) => return UploadResponder::Failure(error),
+    };
+
+    /* The full file checksum covers the plaintext, hashed before the write */
+    let digest = match expected_digest(meteoritus, file.metadata()) {
+        Ok(Some(_)) => {
+            meteoritus.rolling_digests().update(id, req.offset, &data)
+        }
+        _ => None,
+    };
+
+    if let Some(cipher) = &cipher {
+        cipher.apply(req.offset, &mut data);
+    }
+
+    /* The storage error is not Send, so it must not outlive this block */
+    let result = {
+        let patched = vault.patch_file(
+            id,
+            &mut data,
+            req.offset,
+            meteoritus.expiration(),
+        );
+
+        drop(stream);
+
+        /* Nothing of a failed chunk is kept, the hash included */
+        if let (Err(_), Some(digest)) = (&patched, digest) {
+            meteoritus.rolling_digests().restore(id, digest);
+        }
+
+        match patched {
+            Ok(result) => result,
+            Err(VaultError::OffsetMismatch(_)) => {
+                return offset_mismatch(
+                    req.rocket, meteoritus, vault, id, req.offset,
+                );
+            }
+            Err(VaultError::Transient(_)) => {
+                return UploadResponder::Failure(
+                    MeteoritusError::StorageUnavailable,
+                )
+            }
+            Err(_) => {
+                return UploadResponder::Failure(
+                    MeteoritusError::UnprocessableChunk,
+                )
+            }
+        }
     };
 
-    let final_offset = match result {
-        PatchOption::Patched(offset) => offset,
+    meteoritus.reset_chunk_failures(id);
+
+    match result {
+        PatchOption::Patched(offset) => {
+            if let Some(key) = req.idempotency_key {
+                meteoritus.remember_patch(id, key, req.offset, offset);
+            }
+
+            let expires_at = match meteoritus.expiration().is_enabled() {
+                true => vault
+                    .get_file(id)
+                    .ok()
+                    .and_then(|f| f.expires_with(meteoritus.expiration())),
+                false => None,
+            };
+
+            UploadResponder::Success(offset, expires_at)
+        }
         PatchOption::Completed(file) => {
-            if let Some(callback) = &meteoritus.on_completed() {
-                callback(HandlerContext {
-                    rocket: req.rocket,
-                    file_info: &file,
-                });
+            if !verify_checksum(meteoritus, &file, cipher).await {
+                terminate_upload(req.rocket, meteoritus, vault, id);
+
+                return UploadResponder::Failure(
+                    MeteoritusError::ChecksumMismatch,
+                );
+            }
+
+            let finalized = finalize(req.rocket, meteoritus, vault, *file);
+
+            let file = match finalized.await {
+                Ok(file) => file,
+                Err(_) => {
+                    return UploadResponder::Failure(MeteoritusError::Internal)
+                }
             };
 
-            if meteoritus.auto_terminate() {
+            /* Kept uploads are held for the scan before being fanned out or handed over */
+            let scanning = !meteoritus.auto_terminate()
+                && meteoritus.scan_upload(vault, &file);
+
+            if !scanning {
+                fan_out(meteoritus.fan_out_paths(), &file).await;
+            }
+
+            meteoritus.record_usage(&file);
+            meteoritus.emit(EventKind::Completed, &file);
+
+            let handled = match &meteoritus.on_completed() {
+                Some(callback) => guard_callback("on_completed", || {
+                    callback(HandlerContext {
+                        rocket: req.rocket,
+                        file_info: &file,
+                    })
+                })
+                .is_some(),
+                None => true,
+            };
+
+            let location = match &meteoritus.resource_location() {
+                Some(callback) => guard_callback("resource_location", || {
+                    callback(HandlerContext {
+                        rocket: req.rocket,
+                        file_info: &file,
+                    })
+                })
+                .flatten(),
+                None => None,
+            };
+
+            /* A panicking on_completed may not have saved the file, so it is kept */
+            if meteoritus.auto_terminate() && handled {
                 if let Err(_) = vault.terminate_file(id) {
-                    return UploadResponder::Failure(
-                        Status::InternalServerError,
-                    );
+                    return UploadResponder::Failure(MeteoritusError::Internal);
                 };
+            } else {
+                meteoritus.evict_completed(req.rocket, &file);
+
+                /* Only kept because on_completed panicked, so the scan starts after the hand-off */
+                if !scanning {
+                    meteoritus.scan_upload(vault, &file);
+                }
+            }
+
+            /* Only a fully processed completion is acknowledged to a retry */
+            if let Some(key) = req.idempotency_key {
+                meteoritus.remember_patch(id, key, req.offset, *file.length());
+            }
+
+            UploadResponder::Completed(*file.length(), location)
+        }
+    }
+}
+
+/// Answers a chunk sent at another offset than the upload offset.
+fn offset_mismatch(
+    rocket: &Rocket<Orbit>,
+    meteoritus: &Meteoritus<Orbit>,
+    vault: &Arc<dyn Vault>,
+    id: &str,
+    offset: u64,
+) -> UploadResponder {
+    if let Some(callback) = &meteoritus.on_offset_mismatch() {
+        if let Ok(file) = vault.get_file(id) {
+            let context = HandlerContext {
+                rocket,
+                file_info: &file,
+            };
+
+            guard_callback("on_offset_mismatch", || callback(context, offset));
+        }
+    }
+
+    reject_chunk(
+        rocket,
+        meteoritus,
+        vault,
+        id,
+        MeteoritusError::OffsetMismatch,
+    )
+}
+
+/// Answers a rejected chunk, aborting the upload once too many failed in a row.
+fn reject_chunk(
+    rocket: &Rocket<Orbit>,
+    meteoritus: &Meteoritus<Orbit>,
+    vault: &Arc<dyn Vault>,
+    id: &str,
+    error: MeteoritusError,
+) -> UploadResponder {
+    if !meteoritus.record_chunk_failure(id) {
+        return UploadResponder::Failure(error);
+    }
+
+    warn!("Aborting upload {} after too many failed chunks", id);
+    terminate_upload(rocket, meteoritus, vault, id);
+
+    UploadResponder::Failure(MeteoritusError::Gone)
+}
+
+/// Terminates a rejected upload, notifying the event sink and the termination callback.
+fn terminate_upload(
+    rocket: &Rocket<Orbit>,
+    meteoritus: &Meteoritus<Orbit>,
+    vault: &Arc<dyn Vault>,
+    id: &str,
+) {
+    meteoritus.rolling_digests().forget(id);
+
+    if let Ok(file) = vault.terminate_file(id) {
+        meteoritus.emit(EventKind::Terminated, &file);
+
+        if let Some(callback) = &meteoritus.on_termination() {
+            guard_callback("on_termination", || {
+                callback(HandlerContext {
+                    rocket,
+                    file_info: &file,
+                })
+            });
+        }
+    }
+}
+
+/// Reads the chunk into a buffer preallocated with `capacity`, when known.
+async fn read_chunk(
+    data: Data<'_>,
+    limit: ByteUnit,
+    capacity: Option<usize>,
+) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::with_capacity(capacity.unwrap_or_default());
+    data.open(limit).read_to_end(&mut buf).await?;
+
+    Ok(buf)
+}
+
+/// Sizes the chunk buffer after the client hint, without exceeding the remaining bytes.
+fn buffer_capacity(file: &FileInfo<Created>) -> Option<usize> {
+    let remaining = file.length().saturating_sub(*file.offset());
+    let capacity = file.chunk_size().map(|size| size.min(remaining))?;
+
+    usize::try_from(capacity).ok()
+}
+
+/// Checks a `Content-Range: bytes <start>-<end>/<total>` header against the chunk.
+fn matches_content_range(
+    content_range: &str,
+    offset: u64,
+    data: &[u8],
+    file: &FileInfo<Created>,
+) -> bool {
+    let Some(range) = content_range.trim().strip_prefix("bytes ") else {
+        return false;
+    };
+
+    let Some((range, total)) = range.split_once('/') else {
+        return false;
+    };
+
+    let Some((start, end)) = range.split_once('-') else {
+        return false;
+    };
+
+    let (Ok(start), Ok(end)) = (start.parse::<u64>(), end.parse::<u64>())
+    else {
+        return false;
+    };
+
+    let total_matches = match total {
+        "*" => true,
+        total => total.parse::<u64>().is_ok_and(|t| t == *file.length()),
+    };
+
+    start == offset
+        && end >= start
+        && end - start + 1 == data.len() as u64
+        && total_matches
+}
+
+/// Moves the completed file to its final destination, if one is configured.
+async fn finalize(
+    rocket: &Rocket<Orbit>,
+    meteoritus: &Meteoritus<Orbit>,
+    vault: &Arc<dyn Vault>,
+    file: FileInfo<Completed>,
+) -> Result<FileInfo<Completed>, VaultError> {
+    let Some(destination) = meteoritus.destination() else {
+        /* Routed by metadata first, then to the completed path */
+        let directory = meteoritus
+            .route_destination(file.metadata())
+            .flatten()
+            .or(meteoritus.completed_path());
+
+        return match directory {
+            Some(path) => {
+                let destination = Path::new(path).join(file.id());
+                move_completed(meteoritus, vault, &file, destination).await
+            }
+            None => Ok(file),
+        };
+    };
+
+    /* The callback resolves collisions, the move itself never overwrites */
+    for attempt in 0..meteoritus.destination_attempts() {
+        let context = HandlerContext {
+            rocket,
+            file_info: &file,
+        };
+
+        /* The upload stays complete, unmoved, if the callback panics */
+        let Some(path) =
+            guard_callback("destination", || destination(context, attempt))
+        else {
+            return Ok(file);
+        };
+
+        match move_completed(meteoritus, vault, &file, path).await {
+            Err(VaultError::DestinationExists) => continue,
+            result => return result,
+        }
+    }
+
+    Err(VaultError::DestinationExists)
+}
+
+/// Moves the completed file on the blocking thread pool, since it may be copied across
+/// filesystems.
+async fn move_completed(
+    meteoritus: &Meteoritus<Orbit>,
+    vault: &Arc<dyn Vault>,
+    file: &FileInfo<Completed>,
+    destination: PathBuf,
+) -> Result<FileInfo<Completed>, VaultError> {
+    let (vault, file_id) = (vault.to_owned(), file.id().to_string());
+    let mut report = meteoritus.moving_reporter(file);
+
+    /* The storage error is not Send, so only its description leaves the pool */
+    let moved = spawn_blocking(move || {
+        let mut reported = 0;
+
+        let mut progress = |moved: u64| {
+            if moved - reported >= MOVING_REPORT_INTERVAL {
+                reported = moved;
+                report(moved);
+            }
+        };
+
+        vault
+            .finalize_file(&file_id, &destination, &mut progress)
+            .map_err(|e| match e {
+                VaultError::DestinationExists => None,
+                e => Some(format!("{:?}", e)),
+            })
+    });
+
+    match moved.await {
+        Ok(Ok(file)) => Ok(file),
+        Ok(Err(None)) => Err(VaultError::DestinationExists),
+        Ok(Err(Some(e))) => {
+            error!("Failed to move upload {}: {}", file.id(), e);
+            Err(VaultError::Error)
+        }
+        Err(_) => Err(VaultError::Error),
+    }
+}
+
+/// Links the completed file into every fan-out directory, on the blocking thread pool.
+///
+/// Either every directory gets the file or none does, the links already made are removed
+/// when one of them fails. Failures are logged, the upload is complete regardless.
+pub(crate) async fn fan_out(
+    paths: &'static [&'static str],
+    file: &FileInfo<Completed>,
+) {
+    if paths.is_empty() {
+        return;
+    }
+
+    let (source, id) = (PathBuf::from(file.file_name()), file.id().to_owned());
+
+    let fanned_out = spawn_blocking(move || {
+        let mut linked = Vec::with_capacity(paths.len());
+
+        for path in paths {
+            let destination = Path::new(path).join(&id);
+
+            if let Err(e) = link_file(&source, &destination) {
+                for destination in linked {
+                    let _ = std::fs::remove_file(destination);
+                }
+
+                return Err(format!("{} into {}: {}", id, path, e));
+            }
+
+            linked.push(destination);
+        }
+
+        Ok(())
+    })
+    .await;
+
+    match fanned_out {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => error!("Failed to fan out {}", e),
+        Err(e) => error!("Failed to fan out {}: {}", file.id(), e),
+    }
+}
+
+/// Removes the links of a quarantined upload from the fan-out directories.
+pub(crate) fn unlink_fan_out(paths: &[&str], id: &str) {
+    for path in paths {
+        match std::fs::remove_file(Path::new(path).join(id)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                error!("Failed to unlink {} from {}: {}", id, path, e)
             }
+            _ => {}
+        }
+    }
+}
+
+/// Returns the full-file SHA-256 digest supplied in the checksum metadata key, if any.
+///
+/// The value must decode to a hex encoded digest, anything else is rejected rather than
+/// skipping the verification.
+pub(crate) fn expected_digest(
+    meteoritus: &Meteoritus<Orbit>,
+    metadata: &Option<Metadata>,
+) -> Result<Option<Vec<u8>>, MeteoritusError> {
+    let (Some(key), Some(metadata)) = (meteoritus.checksum_key(), metadata)
+    else {
+        return Ok(None);
+    };
 
-            *file.length()
+    match metadata.get_raw(key) {
+        Ok(digest)
+            if digest.len() == 64
+                && digest.iter().all(u8::is_ascii_hexdigit) =>
+        {
+            Ok(Some(digest))
         }
+        Err(MetadataError::InvalidKey) => Ok(None),
+        _ => Err(MeteoritusError::InvalidChecksum),
+    }
+}
+
+/// Checks the completed file against the digest supplied in its metadata, if any.
+///
+/// The digest hashed while the chunks arrived is used when complete, otherwise the
+/// file is hashed on the blocking thread pool, decrypted first if it is encrypted.
+async fn verify_checksum(
+    meteoritus: &Meteoritus<Orbit>,
+    file: &FileInfo<Completed>,
+    cipher: Option<UploadCipher>,
+) -> bool {
+    let expected = match expected_digest(meteoritus, file.metadata()) {
+        Ok(Some(expected)) => expected,
+        Ok(None) => return true,
+        Err(_) => return false,
     };
 
-    UploadResponder::Success(final_offset)
+    let length = *file.length();
+
+    if let Some(digest) = meteoritus.rolling_digests().finish(file.id(), length)
+    {
+        return digest.as_bytes().eq_ignore_ascii_case(&expected);
+    }
+
+    let path = file.file_name().to_owned();
+
+    let digest = spawn_blocking(move || match cipher {
+        Some(cipher) => cipher.plaintext_digest(Path::new(&path)),
+        None => sha256_digest(path),
+    });
+
+    match digest.await {
+        Ok(Ok(digest)) => digest.as_bytes().eq_ignore_ascii_case(&expected),
+        _ => false,
+    }
+}
+
+/// Checks a chunk against its `Upload-Checksum` header, like `sha1 <Base64 digest>`.
+///
+/// The algorithm must be the one declared on creation, if the client declared one.
+fn verify_chunk_checksum(
+    checksum: &str,
+    file: &FileInfo<Created>,
+    data: &[u8],
+) -> Result<(), MeteoritusError> {
+    let Some((algorithm, expected)) = checksum.trim().split_once(' ') else {
+        return Err(MeteoritusError::InvalidChecksum);
+    };
+
+    if let Some(declared) = file.checksum_algorithm() {
+        if declared != algorithm {
+            return Err(MeteoritusError::InvalidChecksum);
+        }
+    }
+
+    let Some(digest) = checksum_digest(algorithm, data) else {
+        return Err(MeteoritusError::InvalidChecksum);
+    };
+
+    match STANDARD.decode(expected.trim()) {
+        Ok(expected) if expected == digest => Ok(()),
+        Ok(_) => Err(MeteoritusError::ChecksumMismatch),
+        Err(_) => Err(MeteoritusError::InvalidChecksum),
+    }
+}
+
+/// Compares the sniffed type of the first chunk with the declared `filetype`.
+#[cfg(feature = "sniff")]
+fn matches_declared_type(file: &FileInfo<Created>, data: &[u8]) -> bool {
+    let Some(detected) = sniff_content_type(data) else {
+        return true;
+    };
+
+    let declared = file
+        .metadata()
+        .as_ref()
+        .and_then(|metadata| metadata.get_raw("filetype").ok())
+        .and_then(|raw| String::from_utf8(raw).ok());
+
+    match declared {
+        Some(declared) => {
+            let essence = declared.split(';').next().unwrap_or_default();
+            essence.trim().eq_ignore_ascii_case(detected)
+        }
+        None => true,
+    }
+}
+
+/// Checks the media type essence, since clients and proxies may append parameters.
+fn is_offset_stream(content_type: &ContentType) -> bool {
+    content_type.top() == "application"
+        && content_type.sub() == "offset+octet-stream"
 }
 
 #[derive(Debug)]
 pub struct UploadRequest<'r> {
     rocket: &'r Rocket<Orbit>,
     offset: u64,
+    upload_length: Option<u64>,
+    idempotency_key: Option<&'r str>,
+    content_range: Option<&'r str>,
+    checksum: Option<&'r str>,
 }
 
 #[rocket::async_trait]
@@ -69,12 +734,21 @@ impl<'r> FromRequest<'r> for UploadRequest<'r> {
     async fn from_request(
         req: &'r Request<'_>,
     ) -> request::Outcome<Self, Self::Error> {
+        let meteoritus = req.rocket().state::<Meteoritus<Orbit>>().unwrap();
+
+        if has_conflicting_headers(req) {
+            return Outcome::Failure((
+                meteoritus.error_status(MeteoritusError::ConflictingHeaders),
+                "Conflicting tus headers",
+            ));
+        }
+
         let tus_resumable_header = req.headers().get_one("Tus-Resumable");
         if tus_resumable_header.is_none()
             || tus_resumable_header.unwrap() != "1.0.0"
         {
             return Outcome::Failure((
-                Status::BadRequest,
+                meteoritus.error_status(MeteoritusError::InvalidTusResumable),
                 "Missing or invalid Tus-Resumable header",
             ));
         }
@@ -84,35 +758,47 @@ impl<'r> FromRequest<'r> for UploadRequest<'r> {
                 Ok(value) => value,
                 Err(_) => {
                     return Outcome::Failure((
-                        Status::BadRequest,
+                        meteoritus
+                            .error_status(MeteoritusError::InvalidUploadOffset),
                         "Invalid Upload-Offset header",
                     ))
                 }
             },
             None => {
                 return Outcome::Failure((
-                    Status::BadRequest,
+                    meteoritus
+                        .error_status(MeteoritusError::InvalidUploadOffset),
                     "Missing Upload-Offset header",
                 ))
             }
         };
 
+        let upload_length = match req.headers().get_one("Upload-Length") {
+            Some(value) => match value.parse::<u64>() {
+                Ok(value) => Some(value),
+                Err(_) => {
+                    return Outcome::Failure((
+                        meteoritus
+                            .error_status(MeteoritusError::InvalidUploadLength),
+                        "Invalid Upload-Length header",
+                    ))
+                }
+            },
+            None => None,
+        };
+
         match req.content_type() {
             None => {
                 return Outcome::Failure((
-                    Status::BadRequest,
+                    meteoritus
+                        .error_status(MeteoritusError::MissingContentType),
                     "Missing Content-Type header",
                 ))
             }
-            Some(value)
-                if value
-                    != &ContentType::new(
-                        "application",
-                        "offset+octet-stream",
-                    ) =>
-            {
+            Some(value) if !is_offset_stream(value) => {
                 return Outcome::Failure((
-                    Status::UnsupportedMediaType,
+                    meteoritus
+                        .error_status(MeteoritusError::InvalidContentType),
                     "Invalid Content-Type header",
                 ))
             }
@@ -122,6 +808,16 @@ impl<'r> FromRequest<'r> for UploadRequest<'r> {
         let upload_values = UploadRequest {
             rocket: req.rocket(),
             offset,
+            upload_length,
+            idempotency_key: req.headers().get_one("Idempotency-Key"),
+            content_range: match meteoritus.validates_content_range() {
+                true => req.headers().get_one("Content-Range"),
+                false => None,
+            },
+            checksum: match meteoritus.chunk_checksums() {
+                true => req.headers().get_one("Upload-Checksum"),
+                false => None,
+            },
         };
 
         Outcome::Success(upload_values)
@@ -129,8 +825,10 @@ impl<'r> FromRequest<'r> for UploadRequest<'r> {
 }
 
 pub enum UploadResponder {
-    Success(u64),
-    Failure(Status),
+    Success(u64, Option<SystemTime>),
+    Completed(u64, Option<String>),
+    Busy(Duration),
+    Failure(MeteoritusError),
 }
 
 impl<'r> Responder<'r, 'static> for UploadResponder {
@@ -142,11 +840,31 @@ impl<'r> Responder<'r, 'static> for UploadResponder {
         res.header(meteoritus.get_protocol_resumable_version());
 
         match self {
-            Self::Success(offset) => {
+            Self::Success(offset, expires_at) => {
+                if let Some(expires_at) = expires_at {
+                    res.header(MeteoritusHeaders::Expires(expires_at));
+                }
+
                 res.status(Status::NoContent);
                 res.raw_header("Upload-Offset", offset.to_string())
             }
-            Self::Failure(status) => res.status(status),
+            Self::Completed(offset, location) => {
+                if let Some(location) = location {
+                    res.raw_header("X-Resource-Location", location);
+                }
+
+                res.status(Status::NoContent);
+                res.raw_header("Upload-Offset", offset.to_string())
+            }
+            Self::Busy(retry_after) => {
+                let seconds = retry_after.as_secs_f64().ceil() as u64;
+
+                res.status(
+                    meteoritus.error_status(MeteoritusError::TooManyStreams),
+                );
+                res.raw_header("Retry-After", seconds.max(1).to_string())
+            }
+            Self::Failure(error) => res.status(meteoritus.error_status(error)),
         };
 
         res.ok()
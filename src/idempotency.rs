@@ -0,0 +1,148 @@
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// An acknowledged `PATCH` request, as `(offset, resulting offset, seen at)`.
+type PatchRecord = (u64, u64, Instant);
+
+/// A creation, as `(fingerprint, upload id once created, seen at)`.
+type CreationRecord = (String, Option<String>, Instant);
+
+/// A creation key, scoped to the client that sent it.
+type CreationKey = (Option<IpAddr>, String);
+
+/// The outcome of reserving the `Idempotency-Key` of a creation.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Reservation {
+    /// The key is new, the upload can be created.
+    Reserved,
+    /// An upload was already created with the key, identified by its id.
+    Created(String),
+    /// A creation with the key is still in flight.
+    Pending,
+    /// The key was used for a creation with another `Upload-Length` or `Upload-Metadata`.
+    Mismatch,
+}
+
+/// Remembers the `Idempotency-Key` of recently applied `PATCH` requests per upload, and
+/// of recent creations.
+#[derive(Debug, Clone)]
+pub(crate) struct IdempotencyCache {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<(String, String), PatchRecord>>>,
+    creations: Arc<Mutex<HashMap<CreationKey, CreationRecord>>>,
+}
+
+impl IdempotencyCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Default::default(),
+            creations: Default::default(),
+        }
+    }
+
+    /// Returns the resulting offset of an already applied request with the same key and offset.
+    pub(crate) fn replay(
+        &self,
+        id: &str,
+        key: &str,
+        offset: u64,
+    ) -> Option<u64> {
+        let entries = self.entries.lock().ok()?;
+
+        match entries.get(&(id.to_string(), key.to_string())) {
+            Some((seen_offset, resulting, seen_at))
+                if *seen_offset == offset && seen_at.elapsed() < self.ttl =>
+            {
+                Some(*resulting)
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn remember(
+        &self,
+        id: &str,
+        key: &str,
+        offset: u64,
+        resulting: u64,
+    ) {
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+
+        entries.retain(|_, (_, _, seen_at)| seen_at.elapsed() < self.ttl);
+        entries.insert(
+            (id.to_string(), key.to_string()),
+            (offset, resulting, Instant::now()),
+        );
+    }
+
+    /// Reserves the key of a creation sent by `client`, unless it was used within the ttl.
+    ///
+    /// A key already used for the same `fingerprint` resolves to the upload created with
+    /// it, as long as `exists` still finds it.
+    pub(crate) fn reserve_creation(
+        &self,
+        client: Option<IpAddr>,
+        key: &str,
+        fingerprint: &str,
+        exists: impl Fn(&str) -> bool,
+    ) -> Reservation {
+        let Ok(mut creations) = self.creations.lock() else {
+            return Reservation::Reserved;
+        };
+
+        creations.retain(|_, (_, _, seen_at)| seen_at.elapsed() < self.ttl);
+
+        let entry = (client, key.to_string());
+        match creations.get(&entry) {
+            Some((seen, _, _)) if seen != fingerprint => {
+                return Reservation::Mismatch
+            }
+            Some((_, None, _)) => return Reservation::Pending,
+            Some((_, Some(id), _)) if exists(id) => {
+                return Reservation::Created(id.to_owned())
+            }
+            _ => {}
+        }
+
+        creations
+            .insert(entry, (fingerprint.to_string(), None, Instant::now()));
+        Reservation::Reserved
+    }
+
+    /// Records the upload created with a reserved key.
+    pub(crate) fn complete_creation(
+        &self,
+        client: Option<IpAddr>,
+        key: &str,
+        id: &str,
+    ) {
+        let Ok(mut creations) = self.creations.lock() else {
+            return;
+        };
+
+        if let Some((_, created, _)) =
+            creations.get_mut(&(client, key.to_string()))
+        {
+            *created = Some(id.to_string());
+        }
+    }
+
+    /// Releases a reserved key whose creation failed, so it can be retried.
+    pub(crate) fn release_creation(&self, client: Option<IpAddr>, key: &str) {
+        let Ok(mut creations) = self.creations.lock() else {
+            return;
+        };
+
+        let entry = (client, key.to_string());
+        if let Some((_, None, _)) = creations.get(&entry) {
+            creations.remove(&entry);
+        }
+    }
+}
@@ -57,6 +57,24 @@
 //!         .mount("/", routes![hello])
 //! }
 //! ```
+//!
+//! ## Resumable downloads
+//!
+//! With the `download` feature, completed uploads can be downloaded from their upload url,
+//! which is an extension beyond the tus core protocol. A client learns the total size from
+//! the `Upload-Length` of a `HEAD` request, then sends `GET` requests with a single `Range`
+//! header, answered with `206 Partial Content`. Every response reports the offset to resume
+//! from in a `X-Download-Offset` header, mirroring `Upload-Offset`. Uploads can only be
+//! downloaded while they are kept, see [`Meteoritus::keep_on_disk()`].
+//!
+//! Downloads are served from the origin of the application, so only images, audio, video
+//! and plain text are displayed inline. Other uploads are served as attachments, with a
+//! declared `filetype` replaced by `application/octet-stream`.
+//!
+//! ```toml
+//! [dependencies]
+//! meteoritus = { version = "0.2.0", features = ["download"] }
+//! ```
 //! [`Rocket`]: https://api.rocket.rs/v0.5-rc/rocket/index.html
 //! [`Fairing`]: https://api.rocket.rs/v0.5-rc/rocket/fairing/index.html
 
@@ -67,25 +85,62 @@
 extern crate rocket;
 
 use rocket::http::Header;
+use std::time::SystemTime;
 
 mod meteoritus;
 pub use crate::meteoritus::Meteoritus;
 
+mod error;
+pub use crate::error::MeteoritusError;
+
+mod event;
+pub use crate::event::{EventKind, EventSink, MeteoritusEvent};
+
+mod eviction;
+
 mod fs;
 pub use crate::fs::{
-    Built, Completed, Created, FileInfo, Metadata, MetadataError, Terminated,
-    Vault,
+    Built, Completed, Created, Expiration, FileInfo, FilesystemInodes,
+    InMemoryKeyProvider, InodeStats, KeyCollision, KeyProvider, LocalVault,
+    Metadata, MetadataError, MetadataOptions, PatchOption, ScanState,
+    Terminated, UploadStats, Vault, VaultError,
 };
 
+mod digests;
+
+mod failures;
+
 mod handlers;
-pub use crate::handlers::HandlerContext;
+pub use crate::handlers::{HandlerContext, LocationFormat};
+
+mod idempotency;
+
+#[cfg(feature = "nats")]
+mod nats;
+#[cfg(feature = "nats")]
+pub use crate::nats::NatsSink;
+
+mod readiness;
+pub use crate::readiness::{Readiness, ReadinessStatus};
+
+mod routing;
+
+mod scan;
+pub use crate::scan::{ScanVerdict, UploadScanner};
+
+mod transform;
+pub use crate::transform::BodyTransform;
+
+mod usage;
+pub use crate::usage::{UploadUsage, UsageReport};
 
 /// Represents the tus protocol headers.
 pub enum MeteoritusHeaders {
     MaxSize(u64),
-    Extensions(&'static [&'static str]),
+    Extensions(Vec<&'static str>),
     Version(&'static [&'static str]),
     Resumable(&'static str),
+    Expires(SystemTime),
 }
 
 impl Into<Header<'_>> for MeteoritusHeaders {
@@ -103,6 +158,9 @@ impl Into<Header<'_>> for MeteoritusHeaders {
             MeteoritusHeaders::Resumable(ver) => {
                 Header::new("Tus-Resumable", ver)
             }
+            MeteoritusHeaders::Expires(at) => {
+                Header::new("Upload-Expires", httpdate::fmt_http_date(at))
+            }
         }
     }
 }
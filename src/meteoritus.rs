@@ -1,17 +1,57 @@
-use std::{error::Error, marker::PhantomData, sync::Arc};
+use std::{
+    collections::HashMap,
+    error::Error,
+    marker::PhantomData,
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
 
 use rocket::{
     data::ByteUnit,
     fairing::{self, Fairing, Info, Kind},
-    Build, Ignite, Orbit, Phase, Rocket,
+    http::Status,
+    tokio::{
+        sync::{Notify, OwnedSemaphorePermit, Semaphore},
+        task::spawn_blocking,
+        time::interval,
+    },
+    Build, Ignite, Orbit, Phase, Request, Response, Rocket,
+};
+
+#[cfg(feature = "download")]
+use rocket::tokio::{
+    pin,
+    time::{timeout_at, Instant},
 };
 
 use crate::{
-    fs::Terminated,
+    digests::RollingDigests,
+    error::MeteoritusError,
+    event::{EventBus, EventKind, EventSink, MeteoritusEvent},
+    eviction::CompletedQueue,
+    failures::FailureCounter,
+    fs::{
+        is_valid_namespace, Expiration, FileInfo, FilesystemInodes, InodeStats,
+        KeyProvider, MetadataOptions, RetryVault, ScanState, Terminated,
+        UploadCipher,
+    },
     handlers::{
-        creation_handler, file_info_handler, info_handler, termination_handler,
-        upload_handler,
+        creation_handler, fan_out, file_info_handler, file_options_handler,
+        guard_callback, info_handler, method_not_allowed_routes,
+        readiness_handler, termination_handler, unlink_fan_out, upload_handler,
+        LocationFormat, CORS_EXPOSED_HEADERS,
     },
+    idempotency::{IdempotencyCache, Reservation},
+    readiness::{count_in_progress, Readiness},
+    routing::DestinationRoutes,
+    scan::{ScanVerdict, UploadScanner},
+    transform::BodyTransform,
+    usage::{UsageLedger, UsageReport},
 };
 
 #[allow(unused_imports)]
@@ -21,6 +61,36 @@ use crate::{
     MeteoritusHeaders, Vault,
 };
 
+/// Callback validating a creation request before the upload is created.
+type CreationCallback = Arc<
+    dyn Fn(HandlerContext<Built>) -> Result<(), Box<dyn Error>> + Send + Sync,
+>;
+
+/// Callback notified of created uploads.
+type CreatedCallback = Arc<dyn Fn(HandlerContext<Created>) + Send + Sync>;
+
+/// Callback notified of completed uploads.
+type CompletedCallback = Arc<dyn Fn(HandlerContext<Completed>) + Send + Sync>;
+
+/// Callback notified of terminated uploads.
+type TerminationCallback =
+    Arc<dyn Fn(HandlerContext<Terminated>) + Send + Sync>;
+
+/// Callback computing the url of the finalized resource of a completed upload.
+type ResourceLocationCallback =
+    Arc<dyn Fn(HandlerContext<Completed>) -> Option<String> + Send + Sync>;
+
+/// Callback notified of the offset sent by a client on an offset mismatch.
+type OffsetMismatchCallback =
+    Arc<dyn Fn(HandlerContext<Created>, u64) + Send + Sync>;
+
+/// Callback choosing the final path of a completed upload, given the attempt number.
+type DestinationCallback =
+    Arc<dyn Fn(HandlerContext<Completed>, u32) -> PathBuf + Send + Sync>;
+
+/// Callback notified of completed uploads evicted by the maximum count.
+type EvictionCallback = Arc<dyn Fn(HandlerContext<Terminated>) + Send + Sync>;
+
 /// The tus fairing itself.
 ///
 /// # Phases
@@ -52,6 +122,15 @@ use crate::{
 ///   An instance in the [`Orbit`] phase represents a _running_ middleware,
 ///   actively serving requests.
 ///
+/// # Callbacks
+///
+/// A panicking callback never fails the request it runs in, the panic is logged and the
+/// request carries on with a safe default. A panicking [`Meteoritus::on_creation()`] rejects
+/// the upload, a panicking [`Meteoritus::on_completed()`] keeps the completed upload on disk
+/// instead of auto-terminating it, and a panicking [`Meteoritus::with_destination()`] leaves
+/// it unmoved. Panics of other callbacks are ignored. An [`EventSink`] panicking only loses
+/// the event it was publishing.
+///
 /// # Launching
 ///
 /// In order to launch a [`Meteoritus`] middleware an instance of [`Meteoritus<Ignite>`] _must_ be
@@ -144,20 +223,72 @@ use crate::{
 #[derive(Clone)]
 pub struct Meteoritus<P: Phase> {
     auto_terminate: bool,
+    strict_termination: bool,
+    soft_termination: Option<Duration>,
+    progress_header: bool,
+    dry_run: bool,
+    content_range: bool,
+    empty_uploads: bool,
+    sniff_content: bool,
     base_route: &'static str,
+    cors_origin: &'static str,
+    node_id: Option<&'static str>,
+    trusted_proxies: usize,
+    captured_headers: &'static [&'static str],
+    location_format: LocationFormat,
     max_size: ByteUnit,
+    min_chunk_size: Option<ByteUnit>,
+    preferred_chunk_size: Option<ByteUnit>,
+    checksum_key: Option<&'static str>,
+    chunk_checksums: bool,
+    metadata_options: MetadataOptions,
+    expiration: Expiration,
+    completed_retention: Option<Duration>,
+    completed_path: Option<&'static str>,
+    destination_attempts: u32,
+    destination_routes: Option<DestinationRoutes>,
+    max_completed: Option<CompletedQueue>,
+    fan_out_paths: &'static [&'static str],
+    sweep_interval: Duration,
+    error_statuses: HashMap<MeteoritusError, Status>,
+    temp_path: &'static str,
+    min_free_inodes: Option<u64>,
+    inode_stats: Option<Arc<dyn InodeStats>>,
+    custom_vault: bool,
+    random_file_names: bool,
+    durable_writes: bool,
+    storage_retries: u32,
+    retry_backoff: Duration,
+    lookup_timeout: Duration,
+    lookups: Arc<Semaphore>,
+    patch_streams: Option<(Arc<Semaphore>, Duration)>,
+    detect_content_type: bool,
+    ring_capacity: Option<u64>,
+    downloads: bool,
+    event_sink: Option<Arc<dyn EventSink>>,
+    body_transform: Option<Arc<dyn BodyTransform>>,
+    scanner: Option<Arc<dyn UploadScanner>>,
+    scan_timeout: Duration,
+    scan_verdicts: Arc<Notify>,
+    event_buffer: usize,
+    events: Option<EventBus>,
+    usage: Option<UsageLedger>,
+    namespace_key: Option<&'static str>,
+    key_provider: Option<Arc<dyn KeyProvider>>,
+    idempotency: Option<IdempotencyCache>,
+    chunk_failures: Option<FailureCounter>,
+    digests: RollingDigests,
+    readiness_path: Option<&'static str>,
+    draining: Arc<AtomicBool>,
     vault: Arc<dyn Vault>,
-    on_creation: Option<
-        Arc<
-            dyn Fn(HandlerContext<Built>) -> Result<(), Box<dyn Error>>
-                + Send
-                + Sync,
-        >,
-    >,
-    on_created: Option<Arc<dyn Fn(HandlerContext<Created>) + Send + Sync>>,
-    on_completed: Option<Arc<dyn Fn(HandlerContext<Completed>) + Send + Sync>>,
-    on_termination:
-        Option<Arc<dyn Fn(HandlerContext<Terminated>) + Send + Sync>>,
+    on_creation: Option<CreationCallback>,
+    on_created: Option<CreatedCallback>,
+    on_completed: Option<CompletedCallback>,
+    on_termination: Option<TerminationCallback>,
+    resource_location: Option<ResourceLocationCallback>,
+    on_offset_mismatch: Option<OffsetMismatchCallback>,
+    on_eviction: Option<EvictionCallback>,
+    destination: Option<DestinationCallback>,
     state: std::marker::PhantomData<P>,
 }
 
@@ -170,13 +301,62 @@ impl<P: Phase> Meteoritus<P> {
         MeteoritusHeaders::Resumable("1.0.0")
     }
 
+    /// Returns the tus extensions supported by the current configuration.
     pub fn get_protocol_extensions(&self) -> MeteoritusHeaders {
-        MeteoritusHeaders::Extensions(&["creation", "termination"])
+        let mut extensions = vec!["creation", "termination"];
+
+        if self.expiration.is_enabled() {
+            extensions.push("expiration");
+        }
+
+        if self.chunk_checksums {
+            extensions.push("checksum");
+        }
+
+        MeteoritusHeaders::Extensions(extensions)
     }
 
     pub fn get_protocol_max_size(&self) -> MeteoritusHeaders {
         MeteoritusHeaders::MaxSize(self.max_size.as_u64())
     }
+
+    /// Returns the option that can't work on the tail kept by a ring buffer, if any.
+    #[cfg(feature = "ring")]
+    fn ring_conflict(&self) -> Option<&'static str> {
+        self.ring_capacity?;
+
+        if self.downloads {
+            return Some("downloads");
+        }
+
+        if self.key_provider.is_some() {
+            return Some("encryption");
+        }
+
+        if self.checksum_key.is_some() || self.chunk_checksums {
+            return Some("checksums");
+        }
+
+        None
+    }
+
+    /// Indicates if options only honored by the local vault are configured.
+    fn has_local_vault_options(&self) -> bool {
+        #[allow(unused_mut)]
+        let mut configured = self.random_file_names || self.durable_writes;
+
+        #[cfg(feature = "sniff")]
+        {
+            configured |= self.detect_content_type;
+        }
+
+        #[cfg(feature = "ring")]
+        {
+            configured |= self.ring_capacity.is_some();
+        }
+
+        configured
+    }
 }
 
 impl Meteoritus<Build> {
@@ -184,29 +364,156 @@ impl Meteoritus<Build> {
     pub fn new() -> Meteoritus<Build> {
         Meteoritus::<Build> {
             auto_terminate: true,
+            strict_termination: false,
+            soft_termination: Default::default(),
+            progress_header: false,
+            dry_run: false,
+            content_range: false,
+            empty_uploads: true,
+            sniff_content: false,
             base_route: "/meteoritus",
+            cors_origin: "*",
+            node_id: Default::default(),
+            trusted_proxies: 0,
+            captured_headers: &[],
+            location_format: Default::default(),
             max_size: ByteUnit::Megabyte(5),
+            min_chunk_size: Default::default(),
+            preferred_chunk_size: Default::default(),
+            checksum_key: Default::default(),
+            chunk_checksums: false,
+            metadata_options: Default::default(),
+            expiration: Default::default(),
+            completed_retention: Default::default(),
+            completed_path: Default::default(),
+            destination_attempts: 5,
+            destination_routes: Default::default(),
+            max_completed: Default::default(),
+            fan_out_paths: &[],
+            sweep_interval: Duration::from_secs(60),
+            error_statuses: Default::default(),
+            temp_path: "./tmp/files",
+            min_free_inodes: Default::default(),
+            inode_stats: Default::default(),
+            custom_vault: false,
+            random_file_names: false,
+            durable_writes: false,
+            storage_retries: 0,
+            retry_backoff: Duration::from_millis(100),
+            lookup_timeout: Duration::from_secs(30),
+            lookups: Arc::new(Semaphore::new(64)),
+            patch_streams: Default::default(),
+            detect_content_type: false,
+            ring_capacity: Default::default(),
+            downloads: cfg!(feature = "download"),
+            event_sink: Default::default(),
+            body_transform: Default::default(),
+            scanner: Default::default(),
+            scan_timeout: Duration::from_secs(30),
+            scan_verdicts: Default::default(),
+            event_buffer: 1024,
+            events: Default::default(),
+            usage: Default::default(),
+            namespace_key: Default::default(),
+            key_provider: Default::default(),
+            idempotency: Default::default(),
+            chunk_failures: Default::default(),
+            digests: Default::default(),
+            readiness_path: Default::default(),
+            draining: Default::default(),
             vault: Arc::new(LocalVault::new("./tmp/files")),
             on_creation: Default::default(),
             on_created: Default::default(),
             on_completed: Default::default(),
             on_termination: Default::default(),
+            resource_location: Default::default(),
+            on_offset_mismatch: Default::default(),
+            on_eviction: Default::default(),
+            destination: Default::default(),
             state: PhantomData::<Build>,
         }
     }
 
     /// Returns a instance of [`Meteoritus`] into the _[`Ignite`]_ phase.
     pub fn build(self) -> Meteoritus<Ignite> {
+        let vault: Arc<dyn Vault> = match self.storage_retries {
+            0 => self.vault,
+            attempts => Arc::new(RetryVault::new(
+                self.vault,
+                attempts,
+                self.retry_backoff,
+            )),
+        };
+
         Meteoritus::<Ignite> {
             state: std::marker::PhantomData,
             auto_terminate: self.auto_terminate,
+            strict_termination: self.strict_termination,
+            soft_termination: self.soft_termination,
+            progress_header: self.progress_header,
+            dry_run: self.dry_run,
+            content_range: self.content_range,
+            empty_uploads: self.empty_uploads,
+            sniff_content: self.sniff_content,
             base_route: self.base_route,
+            cors_origin: self.cors_origin,
+            node_id: self.node_id,
+            trusted_proxies: self.trusted_proxies,
+            captured_headers: self.captured_headers,
+            location_format: self.location_format,
             max_size: self.max_size,
-            vault: self.vault,
+            min_chunk_size: self.min_chunk_size,
+            preferred_chunk_size: self.preferred_chunk_size,
+            checksum_key: self.checksum_key,
+            chunk_checksums: self.chunk_checksums,
+            metadata_options: self.metadata_options,
+            expiration: self.expiration,
+            completed_retention: self.completed_retention,
+            completed_path: self.completed_path,
+            destination_attempts: self.destination_attempts,
+            destination_routes: self.destination_routes,
+            max_completed: self.max_completed,
+            fan_out_paths: self.fan_out_paths,
+            sweep_interval: self.sweep_interval,
+            error_statuses: self.error_statuses,
+            temp_path: self.temp_path,
+            min_free_inodes: self.min_free_inodes,
+            inode_stats: self.inode_stats,
+            custom_vault: self.custom_vault,
+            random_file_names: self.random_file_names,
+            durable_writes: self.durable_writes,
+            storage_retries: self.storage_retries,
+            retry_backoff: self.retry_backoff,
+            lookup_timeout: self.lookup_timeout,
+            lookups: self.lookups,
+            patch_streams: self.patch_streams.to_owned(),
+            detect_content_type: self.detect_content_type,
+            ring_capacity: self.ring_capacity,
+            downloads: self.downloads,
+            event_sink: self.event_sink,
+            body_transform: self.body_transform,
+            scanner: self.scanner,
+            scan_timeout: self.scan_timeout,
+            scan_verdicts: self.scan_verdicts,
+            event_buffer: self.event_buffer,
+            events: self.events,
+            usage: self.usage,
+            namespace_key: self.namespace_key,
+            key_provider: self.key_provider,
+            idempotency: self.idempotency,
+            chunk_failures: self.chunk_failures,
+            digests: self.digests,
+            readiness_path: self.readiness_path,
+            draining: self.draining,
+            vault,
             on_creation: self.on_creation,
             on_created: self.on_created,
             on_completed: self.on_completed,
             on_termination: self.on_termination,
+            resource_location: self.resource_location,
+            on_offset_mismatch: self.on_offset_mismatch,
+            on_eviction: self.on_eviction,
+            destination: self.destination,
         }
         /*  Consider Update to: #![feature(type_changing_struct_update)]
         Meteoritus::<Ignite> {
@@ -223,6 +530,78 @@ impl Meteoritus<Build> {
         self
     }
 
+    /// Optional configuration that rejects termination requests carrying a body.
+    ///
+    /// By default Meteoritus drains and ignores any body of up to 64 KiB sent along with a
+    /// `DELETE` request, independently of [`Meteoritus::with_max_size()`]. A bigger body is
+    /// rejected with `400 Bad Request` and the connection is closed instead of reading it.
+    /// When this option is set, a `DELETE` request with a non-empty body is rejected with
+    /// `400 Bad Request` and the upload is kept. The connection is closed as well, since
+    /// the body is left unread.
+    pub fn strict_termination(mut self) -> Self {
+        self.strict_termination = true;
+        self
+    }
+
+    /// Keeps the data of terminated uploads for the given window before purging it.
+    ///
+    /// By default a `DELETE` request removes the upload immediately. With soft termination
+    /// the upload is tombstoned instead: it is answered with `410 Gone` right away, but its
+    /// data can be recovered with [`Meteoritus::restore()`] until the sweeper purges it.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use std::time::Duration;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_soft_termination(Duration::from_secs(24 * 60 * 60))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_soft_termination(mut self, window: Duration) -> Self {
+        self.soft_termination = Some(window);
+        self
+    }
+
+    /// Optional configuration that adds a non-standard `X-Upload-Progress` header to `HEAD` responses.
+    ///
+    /// The header carries the upload progress as an integer percentage, sparing front-ends from
+    /// computing it from `Upload-Offset` and `Upload-Length` themselves.
+    pub fn with_progress_header(mut self) -> Self {
+        self.progress_header = true;
+        self
+    }
+
+    /// Optional configuration that allows clients to perform dry-run creation requests.
+    ///
+    /// A creation request carrying the `Upload-Dry-Run: 1` header goes through all the creation
+    /// validation, but no storage is reserved and the `on_creation` callback is not invoked.
+    /// It is answered with `204 No Content` and no `Location` header when the upload would be
+    /// accepted, or with the usual error otherwise.
+    ///
+    /// When this option is not set the `Upload-Dry-Run` header is ignored.
+    pub fn allow_dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Optional configuration that rejects the creation of empty uploads.
+    ///
+    /// By default uploads with `Upload-Length: 0` are accepted, as allowed by the tus protocol.
+    /// When this option is set, such creation requests are rejected with `400 Bad Request`.
+    pub fn reject_empty_uploads(mut self) -> Self {
+        self.empty_uploads = false;
+        self
+    }
+
     /// Mounts all tus middleware routes in the supplied given `base` path.
     ///
     /// # Panics
@@ -257,250 +636,1648 @@ impl Meteoritus<Build> {
     /// }
     /// ```
     /// **Note:** [`Meteoritus`] will mount many tus protocol routes based on the specified path.
+    ///
+    /// A trailing slash is ignored, so `"/api/files/"` behaves like `"/api/files"`: creation
+    /// requests are accepted on both `/api/files` and `/api/files/`, and the `Location` never
+    /// carries a doubled slash.
     pub fn mount_to(mut self, base_route: &'static str) -> Self {
-        self.base_route = base_route;
+        self.base_route = match base_route.trim_end_matches('/') {
+            "" => "/",
+            base_route => base_route,
+        };
         self
     }
 
-    /// Directory to store temporary files.
-    ///
-    /*# **Note:** If a custom [`Vault`] has provided then the [`Meteoritus`] will ignore
-    ///# the supplied `temp_path`.*/
+    /// Format of the `Location` header answered on creation, defaults to [`LocationFormat::Path`].
     ///
     /// # Examples
     ///
-    /// Assign relative `tmp/uploads` to store uploaded files into.
-    ///
     ///   ```rust,no_run
     ///   # #[macro_use] extern crate rocket;
     ///   use rocket::Ignite;
-    ///   use meteoritus::Meteoritus;
+    ///   use meteoritus::{LocationFormat, Meteoritus};
     ///
     ///   #[launch]
     ///   fn rocket() -> _ {
     ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
-    ///           .with_temp_path("./tmp/uploads")
+    ///           .with_location_format(LocationFormat::Url("https://uploads.example.com"))
     ///           .build();
     ///     
     ///       rocket::build().attach(meteoritus)
     /// }
     /// ```
-    pub fn with_temp_path(self, temp_path: &'static str) -> Self {
-        self.with_vault(LocalVault::new(temp_path))
+    pub fn with_location_format(mut self, format: LocationFormat) -> Self {
+        self.location_format = format;
+        self
     }
 
-    #[doc(hidden)]
-    /// Overrides the default instance of [`Vault`].
+    /// Number of proxy hops in front of the server trusted to append `X-Forwarded-For`, defaults to `0`.
     ///
-    /// If a custom vault has provided then the [`Meteoritus`] will ignore the [`Meteoritus::with_temp_path()`]
-    /// configuration. Since it assumes that all file system operations will be responsibility of
-    /// the custom vault implementation.
+    /// The address of the uploading client is recorded on creation, see
+    /// [`FileInfo::remote_addr()`](crate::FileInfo::remote_addr). Only the entries appended by
+    /// the trusted hops are considered, so clients cannot spoof their address by sending the
+    /// header themselves. With no trusted hops the peer address is recorded.
     ///
-    /// # Example
+    /// # Examples
     ///
-    /// ```rust,no_run
-    /// # // #[macro_use] extern crate rocket;
-    /// # // use std::io::Result;
-    /// # // use rocket::Ignite;
-    /// # // use meteoritus::{Meteoritus, Vault, FileInfo};
-    /// # //
-    /// # // pub struct MyCustomVault {}
-    /// # //
-    /// # // impl MyCustomVault {
-    /// # //     pub fn new() -> Self {
-    /// # //         Self {}
-    /// # //     }
-    /// # // }
-    /// # //
-    /// # // impl Vault for MyCustomVault {
-    /// # //     fn add(&self, file: &CometFile) -> Result<()> {
-    /// # //         // Save file information on some persistent storage
-    /// # //         todo!()
-    /// # //     }
-    /// # //
-    /// # //     fn take(&self, id: String) -> Result<CometFile> {
-    /// # //         // Get the file information from persistent storage
-    /// # //         todo!()
-    /// # //     }
-    /// # //
-    /// # //     fn remove(&self, file: &CometFile) -> Result<()> {
-    /// # //         // Remove file information and all data from persistent storage
-    /// # //         todo!()
-    /// # //     }
-    /// # //
-    /// # //     fn update(&self, file: &mut CometFile, buf: &mut [u8]) -> std::io::Result<()> {
-    /// # //         // Patch the file content based on current offset
-    /// # //         todo!()
-    /// # //     }
-    /// # // }
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
     ///
-    /// # //   #[launch]
-    /// # //   fn rocket() -> _ {
-    /// # //       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
-    /// # //           .with_temp_path("./tmp/uploads") // This will be ignored by Meteoritus
-    /// # //           .with_vault(MyCustomVault::new())
-    /// # //           .build();
-    /// # //     
-    /// # //       rocket::build().attach(meteoritus)
-    /// # //   }
-    ///   ```
-    pub(crate) fn with_vault<V: Vault + 'static>(mut self, vault: V) -> Self {
-        self.vault = Arc::new(vault);
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_trusted_proxies(1)
+    ///           .on_completed(|ctx| {
+    ///                println!("uploaded by: {:?}", ctx.file_info.remote_addr());
+    ///            })
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_trusted_proxies(mut self, hops: usize) -> Self {
+        self.trusted_proxies = hops;
         self
     }
 
-    /// Maximum upload size in a single `PATCH` request.
+    /// Records the given request headers on creation, to correlate client behavior with uploads.
+    ///
+    /// The snapshot is kept in the upload manifest, see
+    /// [`FileInfo::request_headers()`](crate::FileInfo::request_headers), and published with
+    /// the [`EventKind::Created`] event. Only the listed headers are recorded, so credentials
+    /// like `Authorization` are never stored unless asked for. Nothing is recorded by default.
     ///
     /// # Examples
     ///
     ///   ```rust,no_run
     ///   # #[macro_use] extern crate rocket;
-    ///   use rocket::{Ignite, data::ByteUnit};
+    ///   use rocket::Ignite;
     ///   use meteoritus::Meteoritus;
     ///
     ///   #[launch]
     ///   fn rocket() -> _ {
     ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
-    ///           .with_max_size(ByteUnit::Gibibyte(1))
+    ///           .with_captured_headers(&["User-Agent", "Via"])
+    ///           .on_completed(|ctx| {
+    ///                println!("uploaded with: {:?}", ctx.file_info.request_headers());
+    ///            })
     ///           .build();
     ///     
     ///       rocket::build().attach(meteoritus)
     /// }
     /// ```
-    pub fn with_max_size(mut self, size: ByteUnit) -> Self {
-        self.max_size = size;
+    pub fn with_captured_headers(
+        mut self,
+        headers: &'static [&'static str],
+    ) -> Self {
+        self.captured_headers = headers;
         self
     }
 
-    /// Adds a custom validation callback to be executed during file creation.
+    /// Identifies this node on every tus response with a non-standard `X-Upload-Node` header.
     ///
-    /// The callback function will be called during file creation and can be used to perform custom metadata validation
-    /// or other tasks related to the creation process. The function takes a [`HandlerContext`] parameter that contains
-    /// information about the file being created, including its metadata.
+    /// The header carries the given identifier and the crate version, like
+    /// `node-1 meteoritus/0.2.0`. It is purely informational, e.g. to debug sticky sessions
+    /// behind a load balancer, and is not sent by default.
     ///
-    /// The callback function should return a `Result<(), Box<dyn Error>>` indicating whether the validation
-    /// succeeded or failed. If the validation fails, the function should return an Err containing an error message.
     /// # Examples
     ///
     ///   ```rust,no_run
     ///   # #[macro_use] extern crate rocket;
-    ///   use rocket::{Ignite, data::ByteUnit};
-    ///   use meteoritus::{Built, HandlerContext, Meteoritus};
-    ///   # pub struct DbService {}
-    ///   #
-    ///   # impl DbService {
-    ///   #     fn new() -> DbService {
-    ///   #         Self {}
-    ///   #     }
-    ///   #
-    ///   #     fn say_hello(&self) {
-    ///   #         println!("Hello from DbService")
-    ///   #     }
-    ///   # }
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
     ///
     ///   #[launch]
     ///   fn rocket() -> _ {
     ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
-    ///           .on_creation(|ctx: HandlerContext<Built>| {
-    ///               println!("On Creation: {:?}", ctx.file_info);
-    ///
-    ///               // Apply metadata validation here:
-    ///               let Some(metadata) = ctx.file_info.metadata() else {
-    ///                   return Err("Metadata not specified!".into());
-    ///               };
-    ///     
-    ///               if let Err(e) = metadata.get_raw("filetype") {
-    ///                   return Err(e.into());
-    ///               }
-    ///     
-    ///               // Using rocket instance to get managed services
-    ///               let db_service = ctx.rocket.state::<DbService>().unwrap();
-    ///               db_service.say_hello();
-    ///     
-    ///               Ok(())
-    ///           })
+    ///           .with_node_id("node-1")
     ///           .build();
     ///     
     ///       rocket::build().attach(meteoritus)
     /// }
     /// ```
-    /// The above example adds a custom validation callback that checks the metadata of the file being created to
-    /// ensure that it contains a `"filetype"` field. If the validation fails, an error message is returned. The
-    /// callback also demonstrates the use of the rocket instance to access managed services.
-    pub fn on_creation<F>(mut self, callback: F) -> Self
-    where
-        F: Fn(HandlerContext<Built>) -> Result<(), Box<dyn Error>>
-            + Send
-            + Sync
-            + 'static,
-    {
-        self.on_creation = Some(Arc::new(callback));
+    pub fn with_node_id(mut self, node_id: &'static str) -> Self {
+        self.node_id = Some(node_id);
         self
     }
 
-    /// Adds a callback to be executed after a file has been successfully created and saved to disk.
+    /// Origin allowed on CORS preflight requests, defaults to `*`.
     ///
-    /// The callback function will be called after a file has been successfully created and saved to disk. The function
-    /// takes a [`HandlerContext`] parameter that contains information about the file that was just created,
-    /// including its metadata.
+    /// An `OPTIONS` request carrying `Access-Control-Request-Method` is answered with
+    /// the CORS headers only, while a plain `OPTIONS` request returns the tus capabilities.
     ///
     /// # Examples
+    ///
     ///   ```rust,no_run
     ///   # #[macro_use] extern crate rocket;
-    ///   use rocket::{Ignite, data::ByteUnit};
-    ///   use meteoritus::{Created, HandlerContext, Meteoritus};
-    ///   # pub struct DbService {}
-    ///   #
-    ///   # impl DbService {
-    ///   #     fn new() -> DbService {
-    ///   #         Self {}
-    ///   #     }
-    ///   #
-    ///   #     fn say_hello(&self) {
-    ///   #         println!("Hello from DbService")
-    ///   #     }
-    ///   # }
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
     ///
     ///   #[launch]
     ///   fn rocket() -> _ {
     ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
-    ///           .on_created(|ctx: HandlerContext<Created>| {
-    ///               println!("File saved on disk: {:?}", ctx.file_info);
-    ///
-    ///               // Using rocket instance to get managed services
-    ///               let db_service = ctx.rocket.state::<DbService>().unwrap();
-    ///               db_service.say_hello();
-    ///           })
+    ///           .with_cors_origin("https://example.com")
     ///           .build();
     ///     
     ///       rocket::build().attach(meteoritus)
     /// }
     /// ```
-    /// The above example adds a callback function that simply logs the file information after it has been successfully
-    /// created and saved to disk also demonstrates the use of the rocket instance to access managed services.
-    pub fn on_created<F>(mut self, callback: F) -> Self
-    where
-        F: Fn(HandlerContext<Created>) + Send + Sync + 'static,
-    {
-        self.on_created = Some(Arc::new(callback));
+    pub fn with_cors_origin(mut self, origin: &'static str) -> Self {
+        self.cors_origin = origin;
         self
     }
 
-    /// Specifies a callback to be called when a file upload is completed.
-    ///
-    /// The `on_completed` callback function takes a [`HandlerContext<Completed>`] parameter and
-    /// is called once a file upload is completed. This allows users of the library to perform
-    /// additional actions after the file has been uploaded and processed.
+    /// Directory to store temporary files.
     ///
-    /// At this point is recommended to move uploads to a permanent/secure location, by default Meteoritus
-    /// is configured to auto-terminate after `on_completed` was invoked.
-    /// Consider add [` Meteoritus::keep_on_disk()`] in order to overwrite this.
+    /*# **Note:** If a custom [`Vault`] has provided then the [`Meteoritus`] will ignore
+    ///# the supplied `temp_path`.*/
     ///
     /// # Examples
+    ///
+    /// Assign relative `tmp/uploads` to store uploaded files into.
+    ///
     ///   ```rust,no_run
     ///   # #[macro_use] extern crate rocket;
-    ///   use rocket::{Ignite, data::ByteUnit};
-    ///   use std::{fs, path::Path, str::from_utf8};
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_temp_path("./tmp/uploads")
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_temp_path(mut self, temp_path: &'static str) -> Self {
+        self.temp_path = temp_path;
+
+        self.with_local_vault()
+    }
+
+    /// Stores in-progress uploads under a random, unguessable name instead of
+    /// a path derived from the public upload id.
+    ///
+    /// The random name is kept in the upload manifest and the file is moved to
+    /// its final name once the upload is completed.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_random_file_names()
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_random_file_names(mut self) -> Self {
+        self.random_file_names = true;
+
+        self.with_local_vault()
+    }
+
+    /// Syncs every chunk to disk before recording its offset in the upload manifest.
+    ///
+    /// After a crash uploads resume from the offset actually persisted on disk, and a client
+    /// `PATCH` at a stale offset is answered with `409 Conflict`. The manifest is staged and
+    /// renamed over the previous one, so a crash never leaves it partially written. It costs
+    /// a few disk syncs per chunk.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_durable_writes()
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_durable_writes(mut self) -> Self {
+        self.durable_writes = true;
+
+        self.with_local_vault()
+    }
+
+    /// Refuses creations once fewer than `min_inodes` inodes are left on the temp path filesystem.
+    ///
+    /// Many small uploads can exhaust the inodes of a filesystem while space is still
+    /// available. Creations, dry runs included, are answered with `507 Insufficient Storage`
+    /// when too few remain. The local filesystems are only inspected on Unix platforms,
+    /// elsewhere it is a no-op unless [`Meteoritus::with_inode_stats()`] is used.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_min_free_inodes(10_000)
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_min_free_inodes(mut self, min_inodes: u64) -> Self {
+        self.min_free_inodes = Some(min_inodes);
+        self
+    }
+
+    /// Reads the inodes left from the given [`InodeStats`] instead of the local filesystems.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use std::path::Path;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::{InodeStats, Meteoritus};
+    ///
+    ///   struct FixedInodes(u64);
+    ///
+    ///   impl InodeStats for FixedInodes {
+    ///       fn available_inodes(&self, _path: &Path) -> Option<u64> {
+    ///           Some(self.0)
+    ///       }
+    ///   }
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_min_free_inodes(10_000)
+    ///           .with_inode_stats(FixedInodes(50_000))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_inode_stats<I: InodeStats + 'static>(
+        mut self,
+        stats: I,
+    ) -> Self {
+        self.inode_stats = Some(Arc::new(stats));
+        self
+    }
+
+    /// Retries storage operations failing with transient errors before answering an error.
+    ///
+    /// Operations are retried at most `attempts` times, waiting `backoff` before the first
+    /// retry and twice as long before each next one. Every retry is logged, and the request
+    /// fails with `503 Service Unavailable` once the attempts are exhausted. Non transient
+    /// errors are never retried. The local vault reports timed out, interrupted and would
+    /// block writes as transient, a custom vault returns `VaultError::Transient` instead.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use std::time::Duration;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_storage_retries(3, Duration::from_millis(50))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_storage_retries(
+        mut self,
+        attempts: u32,
+        backoff: Duration,
+    ) -> Self {
+        self.storage_retries = attempts;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Bounds the time a `HEAD` request waits for the storage to look up an upload, defaults to 30 seconds.
+    ///
+    /// A storage stuck under load then answers `504 Gateway Timeout` instead of holding the
+    /// connection indefinitely. The lookup itself cannot be cancelled and finishes in the
+    /// background, only the response is not waiting for it anymore. Those lookups still
+    /// count against [`Meteoritus::with_max_lookups()`] until they finish.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use std::time::Duration;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_lookup_timeout(Duration::from_secs(5))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_lookup_timeout(mut self, timeout: Duration) -> Self {
+        self.lookup_timeout = timeout;
+        self
+    }
+
+    /// Limits the number of storage lookups of `HEAD` requests running at once, defaults to 64.
+    ///
+    /// Lookups run on blocking threads and keep running past their timeout, so a stuck
+    /// storage could otherwise exhaust the blocking thread pool. Requests above the limit
+    /// are answered with `503 Service Unavailable`.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_max_lookups(16)
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_max_lookups(mut self, max_lookups: usize) -> Self {
+        self.lookups = Arc::new(Semaphore::new(max_lookups));
+        self
+    }
+
+    /// Limits the number of `PATCH` request bodies streamed at once, server-wide.
+    ///
+    /// Every streamed body holds a buffer and a file handle, so the limit bounds memory and
+    /// file descriptor usage regardless of the number of uploads. Requests above the limit
+    /// are answered with `503 Service Unavailable` and a `Retry-After` header of `retry_after`.
+    /// A stream is released once its chunk is written, or when the client disconnects.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use std::time::Duration;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_max_patch_streams(64, Duration::from_secs(5))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_max_patch_streams(
+        mut self,
+        max_streams: usize,
+        retry_after: Duration,
+    ) -> Self {
+        self.patch_streams =
+            Some((Arc::new(Semaphore::new(max_streams)), retry_after));
+        self
+    }
+
+    /// Keeps only the last `capacity` bytes of every upload, for bounded live streams.
+    ///
+    /// Older bytes are discarded as new chunks arrive, while the `Upload-Offset` still
+    /// reports every byte received. Completed uploads hold their last `capacity` bytes.
+    /// Random resume isn't supported in this mode: chunks must be appended at the current
+    /// offset, and the discarded bytes can't be read back. Checksums and encryption cover
+    /// the whole upload, and downloads would only serve the tail, so combining them with a
+    /// ring buffer fails to ignite. With the `download` feature, call `without_downloads`.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::{Ignite, data::ByteUnit};
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_ring_buffer(ByteUnit::Mebibyte(16))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    #[cfg(feature = "ring")]
+    pub fn with_ring_buffer(mut self, capacity: ByteUnit) -> Self {
+        self.ring_capacity = Some(capacity.as_u64());
+
+        self.with_local_vault()
+    }
+
+    /// Optional configuration that leaves completed uploads out of `GET` downloads.
+    ///
+    /// The download route isn't mounted, so `GET` is answered with `405 Method Not Allowed`
+    /// and is no longer advertised in `Allow` or CORS preflight responses. A ring buffer
+    /// can only be used once downloads are disabled.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .without_downloads()
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    #[cfg(feature = "download")]
+    pub fn without_downloads(mut self) -> Self {
+        self.downloads = false;
+        self
+    }
+
+    /// Rebuilds the local vault with the current options, a custom vault is kept as is.
+    fn with_local_vault(self) -> Self {
+        if self.custom_vault {
+            return self;
+        }
+
+        let vault = self.local_vault();
+
+        Self {
+            custom_vault: false,
+            ..self.with_vault(vault)
+        }
+    }
+
+    fn local_vault(&self) -> LocalVault {
+        let mut vault = LocalVault::new(self.temp_path);
+
+        if self.random_file_names {
+            vault = vault.with_random_file_names();
+        }
+
+        if self.durable_writes {
+            vault = vault.with_durable_writes();
+        }
+
+        #[cfg(feature = "sniff")]
+        if self.detect_content_type {
+            vault = vault.with_content_detection();
+        }
+
+        #[cfg(feature = "ring")]
+        if let Some(capacity) = self.ring_capacity {
+            vault = vault.with_ring_capacity(capacity);
+        }
+
+        vault
+    }
+
+    #[doc(hidden)]
+    /// Overrides the default instance of [`Vault`].
+    ///
+    /// If a custom vault has provided then the [`Meteoritus`] will ignore the [`Meteoritus::with_temp_path()`]
+    /// configuration. Since it assumes that all file system operations will be responsibility of
+    /// the custom vault implementation. Options of the local vault, such as
+    /// [`Meteoritus::with_random_file_names()`], are rejected on ignite instead of
+    /// replacing the custom vault.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # // #[macro_use] extern crate rocket;
+    /// # // use std::io::Result;
+    /// # // use rocket::Ignite;
+    /// # // use meteoritus::{Meteoritus, Vault, FileInfo};
+    /// # //
+    /// # // pub struct MyCustomVault {}
+    /// # //
+    /// # // impl MyCustomVault {
+    /// # //     pub fn new() -> Self {
+    /// # //         Self {}
+    /// # //     }
+    /// # // }
+    /// # //
+    /// # // impl Vault for MyCustomVault {
+    /// # //     fn add(&self, file: &CometFile) -> Result<()> {
+    /// # //         // Save file information on some persistent storage
+    /// # //         todo!()
+    /// # //     }
+    /// # //
+    /// # //     fn take(&self, id: String) -> Result<CometFile> {
+    /// # //         // Get the file information from persistent storage
+    /// # //         todo!()
+    /// # //     }
+    /// # //
+    /// # //     fn remove(&self, file: &CometFile) -> Result<()> {
+    /// # //         // Remove file information and all data from persistent storage
+    /// # //         todo!()
+    /// # //     }
+    /// # //
+    /// # //     fn update(&self, file: &mut CometFile, buf: &mut [u8]) -> std::io::Result<()> {
+    /// # //         // Patch the file content based on current offset
+    /// # //         todo!()
+    /// # //     }
+    /// # // }
+    ///
+    /// # //   #[launch]
+    /// # //   fn rocket() -> _ {
+    /// # //       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    /// # //           .with_temp_path("./tmp/uploads") // This will be ignored by Meteoritus
+    /// # //           .with_vault(MyCustomVault::new())
+    /// # //           .build();
+    /// # //     
+    /// # //       rocket::build().attach(meteoritus)
+    /// # //   }
+    ///   ```
+    pub fn with_vault<V: Vault + 'static>(mut self, vault: V) -> Self {
+        self.vault = Arc::new(vault);
+        self.custom_vault = true;
+        self
+    }
+
+    /// Maximum upload size in a single `PATCH` request.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::{Ignite, data::ByteUnit};
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_max_size(ByteUnit::Gibibyte(1))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_max_size(mut self, size: ByteUnit) -> Self {
+        self.max_size = size;
+        self
+    }
+
+    /// Validates the optional `Content-Range` header of `PATCH` requests.
+    ///
+    /// A `Content-Range: bytes <start>-<end>/<total>` header must agree with the
+    /// `Upload-Offset`, the body length and the `Upload-Length`, otherwise the request is
+    /// rejected with `400 Bad Request`. The total may be `*`. Requests without the header
+    /// are accepted, and the header is ignored unless this option is set.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .validate_content_range()
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn validate_content_range(mut self) -> Self {
+        self.content_range = true;
+        self
+    }
+
+    /// Minimum upload size in a single `PATCH` request, disabled by default.
+    ///
+    /// Smaller chunks are rejected with `400 Bad Request`, except the final chunk
+    /// completing the upload, which may legitimately be small.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::{Ignite, data::ByteUnit};
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_min_chunk_size(ByteUnit::Kibibyte(256))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_min_chunk_size(mut self, size: ByteUnit) -> Self {
+        self.min_chunk_size = Some(size);
+        self
+    }
+
+    /// Suggests a chunk size to clients with a non-standard `X-Tus-Preferred-Chunk-Size` header.
+    ///
+    /// The header is answered on `OPTIONS` requests. Clients may send the same header on
+    /// creation to hint the chunk size they will use, the hint is bounded by the minimum
+    /// chunk size and [`Meteoritus::with_max_size()`] and sizes the buffer receiving the
+    /// chunks of that upload. Disabled by default.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::{Ignite, data::ByteUnit};
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_preferred_chunk_size(ByteUnit::Mebibyte(8))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_preferred_chunk_size(mut self, size: ByteUnit) -> Self {
+        self.preferred_chunk_size = Some(size);
+        self
+    }
+
+    /// Rejects uploads whose first bytes do not match their declared `filetype` metadata.
+    ///
+    /// The first chunk of an upload is compared against a table of known file signatures.
+    /// On mismatch the upload is terminated and the `PATCH` request fails with
+    /// `415 Unsupported Media Type`. Content that matches no known signature is accepted,
+    /// since sniffing is imperfect.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .reject_mismatched_content()
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    #[cfg(feature = "sniff")]
+    pub fn reject_mismatched_content(mut self) -> Self {
+        self.sniff_content = true;
+        self
+    }
+
+    /// Detects the media type of completed uploads from their content.
+    ///
+    /// The detected type is stored along with the upload and reported by
+    /// [`FileInfo::content_type()`](crate::FileInfo::content_type), which falls back to
+    /// the client declared `filetype` metadata when the content matches no known signature.
+    /// Uploads encrypted with a [`KeyProvider`] are only stored as ciphertext, so their
+    /// type is always the declared one.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .detect_content_type()
+    ///           .on_completed(|ctx| {
+    ///                println!("type: {:?}", ctx.file_info.content_type());
+    ///            })
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    #[cfg(feature = "sniff")]
+    pub fn detect_content_type(mut self) -> Self {
+        self.detect_content_type = true;
+
+        self.with_local_vault()
+    }
+
+    /// Limits applied while parsing the `Upload-Metadata` header of creation requests.
+    ///
+    /// Creation requests with metadata exceeding these limits fail with `400 Bad Request`.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::{Meteoritus, MetadataOptions};
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_metadata_options(MetadataOptions::new().with_max_value_length(1024))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_metadata_options(mut self, options: MetadataOptions) -> Self {
+        self.metadata_options = options;
+        self
+    }
+
+    /// Verifies completed uploads against a full-file SHA-256 digest supplied by the client.
+    ///
+    /// The expected digest is read from the [`Metadata`] entry named `key`, as a hex encoded string.
+    /// Once all bytes have been received the digest of the file is computed and, on mismatch, the upload
+    /// is terminated and the completing `PATCH` request fails with `460 Checksum Mismatch`, without
+    /// invoking the `on_completed` callback. Uploads without that metadata entry are not verified,
+    /// while a creation request whose entry isn't a hex encoded SHA-256 digest is rejected with
+    /// `400 Bad Request`.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_checksum_key("sha256")
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_checksum_key(mut self, key: &'static str) -> Self {
+        self.checksum_key = Some(key);
+        self
+    }
+
+    /// Enables the tus `checksum` extension, verifying chunks against their `Upload-Checksum` header.
+    ///
+    /// The supported algorithms are listed in the `Tus-Checksum-Algorithm` header of `OPTIONS`
+    /// responses. A chunk not matching its checksum is discarded and the `PATCH` request fails
+    /// with `460 Checksum Mismatch`.
+    ///
+    /// A client can also declare its algorithm once on creation, with the
+    /// `Upload-Checksum-Algorithm` header. The algorithm is stored along with the upload, see
+    /// [`FileInfo::checksum_algorithm()`](crate::FileInfo::checksum_algorithm), and chunks
+    /// checksummed with another one are rejected with `400 Bad Request`. Chunks without
+    /// checksum are still accepted either way.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_chunk_checksums()
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_chunk_checksums(mut self) -> Self {
+        self.chunk_checksums = true;
+        self
+    }
+
+    /// Expires uploads after the given `duration` counted from their creation.
+    ///
+    /// The computed expiry is stored along with the file information and can be retrieved
+    /// from [`FileInfo::expires_at()`](crate::FileInfo::expires_at).
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use std::time::Duration;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_expiration(Duration::from_secs(24 * 60 * 60))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_expiration(mut self, duration: Duration) -> Self {
+        self.expiration = self.expiration.with_absolute(duration);
+        self
+    }
+
+    /// Expires uploads that have not received any `PATCH` request for the given `duration`.
+    ///
+    /// Every `PATCH` request renews the upload expiry. If [`Meteoritus::with_expiration()`] is
+    /// also specified, the earliest of both expiries is used.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use std::time::Duration;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_idle_expiration(Duration::from_secs(60 * 60))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_idle_expiration(mut self, duration: Duration) -> Self {
+        self.expiration = self.expiration.with_idle(duration);
+        self
+    }
+
+    /// Moves completed uploads into the given directory, as `<path>/<id>`.
+    ///
+    /// The upload is moved before the `on_completed` callback is invoked so its
+    /// [`FileInfo`] points to the final location. If the directory is on another
+    /// filesystem the file is copied, and its progress is reported as
+    /// [`EventKind::Moving`](crate::EventKind::Moving) events to the configured [`EventSink`].
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_completed_path("./uploads")
+    ///           .keep_on_disk()
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_completed_path(mut self, path: &'static str) -> Self {
+        self.completed_path = Some(path);
+        self
+    }
+
+    /// Moves completed uploads to the path chosen by the given callback.
+    ///
+    /// The callback receives the completed upload and the attempt number, starting at `0`.
+    /// The file is never moved over an existing one: when the chosen path already exists
+    /// the callback is called again with the next attempt number, so it can resolve the
+    /// collision, e.g. by appending a counter. The upload fails with `500 Internal Server Error`
+    /// after [`Meteoritus::with_destination_attempts()`] attempts. This overrides
+    /// [`Meteoritus::with_completed_path()`].
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use std::path::Path;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_destination(|ctx, attempt| {
+    ///               let name = ctx.file_info.metadata()
+    ///                   .as_ref()
+    ///                   .and_then(|metadata| metadata.get_raw("filename").ok())
+    ///                   .and_then(|raw| String::from_utf8(raw).ok())
+    ///                   .unwrap_or_else(|| ctx.file_info.id().to_string());
+    ///
+    ///               match attempt {
+    ///                   0 => Path::new("./uploads").join(name),
+    ///                   n => Path::new("./uploads").join(format!("{} ({})", name, n)),
+    ///               }
+    ///           })
+    ///           .keep_on_disk()
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_destination<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(HandlerContext<Completed>, u32) -> PathBuf
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.destination = Some(Arc::new(callback));
+        self
+    }
+
+    /// Moves completed uploads to a directory chosen by the value of a metadata key.
+    ///
+    /// `routes` maps values of the `key` metadata to directories, like tenants to their
+    /// storage. Uploads with an unknown or missing value are moved to `fallback`, or
+    /// rejected on creation with `422 Unprocessable Entity` when there is no fallback.
+    /// Files are named after the upload id. This overrides [`Meteoritus::with_completed_path()`],
+    /// and is overridden by [`Meteoritus::with_destination()`].
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_destination_routes(
+    ///               "tenant",
+    ///               &[("acme", "./uploads/acme"), ("globex", "./uploads/globex")],
+    ///               Some("./uploads/other"),
+    ///           )
+    ///           .keep_on_disk()
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_destination_routes(
+        mut self,
+        key: &'static str,
+        routes: &'static [(&'static str, &'static str)],
+        fallback: Option<&'static str>,
+    ) -> Self {
+        self.destination_routes =
+            Some(DestinationRoutes::new(key, routes, fallback));
+        self
+    }
+
+    /// Number of paths asked to the [`Meteoritus::with_destination()`] callback, defaults to `5`.
+    pub fn with_destination_attempts(mut self, attempts: u32) -> Self {
+        self.destination_attempts = attempts;
+        self
+    }
+
+    /// Hard links completed uploads into each of the given directories, as `<path>/<id>`.
+    ///
+    /// It allows several consumers watching different directories to pick up the same file
+    /// without copying it. When a directory is on another filesystem the file is copied
+    /// instead. The links are made once the upload has reached its final location and
+    /// before the `on_completed` callback is invoked. The fan-out is all or nothing: copies
+    /// appear under their final name once complete, and if any directory fails, the links
+    /// already made are removed and the error is logged. The upload itself is complete by
+    /// then, so it is still acknowledged and handed to `on_completed`.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_fan_out_paths(&["./thumbnails/inbox", "./archive/inbox"])
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_fan_out_paths(
+        mut self,
+        paths: &'static [&'static str],
+    ) -> Self {
+        self.fan_out_paths = paths;
+        self
+    }
+
+    /// Keeps at most `count` completed uploads on disk, evicting the oldest ones.
+    ///
+    /// When an upload completes and the number of retained completed uploads exceeds `count`,
+    /// the ones completed first are removed and the `on_eviction` callback is executed for each
+    /// of them. Files already moved to their completed path or destination are deleted as well.
+    /// It only applies when uploads are kept on disk, and works alongside
+    /// [`Meteoritus::with_completed_retention()`], whichever triggers first.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .keep_on_disk()
+    ///           .with_max_completed(100)
+    ///           .on_eviction(|ctx| println!("evicted: {}", ctx.file_info.id()))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_max_completed(mut self, count: usize) -> Self {
+        self.max_completed = Some(CompletedQueue::new(count));
+        self
+    }
+
+    /// Keeps completed uploads on disk for the given `duration` before removing them.
+    ///
+    /// This is intended to be used along with [`Meteoritus::keep_on_disk()`], letting completed
+    /// uploads linger in the temp path until an external consumer picks them up. Completed uploads
+    /// that haven't been released with a `DELETE` request within the retention window are removed
+    /// by the sweeper. A file already moved to its final destination is never removed, only the
+    /// manifest left in the temp path is.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use std::time::Duration;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .keep_on_disk()
+    ///           .with_completed_retention(Duration::from_secs(60 * 60))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_completed_retention(mut self, duration: Duration) -> Self {
+        self.completed_retention = Some(duration);
+        self
+    }
+
+    /// How often the sweeper looks for expired uploads, by default every minute.
+    ///
+    /// The sweeper only runs when an expiration or a completed retention window is configured.
+    pub fn with_sweep_interval(mut self, interval: Duration) -> Self {
+        self.sweep_interval = interval;
+        self
+    }
+
+    /// Overrides the [`Status`] answered for the given [`MeteoritusError`].
+    ///
+    /// Some proxies and clients interpret statuses differently, this option lets deployments
+    /// trade protocol conformance for compatibility. Errors without an override are answered
+    /// with their [`MeteoritusError::default_status()`].
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::{http::Status, Ignite};
+    ///   use meteoritus::{Meteoritus, MeteoritusError};
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_error_status(MeteoritusError::OffsetMismatch, Status::PreconditionFailed)
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_error_status(
+        mut self,
+        error: MeteoritusError,
+        status: Status,
+    ) -> Self {
+        self.error_statuses.insert(error, status);
+        self
+    }
+
+    /// Publishes upload lifecycle events to the given [`EventSink`].
+    ///
+    /// Events are buffered and forwarded in the background, see [`Meteoritus::with_event_buffer()`].
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::{EventSink, Meteoritus, MeteoritusEvent};
+    ///
+    ///   struct LogSink;
+    ///
+    ///   #[rocket::async_trait]
+    ///   impl EventSink for LogSink {
+    ///       async fn publish(&self, event: MeteoritusEvent) {
+    ///           println!("{:?}: {}", event.kind, event.id);
+    ///       }
+    ///   }
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_event_sink(LogSink)
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_event_sink<S: EventSink + 'static>(mut self, sink: S) -> Self {
+        self.event_sink = Some(Arc::new(sink));
+        self
+    }
+
+    /// Applies the given [`BodyTransform`] to every chunk sent at the upload offset before it is written.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::{BodyTransform, Meteoritus};
+    ///
+    ///   struct Uppercase;
+    ///
+    ///   #[rocket::async_trait]
+    ///   impl BodyTransform for Uppercase {
+    ///       async fn transform(&self, _offset: u64, chunk: Vec<u8>) -> Vec<u8> {
+    ///           chunk.to_ascii_uppercase()
+    ///       }
+    ///   }
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_body_transform(Uppercase)
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_body_transform<T: BodyTransform + 'static>(
+        mut self,
+        transform: T,
+    ) -> Self {
+        self.body_transform = Some(Arc::new(transform));
+        self
+    }
+
+    /// Gates the download of completed uploads behind the given [`UploadScanner`].
+    ///
+    /// Only uploads kept on disk are scanned, see [`Meteoritus::keep_on_disk()`]. The scan
+    /// starts as soon as the upload is completed and moved to its destination, and the
+    /// upload is only linked into the fan-out directories once found clean. The verdict is
+    /// published as an [`EventKind::Released`] or [`EventKind::Quarantined`] event.
+    ///
+    /// The `on_completed` callback borrows the [`Rocket`] instance of the completing request,
+    /// so it can't wait for the verdict: it is called while the upload is still scanning.
+    /// Applications handing the file over should wait for the [`EventKind::Released`] event.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::{Completed, FileInfo, Meteoritus, ScanVerdict, UploadScanner};
+    ///
+    ///   struct AllowAll;
+    ///
+    ///   #[rocket::async_trait]
+    ///   impl UploadScanner for AllowAll {
+    ///       async fn scan(&self, _file: &FileInfo<Completed>) -> ScanVerdict {
+    ///           ScanVerdict::Clean
+    ///       }
+    ///   }
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .keep_on_disk()
+    ///           .with_scanner(AllowAll)
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_scanner<T: UploadScanner + 'static>(
+        mut self,
+        scanner: T,
+    ) -> Self {
+        self.scanner = Some(Arc::new(scanner));
+        self
+    }
+
+    /// Bounds the time a download waits for the scan verdict of an upload, defaults to 30 seconds.
+    ///
+    /// Downloads of an upload still scanning once the timeout elapsed are refused with
+    /// `423 Locked`, see [`Meteoritus::with_scanner()`].
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use std::time::Duration;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_scan_timeout(Duration::from_secs(5))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_scan_timeout(mut self, timeout: Duration) -> Self {
+        self.scan_timeout = timeout;
+        self
+    }
+
+    /// Accounts completed upload volume by the value of the given metadata key.
+    ///
+    /// At most `max_values` distinct values are tracked, further values and uploads missing
+    /// the key are accounted apart in [`UsageReport::other`](crate::UsageReport::other). The
+    /// counters can be queried with [`Meteoritus::usage()`].
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_usage_accounting("tenant", 100)
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_usage_accounting(
+        mut self,
+        key: &'static str,
+        max_values: usize,
+    ) -> Self {
+        self.usage = Some(UsageLedger::new(key, max_values));
+        self
+    }
+
+    /// Stores uploads in a namespace named by the value of the given metadata key, like a tenant.
+    ///
+    /// The namespace prefixes the upload id, like `acme.<uuid>`, so ids never collide across
+    /// namespaces, and the upload is stored in a directory of the namespace below the temp path.
+    /// The uploads of a namespace can be listed with [`Meteoritus::namespace_files()`] and
+    /// terminated at once with [`Meteoritus::purge_namespace()`].
+    ///
+    /// Namespaces are limited to 64 ASCII alphanumerics, `-` and `_`, so they are always
+    /// safe to use as a path. Creation requests carrying another value are rejected with
+    /// `400 Bad Request`, while uploads without the key are stored outside any namespace.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_namespace_key("tenant")
+    ///           .on_completed(|ctx| {
+    ///                println!("uploaded for: {:?}", ctx.file_info.namespace());
+    ///            })
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_namespace_key(mut self, key: &'static str) -> Self {
+        self.namespace_key = Some(key);
+        self
+    }
+
+    /// Mounts a readiness probe at `path`, to drain the node before it is terminated.
+    ///
+    /// The probe answers `GET` requests with a JSON [`Readiness`](crate::Readiness) body,
+    /// like `{"status":"draining","in_progress":3}`. It answers `200 OK` while the node
+    /// accepts new uploads and `503 Service Unavailable` once [`Meteoritus::drain()`] has been
+    /// called, so the node is taken out of the load balancer. The orchestrator can terminate
+    /// it once the status reads `drained`, when no upload is in progress anymore.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::{Ignite, Orbit, State};
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[post("/drain")]
+    ///   fn drain(meteoritus: &State<Meteoritus<Orbit>>) {
+    ///       meteoritus.drain();
+    ///   }
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_readiness_probe("/ready")
+    ///           .build();
+    ///     
+    ///       rocket::build()
+    ///           .attach(meteoritus)
+    ///           .mount("/admin", routes![drain])
+    /// }
+    /// ```
+    pub fn with_readiness_probe(mut self, path: &'static str) -> Self {
+        self.readiness_path = Some(path);
+        self
+    }
+
+    /// Recognizes retried `PATCH` and creation requests carrying the same `Idempotency-Key` header.
+    ///
+    /// A `PATCH` whose key and `Upload-Offset` match a request applied within the last `ttl`
+    /// is acknowledged with `204 No Content` and the resulting offset, without writing the
+    /// bytes again. This covers clients that can't tell if a `PATCH` landed after a network failure.
+    ///
+    /// Likewise, a `POST` whose key, `Upload-Length` and `Upload-Metadata` match a creation
+    /// within the last `ttl` is answered with the `Location` of the upload created by the first
+    /// attempt, as long as it still exists, instead of leaving an orphaned duplicate behind.
+    /// Creation keys are scoped to the client address, a key reused with another length or
+    /// metadata is answered with `422 Unprocessable Entity`, and a retry arriving while the
+    /// first attempt is still in flight with `409 Conflict`.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use std::time::Duration;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_idempotency_keys(Duration::from_secs(300))
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_idempotency_keys(mut self, ttl: Duration) -> Self {
+        self.idempotency = Some(IdempotencyCache::new(ttl));
+        self
+    }
+
+    /// Aborts uploads once more than `max_failures` `PATCH` requests failed in a row.
+    ///
+    /// Chunks rejected for a mismatched `Upload-Offset`, `Content-Range` or `Upload-Checksum`
+    /// count as failures, and a successful chunk resets the count. An upload exceeding the limit is
+    /// likely corrupt or malicious: it is terminated, [`Meteoritus::on_termination()`] is
+    /// called, and its further requests are answered with `410 Gone`.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::Meteoritus;
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_max_failed_chunks(10)
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_max_failed_chunks(mut self, max_failures: u32) -> Self {
+        self.chunk_failures = Some(FailureCounter::new(max_failures));
+        self
+    }
+
+    /// Encrypts uploads with the keys of the given [`KeyProvider`].
+    ///
+    /// A client selects the key of an upload by sending its id in the `Upload-Key-Id` header
+    /// on creation, unknown ids are rejected with `400 Bad Request`. Chunks are encrypted
+    /// with AES-256 before being written, and the key id, never the key, is stored along
+    /// with the upload. Completed uploads stay encrypted at rest, wherever they are moved:
+    /// the key is fetched again to verify their checksum and to decrypt them as they are
+    /// downloaded. If the provider answers another key than the one the upload was created
+    /// with, the request fails with `500 Internal Server Error`. Uploads without a key id
+    /// are stored as is.
+    ///
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::{InMemoryKeyProvider, Meteoritus};
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let keys = InMemoryKeyProvider::new()
+    ///           .with_key("tenant-1", *b"an example very very secret key.");
+    ///
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_key_provider(keys)
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn with_key_provider<K: KeyProvider + 'static>(
+        mut self,
+        provider: K,
+    ) -> Self {
+        self.key_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Number of events held while the [`EventSink`] is busy, defaults to `1024`.
+    ///
+    /// Events emitted while the buffer is full are dropped and logged.
+    pub fn with_event_buffer(mut self, capacity: usize) -> Self {
+        self.event_buffer = capacity;
+        self
+    }
+
+    /// Adds a custom validation callback to be executed during file creation.
+    ///
+    /// The callback function will be called during file creation and can be used to perform custom metadata validation
+    /// or other tasks related to the creation process. The function takes a [`HandlerContext`] parameter that contains
+    /// information about the file being created, including its metadata.
+    ///
+    /// The callback function should return a `Result<(), Box<dyn Error>>` indicating whether the validation
+    /// succeeded or failed. If the validation fails, the function should return an Err containing an error message.
+    /// # Examples
+    ///
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::{Ignite, data::ByteUnit};
+    ///   use meteoritus::{Built, HandlerContext, Meteoritus};
+    ///   # pub struct DbService {}
+    ///   #
+    ///   # impl DbService {
+    ///   #     fn new() -> DbService {
+    ///   #         Self {}
+    ///   #     }
+    ///   #
+    ///   #     fn say_hello(&self) {
+    ///   #         println!("Hello from DbService")
+    ///   #     }
+    ///   # }
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .on_creation(|ctx: HandlerContext<Built>| {
+    ///               println!("On Creation: {:?}", ctx.file_info);
+    ///
+    ///               // Apply metadata validation here:
+    ///               let Some(metadata) = ctx.file_info.metadata() else {
+    ///                   return Err("Metadata not specified!".into());
+    ///               };
+    ///     
+    ///               if let Err(e) = metadata.get_raw("filetype") {
+    ///                   return Err(e.into());
+    ///               }
+    ///     
+    ///               // Using rocket instance to get managed services
+    ///               let db_service = ctx.rocket.state::<DbService>().unwrap();
+    ///               db_service.say_hello();
+    ///     
+    ///               Ok(())
+    ///           })
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    /// The above example adds a custom validation callback that checks the metadata of the file being created to
+    /// ensure that it contains a `"filetype"` field. If the validation fails, an error message is returned. The
+    /// callback also demonstrates the use of the rocket instance to access managed services.
+    pub fn on_creation<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(HandlerContext<Built>) -> Result<(), Box<dyn Error>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.on_creation = Some(Arc::new(callback));
+        self
+    }
+
+    /// Adds a callback to be executed after a file has been successfully created and saved to disk.
+    ///
+    /// The callback function will be called after a file has been successfully created and saved to disk. The function
+    /// takes a [`HandlerContext`] parameter that contains information about the file that was just created,
+    /// including its metadata.
+    ///
+    /// # Examples
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::{Ignite, data::ByteUnit};
+    ///   use meteoritus::{Created, HandlerContext, Meteoritus};
+    ///   # pub struct DbService {}
+    ///   #
+    ///   # impl DbService {
+    ///   #     fn new() -> DbService {
+    ///   #         Self {}
+    ///   #     }
+    ///   #
+    ///   #     fn say_hello(&self) {
+    ///   #         println!("Hello from DbService")
+    ///   #     }
+    ///   # }
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .on_created(|ctx: HandlerContext<Created>| {
+    ///               println!("File saved on disk: {:?}", ctx.file_info);
+    ///
+    ///               // Using rocket instance to get managed services
+    ///               let db_service = ctx.rocket.state::<DbService>().unwrap();
+    ///               db_service.say_hello();
+    ///           })
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    /// The above example adds a callback function that simply logs the file information after it has been successfully
+    /// created and saved to disk also demonstrates the use of the rocket instance to access managed services.
+    pub fn on_created<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(HandlerContext<Created>) + Send + Sync + 'static,
+    {
+        self.on_created = Some(Arc::new(callback));
+        self
+    }
+
+    /// Specifies a callback to be called when a file upload is completed.
+    ///
+    /// The `on_completed` callback function takes a [`HandlerContext<Completed>`] parameter and
+    /// is called once a file upload is completed. This allows users of the library to perform
+    /// additional actions after the file has been uploaded and processed.
+    ///
+    /// At this point is recommended to move uploads to a permanent/secure location, by default Meteoritus
+    /// is configured to auto-terminate after `on_completed` was invoked.
+    /// Consider add [` Meteoritus::keep_on_disk()`] in order to overwrite this.
+    ///
+    /// Timing metrics are available for performance analysis: [`FileInfo::upload_duration()`]
+    /// and the [`FileInfo::stats()`] of the `PATCH` requests, like their count and chunk sizes.
+    ///
+    /// # Examples
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::{Ignite, data::ByteUnit};
+    ///   use std::{fs, path::Path, str::from_utf8};
     ///   use meteoritus::{Completed, HandlerContext, Meteoritus};
     ///   # pub struct DbService {}
     ///   #
@@ -590,24 +2367,104 @@ impl Meteoritus<Build> {
     ///   #[launch]
     ///   fn rocket() -> _ {
     ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
-    ///           .on_termination(|ctx: HandlerContext<Terminated>| {
-    ///               println!("File was terminated by client: {:?}", ctx.file_info);
-    ///
-    ///               // Using rocket instance to get managed services
-    ///               let db_service = ctx.rocket.state::<DbService>().unwrap();
-    ///               db_service.say_hello();
+    ///           .on_termination(|ctx: HandlerContext<Terminated>| {
+    ///               println!("File was terminated by client: {:?}", ctx.file_info);
+    ///
+    ///               // Using rocket instance to get managed services
+    ///               let db_service = ctx.rocket.state::<DbService>().unwrap();
+    ///               db_service.say_hello();
+    ///           })
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    /// The above example adds a callback function that simply logs the file information after it has been terminated by a client request. Also demonstrates the use of the rocket instance to access managed services.
+    pub fn on_termination<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(HandlerContext<Terminated>) + Send + Sync + 'static,
+    {
+        self.on_termination = Some(Arc::new(callback));
+        self
+    }
+
+    /// Specifies a callback to be executed after a completed upload has been evicted.
+    ///
+    /// See [`Meteoritus::with_max_completed()`].
+    pub fn on_eviction<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(HandlerContext<Terminated>) + Send + Sync + 'static,
+    {
+        self.on_eviction = Some(Arc::new(callback));
+        self
+    }
+
+    /// Specifies a callback to be executed when a `PATCH` request carries an unexpected `Upload-Offset`.
+    ///
+    /// The callback is executed before the `409 Conflict` is answered. The [`HandlerContext`] holds the
+    /// upload with its current offset, the expected one, and the second parameter is the offset sent
+    /// by the client. It's useful to diagnose buggy clients or concurrent writers.
+    ///
+    /// # Examples
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::{Created, HandlerContext, Meteoritus};
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .on_offset_mismatch(|ctx: HandlerContext<Created>, offset: u64| {
+    ///               println!(
+    ///                   "Upload {} expected offset {} but got {}",
+    ///                   ctx.file_info.id(),
+    ///                   ctx.file_info.offset(),
+    ///                   offset
+    ///               );
+    ///           })
+    ///           .build();
+    ///     
+    ///       rocket::build().attach(meteoritus)
+    /// }
+    /// ```
+    pub fn on_offset_mismatch<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(HandlerContext<Created>, u64) + Send + Sync + 'static,
+    {
+        self.on_offset_mismatch = Some(Arc::new(callback));
+        self
+    }
+
+    /// Specifies a callback computing the url of the finalized resource of a completed upload.
+    ///
+    /// The callback is executed after `on_completed` and the returned url is sent to the client
+    /// in the `X-Resource-Location` header of the completing `PATCH` response.
+    ///
+    /// # Examples
+    ///   ```rust,no_run
+    ///   # #[macro_use] extern crate rocket;
+    ///   use rocket::Ignite;
+    ///   use meteoritus::{Completed, HandlerContext, Meteoritus};
+    ///
+    ///   #[launch]
+    ///   fn rocket() -> _ {
+    ///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+    ///           .with_resource_location(|ctx: HandlerContext<Completed>| {
+    ///               Some(format!("/files/{}", ctx.file_info.id()))
     ///           })
     ///           .build();
     ///     
     ///       rocket::build().attach(meteoritus)
     /// }
     /// ```
-    /// The above example adds a callback function that simply logs the file information after it has been terminated by a client request. Also demonstrates the use of the rocket instance to access managed services.
-    pub fn on_termination<F>(mut self, callback: F) -> Self
+    pub fn with_resource_location<F>(mut self, callback: F) -> Self
     where
-        F: Fn(HandlerContext<Terminated>) + Send + Sync + 'static,
+        F: Fn(HandlerContext<Completed>) -> Option<String>
+            + Send
+            + Sync
+            + 'static,
     {
-        self.on_termination = Some(Arc::new(callback));
+        self.resource_location = Some(Arc::new(callback));
         self
     }
 }
@@ -618,13 +2475,72 @@ impl Meteoritus<Ignite> {
         Meteoritus::<Orbit> {
             state: std::marker::PhantomData,
             auto_terminate: self.auto_terminate,
+            strict_termination: self.strict_termination,
+            soft_termination: self.soft_termination,
+            progress_header: self.progress_header,
+            dry_run: self.dry_run,
+            content_range: self.content_range,
+            empty_uploads: self.empty_uploads,
+            sniff_content: self.sniff_content,
             base_route: self.base_route,
+            cors_origin: self.cors_origin,
+            node_id: self.node_id,
+            trusted_proxies: self.trusted_proxies,
+            captured_headers: self.captured_headers,
+            location_format: self.location_format,
             max_size: self.max_size,
+            min_chunk_size: self.min_chunk_size,
+            preferred_chunk_size: self.preferred_chunk_size,
+            checksum_key: self.checksum_key,
+            chunk_checksums: self.chunk_checksums,
+            metadata_options: self.metadata_options,
+            expiration: self.expiration,
+            completed_retention: self.completed_retention,
+            completed_path: self.completed_path,
+            destination_attempts: self.destination_attempts,
+            destination_routes: self.destination_routes,
+            max_completed: self.max_completed.to_owned(),
+            fan_out_paths: self.fan_out_paths,
+            sweep_interval: self.sweep_interval,
+            error_statuses: self.error_statuses.to_owned(),
+            temp_path: self.temp_path,
+            min_free_inodes: self.min_free_inodes,
+            inode_stats: self.inode_stats.to_owned(),
+            custom_vault: self.custom_vault,
+            random_file_names: self.random_file_names,
+            durable_writes: self.durable_writes,
+            storage_retries: self.storage_retries,
+            retry_backoff: self.retry_backoff,
+            lookup_timeout: self.lookup_timeout,
+            lookups: self.lookups.to_owned(),
+            patch_streams: self.patch_streams.to_owned(),
+            detect_content_type: self.detect_content_type,
+            ring_capacity: self.ring_capacity,
+            downloads: self.downloads,
+            event_sink: self.event_sink.to_owned(),
+            body_transform: self.body_transform.to_owned(),
+            scanner: self.scanner.to_owned(),
+            scan_timeout: self.scan_timeout,
+            scan_verdicts: self.scan_verdicts.to_owned(),
+            event_buffer: self.event_buffer,
+            events: self.events.to_owned(),
+            usage: self.usage.to_owned(),
+            namespace_key: self.namespace_key,
+            key_provider: self.key_provider.to_owned(),
+            idempotency: self.idempotency.to_owned(),
+            chunk_failures: self.chunk_failures.to_owned(),
+            digests: self.digests.to_owned(),
+            readiness_path: self.readiness_path,
+            draining: self.draining.to_owned(),
             vault: self.vault.to_owned(),
             on_creation: self.on_creation.to_owned(),
             on_created: self.on_created.to_owned(),
             on_completed: self.on_completed.to_owned(),
             on_termination: self.on_termination.to_owned(),
+            resource_location: self.resource_location.to_owned(),
+            on_offset_mismatch: self.on_offset_mismatch.to_owned(),
+            on_eviction: self.on_eviction.to_owned(),
+            destination: self.destination.to_owned(),
         }
         /*  Consider Update to: #![feature(type_changing_struct_update)]
         Meteoritus::<Orbit> {
@@ -645,45 +2561,685 @@ impl Meteoritus<Orbit> {
         self.base_route
     }
 
+    /// Returns the format of the `Location` header answered on creation.
+    pub fn location_format(&self) -> &LocationFormat {
+        &self.location_format
+    }
+
+    /// Returns the origin allowed on CORS preflight requests.
+    pub fn cors_origin(&self) -> &str {
+        self.cors_origin
+    }
+
+    /// Returns the number of proxy hops trusted to append `X-Forwarded-For`.
+    pub fn trusted_proxies(&self) -> usize {
+        self.trusted_proxies
+    }
+
+    /// Returns the request headers recorded on creation.
+    pub fn captured_headers(&self) -> &[&'static str] {
+        self.captured_headers
+    }
+
     /// Indicates if completed uploads should be auto deleted from disk.
     pub fn auto_terminate(&self) -> bool {
         self.auto_terminate
     }
 
+    /// Indicates if termination requests carrying a body should be rejected.
+    pub fn strict_termination(&self) -> bool {
+        self.strict_termination
+    }
+
+    /// Returns how long terminated uploads are kept before being purged.
+    pub fn soft_termination(&self) -> Option<Duration> {
+        self.soft_termination
+    }
+
+    /// Restores a soft terminated upload that has not been purged yet.
+    ///
+    /// See [`Meteoritus::with_soft_termination()`].
+    pub fn restore(&self, id: &str) -> Option<FileInfo<Created>> {
+        self.vault.restore_file(id).ok()
+    }
+
+    /// Indicates if uploads with `Upload-Length: 0` are accepted.
+    pub fn empty_uploads_allowed(&self) -> bool {
+        self.empty_uploads
+    }
+
+    /// Indicates if uploads are checked against their declared `filetype`.
+    pub fn sniff_content(&self) -> bool {
+        self.sniff_content
+    }
+
+    /// Indicates if the `Content-Range` header of `PATCH` requests is validated.
+    pub fn validates_content_range(&self) -> bool {
+        self.content_range
+    }
+
+    /// Indicates if dry-run creation requests are allowed.
+    pub fn dry_run_allowed(&self) -> bool {
+        self.dry_run
+    }
+
+    /// Indicates if `HEAD` responses should include the `X-Upload-Progress` header.
+    pub fn progress_header(&self) -> bool {
+        self.progress_header
+    }
+
+    /// Indicates if completed uploads can be downloaded with `GET` requests.
+    pub fn downloads(&self) -> bool {
+        self.downloads
+    }
+
     /// Returns the maximum allowed upload size.
     pub fn max_size(&self) -> ByteUnit {
         self.max_size
     }
 
-    pub(crate) fn on_creation(
+    /// Returns the minimum size of a chunk not completing the upload.
+    pub fn min_chunk_size(&self) -> Option<ByteUnit> {
+        self.min_chunk_size
+    }
+
+    /// Returns the chunk size suggested to clients.
+    pub fn preferred_chunk_size(&self) -> Option<ByteUnit> {
+        self.preferred_chunk_size
+    }
+
+    /// Bounds a client chunk size hint by the configured chunk sizes.
+    pub(crate) fn accept_chunk_size(&self, hint: u64) -> Option<u64> {
+        self.preferred_chunk_size?;
+
+        let min = self.min_chunk_size.map_or(1, |size| size.as_u64());
+        Some(hint.clamp(min, self.max_size.as_u64().max(min)))
+    }
+
+    /// Returns the metadata key carrying the expected full-file digest.
+    pub fn checksum_key(&self) -> Option<&str> {
+        self.checksum_key
+    }
+
+    /// Indicates if chunks are verified against their `Upload-Checksum` header.
+    pub fn chunk_checksums(&self) -> bool {
+        self.chunk_checksums
+    }
+
+    /// Returns the limits applied while parsing upload metadata.
+    pub fn metadata_options(&self) -> &MetadataOptions {
+        &self.metadata_options
+    }
+
+    /// Returns the configured upload expiration.
+    pub fn expiration(&self) -> &Expiration {
+        &self.expiration
+    }
+
+    /// Returns the maximum time a `HEAD` request waits for the storage.
+    pub fn lookup_timeout(&self) -> Duration {
+        self.lookup_timeout
+    }
+
+    /// Returns the maximum time a download waits for a scan verdict.
+    pub fn scan_timeout(&self) -> Duration {
+        self.scan_timeout
+    }
+
+    /// Returns the directories completed uploads are linked into.
+    pub fn fan_out_paths(&self) -> &'static [&'static str] {
+        self.fan_out_paths
+    }
+
+    /// Returns the directory completed uploads are moved into.
+    pub fn completed_path(&self) -> Option<&str> {
+        self.completed_path
+    }
+
+    /// Returns how long completed uploads are kept on disk.
+    pub fn completed_retention(&self) -> Option<Duration> {
+        self.completed_retention
+    }
+
+    /// Returns the [`Status`] answered for the given [`MeteoritusError`].
+    pub fn error_status(&self, error: MeteoritusError) -> Status {
+        match self.error_statuses.get(&error) {
+            Some(status) => *status,
+            None => error.default_status(),
+        }
+    }
+
+    /// Removes all expired uploads and completed uploads older than the retention window.
+    pub(crate) fn sweep(&self, now: SystemTime) {
+        let Ok(files) = self.vault.list_files() else {
+            return;
+        };
+
+        let swept: Vec<String> = files
+            .filter(|file| {
+                let deadline = if file.is_completed() {
+                    let retention = self.completed_retention;
+                    retention.zip(*file.completed_at()).map(|(d, t)| t + d)
+                } else {
+                    file.expires_with(&self.expiration)
+                };
+
+                deadline.is_some_and(|deadline| deadline <= now)
+            })
+            .map(|file| file.id().to_string())
+            .collect();
+
+        for file_id in swept {
+            self.digests.forget(&file_id);
+
+            if let Ok(file) = self.vault.terminate_file(&file_id) {
+                self.emit(EventKind::Expired, &file);
+            }
+        }
+
+        self.purge_tombstones(now);
+    }
+
+    /// Removes the soft terminated uploads older than the termination window.
+    fn purge_tombstones(&self, now: SystemTime) {
+        let Some(window) = self.soft_termination else {
+            return;
+        };
+
+        let Ok(tombstones) = self.vault.list_tombstones() else {
+            return;
+        };
+
+        let purged: Vec<String> = tombstones
+            .filter(|file| {
+                file.terminated_at().is_some_and(|t| t + window <= now)
+            })
+            .map(|file| file.id().to_string())
+            .collect();
+
+        for file_id in purged {
+            let _ = self.vault.terminate_file(&file_id);
+        }
+    }
+
+    /// Returns the completed upload volume by value of the accounting metadata key.
+    ///
+    /// Empty unless [`Meteoritus::with_usage_accounting()`] has been configured.
+    pub fn usage(&self) -> UsageReport {
+        match &self.usage {
+            Some(usage) => usage.snapshot(),
+            None => Default::default(),
+        }
+    }
+
+    /// Returns the uploads stored in the given namespace, see [`Meteoritus::with_namespace_key()`].
+    pub fn namespace_files(&self, namespace: &str) -> Vec<FileInfo<Created>> {
+        match self.vault.list_namespace(namespace) {
+            Ok(files) => files.collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Terminates every upload of the given namespace, returns the number of terminated uploads.
+    ///
+    /// Completed uploads still kept in the temp path are terminated as well, uploads already
+    /// moved to their final destination are left untouched.
+    pub fn purge_namespace(&self, namespace: &str) -> usize {
+        self.namespace_files(namespace)
+            .iter()
+            .filter(|file| self.vault.terminate_file(file.id()).is_ok())
+            .count()
+    }
+
+    /// Stops accepting new uploads, the uploads in progress can still be completed.
+    ///
+    /// Creation requests are then answered with `503 Service Unavailable`, while `HEAD`,
+    /// `PATCH` and `DELETE` requests are served as usual. A drained node doesn't accept
+    /// uploads again, it is expected to be terminated.
+    pub fn drain(&self) {
+        self.draining.store(true, Ordering::Relaxed);
+    }
+
+    /// Indicates if the node is draining, see [`Meteoritus::drain()`].
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+
+    /// Returns the readiness of the node along with the number of uploads in progress.
+    ///
+    /// Uploads in progress are counted from the storage, expired ones included until swept.
+    /// The storage is listed on the calling thread, a storage that can't be listed is
+    /// reported as [`ReadinessStatus::Unavailable`](crate::ReadinessStatus::Unavailable).
+    pub fn readiness(&self) -> Readiness {
+        Readiness::new(
+            self.is_draining(),
+            count_in_progress(self.vault.as_ref()),
+        )
+    }
+
+    /// Indicates if enough inodes are left to create an upload, `true` when unknown.
+    pub(crate) fn has_free_inodes(&self) -> bool {
+        let Some(min_inodes) = self.min_free_inodes else {
+            return true;
+        };
+
+        /* The temp path may not be created yet */
+        let available =
+            Path::new(self.temp_path)
+                .ancestors()
+                .find_map(|path| match &self.inode_stats {
+                    Some(stats) => stats.available_inodes(path),
+                    None => FilesystemInodes.available_inodes(path),
+                });
+
+        match available {
+            Some(available) => available >= min_inodes,
+            None => true,
+        }
+    }
+
+    /// Takes a permit to look up an upload, `None` when all lookups are busy.
+    pub(crate) fn acquire_lookup(&self) -> Option<OwnedSemaphorePermit> {
+        self.lookups.to_owned().try_acquire_owned().ok()
+    }
+
+    /// Takes a stream permit for a `PATCH` body, `None` when streams are not limited.
+    ///
+    /// Fails with the `Retry-After` delay when all streams are busy.
+    pub(crate) fn acquire_patch_stream(
         &self,
-    ) -> &Option<
-        Arc<
-            dyn Fn(HandlerContext<Built>) -> Result<(), Box<dyn Error>>
-                + Send
-                + Sync,
-        >,
-    > {
-        &self.on_creation
+    ) -> Result<Option<OwnedSemaphorePermit>, Duration> {
+        let Some((streams, retry_after)) = &self.patch_streams else {
+            return Ok(None);
+        };
+
+        match streams.to_owned().try_acquire_owned() {
+            Ok(permit) => Ok(Some(permit)),
+            Err(_) => Err(*retry_after),
+        }
+    }
+
+    pub(crate) fn body_transform(&self) -> &Option<Arc<dyn BodyTransform>> {
+        &self.body_transform
     }
 
-    pub(crate) fn on_created(
+    /// Scans a kept completed upload in the background, if a scanner is configured.
+    ///
+    /// The upload is marked as scanning before the scan starts, so it can't be downloaded
+    /// before the verdict is recorded. A clean upload is fanned out before it is released,
+    /// an infected one is unlinked from the fan-out directories as well. Returns whether
+    /// the upload is held for a scan.
+    pub(crate) fn scan_upload(
         &self,
-    ) -> &Option<Arc<dyn Fn(HandlerContext<Created>) + Send + Sync>> {
-        &self.on_created
+        vault: &Arc<dyn Vault>,
+        file: &FileInfo<Completed>,
+    ) -> bool {
+        let Some(scanner) = self.scanner.to_owned() else {
+            return false;
+        };
+
+        let file = match vault.set_scan_state(file.id(), ScanState::Scanning) {
+            Ok(file) => file,
+            Err(e) => {
+                error!("Failed to hold upload {} for scan: {:?}", file.id(), e);
+                return false;
+            }
+        };
+
+        let (vault, events) = (vault.to_owned(), self.events.to_owned());
+        let (paths, verdicts) =
+            (self.fan_out_paths, self.scan_verdicts.to_owned());
+
+        rocket::tokio::spawn(async move {
+            let (state, kind) = match scanner.scan(&file).await {
+                ScanVerdict::Clean => {
+                    fan_out(paths, &file).await;
+                    (ScanState::Clean, EventKind::Released)
+                }
+                ScanVerdict::Infected => {
+                    (ScanState::Infected, EventKind::Quarantined)
+                }
+            };
+
+            let file_id = file.id().to_string();
+            let scanned = spawn_blocking(move || {
+                if state == ScanState::Infected {
+                    unlink_fan_out(paths, &file_id);
+                }
+
+                vault
+                    .set_scan_state(&file_id, state)
+                    .map_err(|e| format!("{:?}", e))
+            });
+
+            let scanned = scanned.await;
+            verdicts.notify_waiters();
+
+            match scanned {
+                Ok(Ok(file)) => {
+                    if let Some(events) = events {
+                        events.emit(MeteoritusEvent::new(kind, &file));
+                    }
+                }
+                Ok(Err(e)) => {
+                    error!("Failed to record scan of {}: {}", file.id(), e)
+                }
+                Err(_) => error!("Failed to record scan of {}", file.id()),
+            }
+        });
+
+        true
     }
 
-    pub(crate) fn on_completed(
+    /// Waits for the scan verdict of an upload still scanning, at most for the scan timeout.
+    ///
+    /// The latest known state of the upload is returned, still scanning once timed out.
+    #[cfg(feature = "download")]
+    pub(crate) async fn await_verdict(
         &self,
-    ) -> &Option<Arc<dyn Fn(HandlerContext<Completed>) + Send + Sync>> {
-        &self.on_completed
+        vault: &Arc<dyn Vault>,
+        mut file: FileInfo<Created>,
+    ) -> FileInfo<Created> {
+        let deadline = Instant::now() + self.scan_timeout;
+
+        while *file.scan_state() == Some(ScanState::Scanning) {
+            let verdict = self.scan_verdicts.notified();
+            pin!(verdict);
+
+            /* Looked up again once waiting, so a verdict recorded meanwhile isn't missed */
+            verdict.as_mut().enable();
+
+            let (vault, id) = (vault.to_owned(), file.id().to_string());
+            match spawn_blocking(move || vault.get_file(&id).ok()).await {
+                Ok(Some(latest)) => file = latest,
+                _ => break,
+            }
+
+            if *file.scan_state() != Some(ScanState::Scanning)
+                || timeout_at(deadline, verdict).await.is_err()
+            {
+                break;
+            }
+        }
+
+        file
+    }
+
+    /// Returns the resulting offset of an already applied `PATCH` with the same idempotency key.
+    pub(crate) fn replay_patch(
+        &self,
+        id: &str,
+        key: &str,
+        offset: u64,
+    ) -> Option<u64> {
+        self.idempotency.as_ref()?.replay(id, key, offset)
+    }
+
+    pub(crate) fn remember_patch(
+        &self,
+        id: &str,
+        key: &str,
+        offset: u64,
+        resulting: u64,
+    ) {
+        if let Some(idempotency) = &self.idempotency {
+            idempotency.remember(id, key, offset, resulting);
+        }
+    }
+
+    /// Returns the namespace of a new upload with the given metadata, if any.
+    ///
+    /// Fails when the metadata value is not a valid namespace.
+    pub(crate) fn upload_namespace(
+        &self,
+        metadata: &Option<Metadata>,
+    ) -> Result<Option<String>, MeteoritusError> {
+        let Some(key) = self.namespace_key else {
+            return Ok(None);
+        };
+
+        let Some(raw) = metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get_raw(key).ok())
+        else {
+            return Ok(None);
+        };
+
+        match String::from_utf8(raw) {
+            Ok(namespace) if is_valid_namespace(&namespace) => {
+                Ok(Some(namespace))
+            }
+            _ => Err(MeteoritusError::InvalidMetadata),
+        }
+    }
+
+    /// Returns the directory an upload with the given metadata is routed to on completion.
+    ///
+    /// The outer `None` means no routes are configured, the inner one that the upload is rejected.
+    pub(crate) fn route_destination(
+        &self,
+        metadata: &Option<Metadata>,
+    ) -> Option<Option<&'static str>> {
+        Some(self.destination_routes?.resolve(metadata))
+    }
+
+    /// Records a failed chunk of an upload, returns `true` once it has to be aborted.
+    pub(crate) fn record_chunk_failure(&self, id: &str) -> bool {
+        match &self.chunk_failures {
+            Some(failures) => failures.record(id),
+            None => false,
+        }
+    }
+
+    pub(crate) fn reset_chunk_failures(&self, id: &str) {
+        if let Some(failures) = &self.chunk_failures {
+            failures.reset(id);
+        }
+    }
+
+    /// Returns the full file digests of the uploads computed as their chunks arrive.
+    pub(crate) fn rolling_digests(&self) -> &RollingDigests {
+        &self.digests
+    }
+
+    /// Indicates if an upload was aborted for too many failed chunks.
+    pub(crate) fn is_aborted(&self, id: &str) -> bool {
+        match &self.chunk_failures {
+            Some(failures) => failures.is_aborted(id),
+            None => false,
+        }
+    }
+
+    /// Reserves the idempotency key of a creation sent by `client`.
+    pub(crate) fn reserve_creation(
+        &self,
+        client: Option<IpAddr>,
+        key: &str,
+        fingerprint: &str,
+        exists: impl Fn(&str) -> bool,
+    ) -> Reservation {
+        match &self.idempotency {
+            Some(idempotency) => {
+                idempotency.reserve_creation(client, key, fingerprint, exists)
+            }
+            None => Reservation::Reserved,
+        }
+    }
+
+    pub(crate) fn complete_creation(
+        &self,
+        client: Option<IpAddr>,
+        key: &str,
+        id: &str,
+    ) {
+        if let Some(idempotency) = &self.idempotency {
+            idempotency.complete_creation(client, key, id);
+        }
+    }
+
+    pub(crate) fn release_creation(&self, client: Option<IpAddr>, key: &str) {
+        if let Some(idempotency) = &self.idempotency {
+            idempotency.release_creation(client, key);
+        }
+    }
+
+    /// Returns the check digest of the key with the given id, if the key provider knows it.
+    pub(crate) fn key_check(&self, key_id: &str) -> Option<String> {
+        let key = self.key_provider.as_ref()?.key(key_id)?;
+        Some(UploadCipher::key_check(&key))
+    }
+
+    /// Fetches the cipher of an encrypted upload, `None` for uploads stored as is.
+    ///
+    /// Fails if the key is unavailable or differs from the one the upload was created with.
+    pub(crate) fn upload_cipher<State>(
+        &self,
+        file: &FileInfo<State>,
+    ) -> Result<Option<UploadCipher>, MeteoritusError> {
+        let Some(key_id) = file.key_id() else {
+            return Ok(None);
+        };
+
+        let key = self
+            .key_provider
+            .as_ref()
+            .and_then(|provider| provider.key(key_id));
+
+        match key {
+            Some(key)
+                if Some(UploadCipher::key_check(&key)) == *file.key_check() =>
+            {
+                Ok(Some(UploadCipher::new(key, file.id())))
+            }
+            _ => {
+                error!("Key {} of upload {} is unavailable", key_id, file.id());
+                Err(MeteoritusError::Internal)
+            }
+        }
+    }
+
+    pub(crate) fn record_usage(&self, file: &FileInfo<Completed>) {
+        if let Some(usage) = &self.usage {
+            usage.record(file);
+        }
+    }
+
+    /// Emits a lifecycle event for the given file, if an [`EventSink`] is configured.
+    pub(crate) fn emit<State>(&self, kind: EventKind, file: &FileInfo<State>) {
+        if let Some(events) = &self.events {
+            events.emit(MeteoritusEvent::new(kind, file));
+        }
+    }
+
+    /// Returns a reporter emitting the progress of a completed file being copied to its
+    /// final destination, it can be moved to the blocking thread pool.
+    pub(crate) fn moving_reporter<State>(
+        &self,
+        file: &FileInfo<State>,
+    ) -> impl FnMut(u64) + Send + 'static {
+        let events = self.events.to_owned();
+        let event = MeteoritusEvent::new(EventKind::Moving, file);
+
+        move |moved| {
+            if let Some(events) = &events {
+                let event = MeteoritusEvent {
+                    emitted_at: SystemTime::now(),
+                    ..event.to_owned()
+                };
+
+                events.emit(event.with_moved_bytes(moved));
+            }
+        }
     }
 
-    pub(crate) fn on_termination(
+    /// Removes the oldest completed uploads exceeding the configured maximum count.
+    pub(crate) fn evict_completed(
         &self,
-    ) -> &Option<Arc<dyn Fn(HandlerContext<Terminated>) + Send + Sync>> {
+        rocket: &Rocket<Orbit>,
+        file: &FileInfo<Completed>,
+    ) {
+        let (Some(max_completed), Some(completed_at)) =
+            (&self.max_completed, file.completed_at())
+        else {
+            return;
+        };
+
+        let stored = || {
+            self.vault
+                .list_files()
+                .into_iter()
+                .flatten()
+                .filter_map(|file| {
+                    Some(((*file.completed_at())?, file.id().into()))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for file_id in max_completed.push(*completed_at, file.id(), stored) {
+            /* Uploads already removed by other means are only dropped from the queue */
+            let Ok(file) = self.vault.terminate_file(&file_id) else {
+                continue;
+            };
+
+            /* Moved files are evicted too, they still take up staging */
+            match std::fs::remove_file(file.file_name()) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    error!("Failed to evict {}: {}", file.file_name(), e)
+                }
+                _ => {}
+            }
+
+            if let Some(callback) = &self.on_eviction {
+                guard_callback("on_eviction", || {
+                    callback(HandlerContext {
+                        rocket,
+                        file_info: &file,
+                    })
+                });
+            }
+        }
+    }
+
+    fn should_sweep(&self) -> bool {
+        self.expiration.is_enabled()
+            || self.completed_retention.is_some()
+            || self.soft_termination.is_some()
+    }
+
+    pub(crate) fn on_creation(&self) -> &Option<CreationCallback> {
+        &self.on_creation
+    }
+
+    pub(crate) fn on_created(&self) -> &Option<CreatedCallback> {
+        &self.on_created
+    }
+
+    pub(crate) fn on_completed(&self) -> &Option<CompletedCallback> {
+        &self.on_completed
+    }
+
+    pub(crate) fn on_termination(&self) -> &Option<TerminationCallback> {
         &self.on_termination
     }
+
+    pub(crate) fn on_offset_mismatch(&self) -> &Option<OffsetMismatchCallback> {
+        &self.on_offset_mismatch
+    }
+
+    pub(crate) fn resource_location(
+        &self,
+    ) -> &Option<ResourceLocationCallback> {
+        &self.resource_location
+    }
+
+    pub(crate) fn destination(&self) -> &Option<DestinationCallback> {
+        &self.destination
+    }
+
+    /// Returns the number of paths asked to the destination callback.
+    pub fn destination_attempts(&self) -> u32 {
+        self.destination_attempts
+    }
 }
 
 #[rocket::async_trait]
@@ -691,22 +3247,115 @@ impl Fairing for Meteoritus<Ignite> {
     fn info(&self) -> Info {
         Info {
             name: "Meteoritus",
-            kind: Kind::Ignite,
+            kind: Kind::Ignite | Kind::Liftoff | Kind::Response,
+        }
+    }
+
+    async fn on_response<'r>(
+        &self,
+        req: &'r Request<'_>,
+        res: &mut Response<'r>,
+    ) {
+        /* Matching whole segments, `/meteoritus-admin` is not under `/meteoritus` */
+        let base = self.base_route.trim_end_matches('/');
+        let below_base = req
+            .uri()
+            .path()
+            .as_str()
+            .strip_prefix(base)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'));
+
+        if !below_base {
+            return;
+        }
+
+        /* Cross-origin clients can only read the tus headers once exposed */
+        res.set_raw_header("Access-Control-Allow-Origin", self.cors_origin);
+        res.set_raw_header(
+            "Access-Control-Expose-Headers",
+            CORS_EXPOSED_HEADERS,
+        );
+
+        if let Some(node_id) = self.node_id {
+            res.set_raw_header(
+                "X-Upload-Node",
+                format!("{} meteoritus/{}", node_id, env!("CARGO_PKG_VERSION")),
+            );
+        }
+    }
+
+    async fn on_liftoff(&self, rocket: &Rocket<Orbit>) {
+        let mut meteoritus = self.launch();
+
+        /* The sweeper emits on the event bus spawned for the routes on ignite */
+        meteoritus.events = rocket
+            .state::<Meteoritus<Orbit>>()
+            .and_then(|launched| launched.events.to_owned());
+
+        let meteoritus = Arc::new(meteoritus);
+
+        if !meteoritus.should_sweep() {
+            return;
         }
+
+        rocket::tokio::spawn(async move {
+            let mut interval = interval(meteoritus.sweep_interval);
+
+            loop {
+                interval.tick().await;
+
+                let meteoritus = meteoritus.to_owned();
+                let sweep = move || meteoritus.sweep(SystemTime::now());
+
+                let _ = spawn_blocking(sweep).await;
+            }
+        });
     }
 
     async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
-        let routes = routes![
+        if self.custom_vault && self.has_local_vault_options() {
+            error!("Local vault options can not be applied to a custom vault");
+            return Err(rocket);
+        }
+
+        #[cfg(feature = "ring")]
+        if let Some(conflict) = self.ring_conflict() {
+            error!("A ring buffer can not be combined with {}", conflict);
+            return Err(rocket);
+        }
+
+        let mut routes = routes![
             creation_handler,
             info_handler,
+            file_options_handler,
             file_info_handler,
             termination_handler,
             upload_handler,
         ];
 
-        Ok(rocket
-            .manage(self.launch())
+        #[cfg(feature = "download")]
+        if self.downloads {
+            routes.extend(routes![crate::handlers::download_handler]);
+        }
+
+        /* Unknown methods are answered with the allowed ones instead of a 404 */
+        routes.extend(method_not_allowed_routes(self.downloads));
+
+        let mut meteoritus = self.launch();
+
+        if let Some(sink) = &self.event_sink {
+            let events = EventBus::spawn(sink.to_owned(), self.event_buffer);
+            meteoritus.events = Some(events);
+        }
+
+        let rocket = rocket
+            .manage(meteoritus)
             .manage(self.vault.to_owned())
-            .mount(self.base_route, routes))
+            .mount(self.base_route, routes);
+
+        match self.readiness_path {
+            Some(path) => Ok(rocket.mount(path, routes![readiness_handler])),
+            None => Ok(rocket),
+        }
     }
 }
@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use rocket::tokio::{
+    io::AsyncWriteExt,
+    net::TcpStream,
+    sync::Mutex,
+    time::{self, error::Elapsed},
+};
+
+use crate::event::{EventSink, MeteoritusEvent};
+
+/// Bounds connecting to the server and writing a single event.
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An [`EventSink`] publishing every event as JSON to a NATS subject.
+///
+/// It speaks the core NATS protocol over a single connection, which is opened on the first
+/// event and reopened on the next one after any failure. Events failing to publish are
+/// logged and dropped.
+///
+/// # Example
+///
+///   ```rust,no_run
+///   # #[macro_use] extern crate rocket;
+///   use rocket::Ignite;
+///   use meteoritus::{Meteoritus, NatsSink};
+///
+///   #[launch]
+///   fn rocket() -> _ {
+///       let meteoritus: Meteoritus<Ignite> = Meteoritus::new()
+///           .with_event_sink(NatsSink::new("127.0.0.1:4222", "uploads"))
+///           .build();
+///
+///       rocket::build().attach(meteoritus)
+/// }
+///   ```
+pub struct NatsSink {
+    address: String,
+    subject: String,
+    connection: Mutex<Option<TcpStream>>,
+}
+
+impl NatsSink {
+    /// Creates a sink publishing to `subject` on the NATS server at `address`.
+    pub fn new(address: impl Into<String>, subject: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            subject: subject.into(),
+            connection: Mutex::new(None),
+        }
+    }
+
+    async fn connect(&self) -> std::io::Result<TcpStream> {
+        let mut stream = TcpStream::connect(&self.address).await?;
+
+        stream
+            .write_all(b"CONNECT {\"verbose\":false,\"pedantic\":false}\r\n")
+            .await?;
+
+        Ok(stream)
+    }
+
+    async fn send(
+        &self,
+        connection: &mut Option<TcpStream>,
+        payload: &[u8],
+    ) -> std::io::Result<()> {
+        let stream = match connection {
+            Some(stream) => stream,
+            None => connection.insert(self.connect().await?),
+        };
+
+        answer_pings(stream).await?;
+
+        let mut message =
+            format!("PUB {} {}\r\n", self.subject, payload.len()).into_bytes();
+        message.extend_from_slice(payload);
+        message.extend_from_slice(b"\r\n");
+
+        stream.write_all(&message).await
+    }
+}
+
+/// Answers the keep-alive pings received since the last event, the server closes connections
+/// leaving them unanswered.
+async fn answer_pings(stream: &mut TcpStream) -> std::io::Result<()> {
+    let mut received = Vec::new();
+    let mut buf = [0; 1024];
+
+    loop {
+        match stream.try_read(&mut buf) {
+            Ok(0) => return Err(std::io::ErrorKind::ConnectionReset.into()),
+            Ok(read) => received.extend_from_slice(&buf[..read]),
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+            Err(e) => return Err(e),
+        }
+    }
+
+    if received.windows(4).any(|w| w == b"PING") {
+        stream.write_all(b"PONG\r\n").await?;
+    }
+
+    Ok(())
+}
+
+#[rocket::async_trait]
+impl EventSink for NatsSink {
+    async fn publish(&self, event: MeteoritusEvent) {
+        let payload = match serde_json::to_vec(&event) {
+            Ok(payload) => payload,
+            Err(e) => {
+                error!("Failed to serialize event for {}: {}", event.id, e);
+                return;
+            }
+        };
+
+        let mut connection = self.connection.lock().await;
+
+        let sent = time::timeout(
+            PUBLISH_TIMEOUT,
+            self.send(&mut connection, &payload),
+        )
+        .await
+        .unwrap_or_else(|e: Elapsed| Err(e.into()));
+
+        if let Err(e) = sent {
+            error!(
+                "Failed to publish {:?} event for {} to NATS: {}",
+                event.kind, event.id, e
+            );
+
+            /* Reconnecting on the next event */
+            *connection = None;
+        }
+    }
+}
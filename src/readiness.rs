@@ -0,0 +1,49 @@
+use rocket::serde::Serialize;
+
+use crate::Vault;
+
+/// Represents the stage of a node reported by its readiness probe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(crate = "rocket::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum ReadinessStatus {
+    /// New uploads are accepted.
+    Ready,
+    /// New uploads are refused, some uploads are still in progress.
+    Draining,
+    /// New uploads are refused and no upload is in progress anymore.
+    Drained,
+    /// The storage could not be listed, so the uploads in progress are unknown.
+    Unavailable,
+}
+
+/// Represents the readiness of a node, see [`Meteoritus::readiness()`](crate::Meteoritus::readiness).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Readiness {
+    pub status: ReadinessStatus,
+    /// Number of created uploads still waiting for bytes, `None` when the storage could not be listed.
+    pub in_progress: Option<usize>,
+}
+
+impl Readiness {
+    /// Combines the draining state of a node with the uploads it has in progress.
+    pub(crate) fn new(draining: bool, in_progress: Option<usize>) -> Self {
+        let status = match (draining, in_progress) {
+            (_, None) => ReadinessStatus::Unavailable,
+            (false, Some(_)) => ReadinessStatus::Ready,
+            (true, Some(0)) => ReadinessStatus::Drained,
+            (true, Some(_)) => ReadinessStatus::Draining,
+        };
+
+        Self {
+            status,
+            in_progress,
+        }
+    }
+}
+
+/// Counts the uploads still waiting for bytes, `None` when the storage can't be listed.
+pub(crate) fn count_in_progress(vault: &dyn Vault) -> Option<usize> {
+    vault.list_incomplete().ok().map(|files| files.count())
+}
@@ -0,0 +1,43 @@
+use crate::fs::Metadata;
+
+/// Maps the value of a metadata key to the directory completed uploads are moved to.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DestinationRoutes {
+    key: &'static str,
+    routes: &'static [(&'static str, &'static str)],
+    fallback: Option<&'static str>,
+}
+
+impl DestinationRoutes {
+    pub(crate) fn new(
+        key: &'static str,
+        routes: &'static [(&'static str, &'static str)],
+        fallback: Option<&'static str>,
+    ) -> Self {
+        Self {
+            key,
+            routes,
+            fallback,
+        }
+    }
+
+    /// Returns the directory of an upload with the given metadata, `None` if it is rejected.
+    pub(crate) fn resolve(
+        &self,
+        metadata: &Option<Metadata>,
+    ) -> Option<&'static str> {
+        let value = metadata
+            .as_ref()
+            .and_then(|metadata| metadata.get_raw(self.key).ok())
+            .and_then(|raw| String::from_utf8(raw).ok());
+
+        let routed = value.and_then(|value| {
+            self.routes
+                .iter()
+                .find(|(route, _)| *route == value)
+                .map(|(_, path)| *path)
+        });
+
+        routed.or(self.fallback)
+    }
+}
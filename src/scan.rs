@@ -0,0 +1,39 @@
+use crate::fs::{Completed, FileInfo};
+
+/// The verdict of an [`UploadScanner`] on a completed upload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanVerdict {
+    /// The file is released for download.
+    Clean,
+    /// The file is quarantined, its data is deleted.
+    Infected,
+}
+
+/// A scan run on every completed upload before it is released for download, like an antivirus.
+///
+/// The completing `PATCH` request is answered right away while the scan runs in a background
+/// task. Until its verdict, `HEAD` requests report `X-Upload-Scan: scanning` and downloads wait
+/// for it, refused with `423 Locked` past the scan timeout. Infected uploads keep reporting
+/// `X-Upload-Scan: infected` and their downloads are refused with `403 Forbidden`.
+///
+/// # Example
+///
+///   ```rust,no_run
+///   use meteoritus::{Completed, FileInfo, ScanVerdict, UploadScanner};
+///
+///   struct RejectExecutables;
+///
+///   #[rocket::async_trait]
+///   impl UploadScanner for RejectExecutables {
+///       async fn scan(&self, file: &FileInfo<Completed>) -> ScanVerdict {
+///           match rocket::tokio::fs::read(file.file_name()).await {
+///               Ok(bytes) if !bytes.starts_with(b"MZ") => ScanVerdict::Clean,
+///               _ => ScanVerdict::Infected,
+///           }
+///       }
+///   }
+///   ```
+#[rocket::async_trait]
+pub trait UploadScanner: Send + Sync {
+    async fn scan(&self, file: &FileInfo<Completed>) -> ScanVerdict;
+}
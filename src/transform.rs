@@ -0,0 +1,29 @@
+/// A streaming transform applied to every chunk between the request body and the vault.
+///
+/// It can inspect or rewrite the bytes of an upload as they arrive, like feeding extra
+/// hashes or stripping sensitive data. Chunks are transformed in order, each one along
+/// with the upload offset it is written at. Only chunks sent at the current upload offset
+/// reach the transform, retried or mismatched chunks are rejected before it.
+///
+/// The transform must preserve the chunk length, since clients keep accounting offsets
+/// in terms of the bytes they sent. A chunk transformed to another length is rejected
+/// with `422 Unprocessable Entity` without being written.
+///
+/// # Example
+///
+///   ```rust,no_run
+///   use meteoritus::BodyTransform;
+///
+///   struct Uppercase;
+///
+///   #[rocket::async_trait]
+///   impl BodyTransform for Uppercase {
+///       async fn transform(&self, _offset: u64, chunk: Vec<u8>) -> Vec<u8> {
+///           chunk.to_ascii_uppercase()
+///       }
+///   }
+///   ```
+#[rocket::async_trait]
+pub trait BodyTransform: Send + Sync {
+    async fn transform(&self, offset: u64, chunk: Vec<u8>) -> Vec<u8>;
+}
@@ -0,0 +1,76 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use crate::fs::{Completed, FileInfo};
+
+/// Upload volume accounted for a single value of the accounting metadata key.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct UploadUsage {
+    /// Number of completed uploads.
+    pub uploads: u64,
+    /// Total bytes of completed uploads.
+    pub bytes: u64,
+}
+
+/// Completed upload volume broken down by the value of the accounting metadata key.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UsageReport {
+    /// Volume of each tracked value, at most the configured number of values.
+    pub values: HashMap<String, UploadUsage>,
+    /// Volume of uploads missing the key, or with a value beyond the configured cardinality.
+    pub other: UploadUsage,
+}
+
+/// Tracks completed upload volume broken down by a metadata key.
+#[derive(Debug, Clone)]
+pub(crate) struct UsageLedger {
+    key: &'static str,
+    max_values: usize,
+    report: Arc<Mutex<UsageReport>>,
+}
+
+impl UsageLedger {
+    pub(crate) fn new(key: &'static str, max_values: usize) -> Self {
+        Self {
+            key,
+            max_values,
+            report: Default::default(),
+        }
+    }
+
+    pub(crate) fn record(&self, file: &FileInfo<Completed>) {
+        let value = file
+            .metadata()
+            .as_ref()
+            .and_then(|metadata| metadata.get_raw(self.key).ok())
+            .and_then(|raw| String::from_utf8(raw).ok());
+
+        let Ok(mut report) = self.report.lock() else {
+            return;
+        };
+
+        let UsageReport { values, other } = &mut *report;
+
+        let usage = match value {
+            Some(value)
+                if values.contains_key(&value)
+                    || values.len() < self.max_values =>
+            {
+                values.entry(value).or_default()
+            }
+            _ => other,
+        };
+
+        usage.uploads += 1;
+        usage.bytes += *file.length();
+    }
+
+    pub(crate) fn snapshot(&self) -> UsageReport {
+        match self.report.lock() {
+            Ok(report) => report.to_owned(),
+            Err(_) => Default::default(),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use common::{temp_path, tus_header, MemorySink};
+use meteoritus::{EventKind, Meteoritus};
+use rocket::{
+    http::{Header, Status},
+    local::asynchronous::Client,
+    tokio::time::sleep,
+    Build,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+async fn client(meteoritus: Meteoritus<Build>) -> Client {
+    let meteoritus = meteoritus.with_temp_path(temp_path()).keep_on_disk();
+
+    Client::tracked(rocket::build().attach(meteoritus.build()))
+        .await
+        .unwrap()
+}
+
+async fn create(client: &Client) {
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "1"))
+        .header(Header::new("User-Agent", "uploader/1.0"))
+        .header(Header::new("Via", "1.1 first"))
+        .header(Header::new("Via", "1.1 second"))
+        .header(Header::new("Authorization", "Bearer secret"))
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::Created);
+}
+
+#[rocket::async_test]
+async fn only_the_listed_headers_are_recorded() {
+    let recorded = Arc::new(Mutex::new(Vec::new()));
+    let created = Arc::clone(&recorded);
+
+    let client = client(
+        Meteoritus::new()
+            .with_captured_headers(&["User-Agent", "Via", "X-Missing"])
+            .on_created(move |ctx| {
+                created
+                    .lock()
+                    .unwrap()
+                    .push(ctx.file_info.request_headers().to_owned());
+            }),
+    )
+    .await;
+
+    create(&client).await;
+
+    let expected = HashMap::from([
+        ("User-Agent".to_string(), "uploader/1.0".to_string()),
+        ("Via".to_string(), "1.1 first, 1.1 second".to_string()),
+    ]);
+    assert_eq!(*recorded.lock().unwrap(), [Some(expected)]);
+}
+
+#[rocket::async_test]
+async fn nothing_is_recorded_by_default() {
+    let recorded = Arc::new(Mutex::new(Vec::new()));
+    let created = Arc::clone(&recorded);
+
+    let client = client(Meteoritus::new().on_created(move |ctx| {
+        created
+            .lock()
+            .unwrap()
+            .push(ctx.file_info.request_headers().to_owned());
+    }))
+    .await;
+
+    create(&client).await;
+
+    assert_eq!(*recorded.lock().unwrap(), [None]);
+}
+
+#[rocket::async_test]
+async fn created_events_carry_the_recorded_headers() {
+    let sink = MemorySink::default();
+    let client = client(
+        Meteoritus::new()
+            .with_captured_headers(&["User-Agent"])
+            .with_event_sink(sink.clone()),
+    )
+    .await;
+
+    create(&client).await;
+    sleep(Duration::from_millis(100)).await;
+
+    let events = sink.0.lock().unwrap();
+    assert_eq!(events[0].kind, EventKind::Created);
+    assert_eq!(
+        events[0].request_headers,
+        Some(HashMap::from([(
+            "User-Agent".to_string(),
+            "uploader/1.0".to_string()
+        )]))
+    );
+}
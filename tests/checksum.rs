@@ -0,0 +1,178 @@
+mod common;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use common::{
+    b64, client, client_at, create, create_with_metadata, head, patch,
+    temp_path, tus_header, upload_id,
+};
+use meteoritus::Meteoritus;
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::blocking::{Client, LocalResponse},
+};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+fn hex_digest(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn checksummed_patch<'c>(
+    client: &'c Client,
+    location: &str,
+    offset: u64,
+    body: &[u8],
+    checksummed: &[u8],
+) -> LocalResponse<'c> {
+    let checksum = STANDARD.encode(Sha256::digest(checksummed));
+
+    client
+        .patch(location.to_string())
+        .header(tus_header())
+        .header(Header::new("Upload-Offset", offset.to_string()))
+        .header(Header::new(
+            "Upload-Checksum",
+            format!("sha256 {}", checksum),
+        ))
+        .header(ContentType::new("application", "offset+octet-stream"))
+        .body(body)
+        .dispatch()
+}
+
+#[test]
+fn full_file_checksum_is_verified_on_completion() {
+    let client = client(Meteoritus::new().with_checksum_key("sha256"));
+
+    let metadata = format!("sha256 {}", b64(&hex_digest(b"abcd")));
+    let response = create_with_metadata(&client, 4, &metadata);
+    let location = response.headers().get_one("Location").unwrap();
+
+    assert_eq!(
+        patch(&client, location, 0, b"ab").status(),
+        Status::NoContent
+    );
+    assert_eq!(
+        patch(&client, location, 2, b"cd").status(),
+        Status::NoContent
+    );
+
+    let metadata = format!("sha256 {}", b64(&hex_digest(b"wxyz")));
+    let response = create_with_metadata(&client, 4, &metadata);
+    let location = response.headers().get_one("Location").unwrap();
+
+    let response = patch(&client, location, 0, b"abcd");
+    assert_eq!(response.status().code, 460);
+    assert_eq!(head(&client, location).status(), Status::NotFound);
+}
+
+#[test]
+fn undecodable_full_file_checksum_is_rejected() {
+    let client = client(Meteoritus::new().with_checksum_key("sha256"));
+
+    for value in [b64("not a digest"), "%%%".to_string()] {
+        let metadata = format!("sha256 {}", value);
+        let response = create_with_metadata(&client, 4, &metadata);
+
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+}
+
+#[test]
+fn checksum_mismatch_is_reported_as_a_termination() {
+    static TERMINATED: AtomicUsize = AtomicUsize::new(0);
+
+    let client = client(
+        Meteoritus::new()
+            .with_checksum_key("sha256")
+            .on_termination(|_| {
+                TERMINATED.fetch_add(1, Ordering::SeqCst);
+            }),
+    );
+
+    let metadata = format!("sha256 {}", b64(&hex_digest(b"wxyz")));
+    let response = create_with_metadata(&client, 4, &metadata);
+    let location = response.headers().get_one("Location").unwrap();
+
+    assert_eq!(patch(&client, location, 0, b"abcd").status().code, 460);
+    assert_eq!(TERMINATED.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn failed_chunk_leaves_the_data_and_the_digest_as_before() {
+    let path = temp_path();
+    let client = client_at(
+        Meteoritus::new()
+            .with_checksum_key("sha256")
+            .with_chunk_checksums(),
+        path,
+    );
+
+    let metadata = format!("sha256 {}", b64(&hex_digest(b"abcdef")));
+    let response = create_with_metadata(&client, 6, &metadata);
+    let location = response.headers().get_one("Location").unwrap();
+    let data = Path::new(path).join(upload_id(location)).join("file");
+
+    let response = checksummed_patch(&client, location, 0, b"abc", b"abc");
+    assert_eq!(response.status(), Status::NoContent);
+
+    let response = checksummed_patch(&client, location, 3, b"dxf", b"def");
+    assert_eq!(response.status().code, 460);
+    assert_eq!(fs::read(&data).unwrap(), b"abc\0\0\0");
+
+    let response = head(&client, location);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("3"));
+
+    let response = checksummed_patch(&client, location, 3, b"def", b"def");
+    assert_eq!(response.status(), Status::NoContent);
+}
+
+#[test]
+fn upload_resumed_after_a_restart_is_hashed_from_disk() {
+    let path = temp_path();
+    let metadata = format!("sha256 {}", b64(&hex_digest(b"abcdef")));
+
+    let location = {
+        let client =
+            client_at(Meteoritus::new().with_checksum_key("sha256"), path);
+
+        let response = create_with_metadata(&client, 6, &metadata);
+        let location = response.headers().get_one("Location").unwrap();
+        assert_eq!(
+            patch(&client, location, 0, b"abc").status(),
+            Status::NoContent
+        );
+
+        location.to_string()
+    };
+
+    let client = client_at(Meteoritus::new().with_checksum_key("sha256"), path);
+    assert_eq!(
+        patch(&client, &location, 3, b"def").status(),
+        Status::NoContent
+    );
+}
+
+#[test]
+fn failed_chunk_checksums_count_towards_aborting_the_upload() {
+    let client = client(
+        Meteoritus::new()
+            .with_chunk_checksums()
+            .with_max_failed_chunks(1),
+    );
+
+    let location = create(&client, 4);
+
+    let response = checksummed_patch(&client, &location, 0, b"ab", b"xx");
+    assert_eq!(response.status().code, 460);
+
+    let response = checksummed_patch(&client, &location, 0, b"ab", b"xx");
+    assert_eq!(response.status(), Status::Gone);
+    assert_eq!(head(&client, &location).status(), Status::Gone);
+}
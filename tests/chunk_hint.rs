@@ -0,0 +1,58 @@
+mod common;
+
+use common::{client, patch, tus_header};
+use meteoritus::Meteoritus;
+use rocket::{
+    data::ByteUnit,
+    http::{Header, Status},
+};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn options_suggest_the_preferred_chunk_size_when_configured() {
+    let configured = client(
+        Meteoritus::new().with_preferred_chunk_size(ByteUnit::Byte(1000)),
+    );
+    let response = configured.options("/meteoritus").dispatch();
+    assert_eq!(
+        response.headers().get_one("X-Tus-Preferred-Chunk-Size"),
+        Some("1000")
+    );
+
+    let unconfigured = client(Meteoritus::new());
+    let response = unconfigured.options("/meteoritus").dispatch();
+    assert!(!response.headers().contains("X-Tus-Preferred-Chunk-Size"));
+}
+
+#[test]
+fn client_hint_is_bounded_by_the_chunk_limits() {
+    let hinted = Arc::new(Mutex::new(Vec::new()));
+    let created = Arc::clone(&hinted);
+
+    let client = client(
+        Meteoritus::new()
+            .with_preferred_chunk_size(ByteUnit::Byte(1000))
+            .with_min_chunk_size(ByteUnit::Byte(4))
+            .with_max_size(ByteUnit::Byte(100))
+            .on_created(move |ctx| {
+                created.lock().unwrap().push(*ctx.file_info.chunk_size());
+            }),
+    );
+
+    for hint in ["50", "1", "5000"] {
+        let response = client
+            .post("/meteoritus")
+            .header(tus_header())
+            .header(Header::new("Upload-Length", "8"))
+            .header(Header::new("X-Tus-Preferred-Chunk-Size", hint))
+            .dispatch();
+        let location = response.headers().get_one("Location").unwrap();
+
+        assert_eq!(
+            patch(&client, location, 0, b"abcdefgh").status(),
+            Status::NoContent
+        );
+    }
+
+    assert_eq!(*hinted.lock().unwrap(), [Some(50), Some(4), Some(100)]);
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::{client, create, patch};
+use meteoritus::Meteoritus;
+use rocket::{data::ByteUnit, http::Status};
+
+#[test]
+fn undersized_chunks_are_rejected_unless_final() {
+    let client =
+        client(Meteoritus::new().with_min_chunk_size(ByteUnit::Byte(4)));
+
+    let location = create(&client, 6);
+
+    assert_eq!(
+        patch(&client, &location, 0, b"ab").status(),
+        Status::BadRequest
+    );
+    assert_eq!(
+        patch(&client, &location, 0, b"abcd").status(),
+        Status::NoContent
+    );
+    assert_eq!(
+        patch(&client, &location, 4, b"ef").status(),
+        Status::NoContent
+    );
+}
+
+#[test]
+fn chunks_of_any_size_are_accepted_by_default() {
+    let client = client(Meteoritus::new());
+
+    let location = create(&client, 6);
+
+    assert_eq!(
+        patch(&client, &location, 0, b"a").status(),
+        Status::NoContent
+    );
+}
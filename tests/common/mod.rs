@@ -0,0 +1,133 @@
+#![allow(dead_code)]
+
+use meteoritus::{EventKind, EventSink, Meteoritus, MeteoritusEvent};
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::blocking::{Client, LocalResponse},
+    Build,
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Returns a fresh directory path for a test server to store uploads in.
+pub fn temp_path() -> &'static str {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+
+    let path = std::env::temp_dir().join(format!(
+        "meteoritus-test-{}-{}-{}",
+        std::process::id(),
+        nanos,
+        COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+
+    Box::leak(path.to_string_lossy().into_owned().into_boxed_str())
+}
+
+/// Builds a tracked client for `meteoritus`, storing uploads in a fresh directory.
+pub fn client(meteoritus: Meteoritus<Build>) -> Client {
+    client_at(meteoritus, temp_path())
+}
+
+/// Builds a tracked client for `meteoritus`, storing uploads under `path`.
+pub fn client_at(meteoritus: Meteoritus<Build>, path: &'static str) -> Client {
+    let meteoritus = meteoritus.with_temp_path(path).keep_on_disk().build();
+
+    Client::tracked(rocket::build().attach(meteoritus)).unwrap()
+}
+
+/// Returns the id of the upload at `location`.
+pub fn upload_id(location: &str) -> &str {
+    location.rsplit('/').next().unwrap()
+}
+
+/// Base64 encodes `value` for use in `Upload-Metadata`.
+pub fn b64(value: &str) -> String {
+    use base64::Engine;
+
+    base64::engine::general_purpose::STANDARD.encode(value)
+}
+
+pub fn tus_header() -> Header<'static> {
+    Header::new("Tus-Resumable", "1.0.0")
+}
+
+/// Creates an upload of `length` bytes and returns its location.
+pub fn create(client: &Client, length: u64) -> String {
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", length.to_string()))
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Created);
+    response.headers().get_one("Location").unwrap().to_string()
+}
+
+/// Sends a creation request for `length` bytes with the given `Upload-Metadata`.
+pub fn create_with_metadata<'c>(
+    client: &'c Client,
+    length: u64,
+    metadata: &str,
+) -> LocalResponse<'c> {
+    client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", length.to_string()))
+        .header(Header::new("Upload-Metadata", metadata.to_string()))
+        .dispatch()
+}
+
+/// Sends `body` to the upload at `location`, starting at `offset`.
+pub fn patch<'c>(
+    client: &'c Client,
+    location: &str,
+    offset: u64,
+    body: &[u8],
+) -> LocalResponse<'c> {
+    client
+        .patch(location.to_string())
+        .header(tus_header())
+        .header(Header::new("Upload-Offset", offset.to_string()))
+        .header(ContentType::new("application", "offset+octet-stream"))
+        .body(body)
+        .dispatch()
+}
+
+pub fn head<'c>(client: &'c Client, location: &str) -> LocalResponse<'c> {
+    client
+        .head(location.to_string())
+        .header(tus_header())
+        .dispatch()
+}
+
+/// Event sink recording every published event.
+#[derive(Clone, Default)]
+pub struct MemorySink(pub Arc<Mutex<Vec<MeteoritusEvent>>>);
+
+impl MemorySink {
+    pub fn kinds(&self) -> Vec<EventKind> {
+        self.0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|event| event.kind)
+            .collect()
+    }
+}
+
+#[rocket::async_trait]
+impl EventSink for MemorySink {
+    async fn publish(&self, event: MeteoritusEvent) {
+        self.0.lock().unwrap().push(event);
+    }
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::{client, create, tus_header};
+use meteoritus::Meteoritus;
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::blocking::Client,
+};
+
+fn ranged_patch(
+    client: &Client,
+    location: &str,
+    offset: u64,
+    body: &'static [u8],
+    range: &str,
+) -> Status {
+    client
+        .patch(location.to_string())
+        .header(tus_header())
+        .header(Header::new("Upload-Offset", offset.to_string()))
+        .header(Header::new("Content-Range", range.to_string()))
+        .header(ContentType::new("application", "offset+octet-stream"))
+        .body(body)
+        .dispatch()
+        .status()
+}
+
+#[test]
+fn content_range_is_checked_against_the_chunk() {
+    let client = client(Meteoritus::new().validate_content_range());
+    let location = create(&client, 8);
+
+    assert_eq!(
+        ranged_patch(&client, &location, 0, b"abcd", "bytes 0-3/8"),
+        Status::NoContent
+    );
+    assert_eq!(
+        ranged_patch(&client, &location, 4, b"ef", "bytes 4-6/8"),
+        Status::BadRequest
+    );
+    assert_eq!(
+        ranged_patch(&client, &location, 4, b"ef", "bytes 4-5/9"),
+        Status::BadRequest
+    );
+    assert_eq!(
+        ranged_patch(&client, &location, 4, b"ef", "bytes 4-5/*"),
+        Status::NoContent
+    );
+}
+
+#[test]
+fn content_range_is_ignored_unless_enabled() {
+    let client = client(Meteoritus::new());
+    let location = create(&client, 8);
+
+    assert_eq!(
+        ranged_patch(&client, &location, 0, b"abcd", "garbage"),
+        Status::NoContent
+    );
+}
+
+#[test]
+fn preflight_allows_the_content_range() {
+    let client = client(Meteoritus::new().with_cors_origin("https://a.b"));
+
+    let response = client
+        .options("/meteoritus")
+        .header(Header::new("Access-Control-Request-Method", "PATCH"))
+        .header(Header::new("Origin", "https://a.b"))
+        .dispatch();
+
+    let allowed = response
+        .headers()
+        .get_one("Access-Control-Allow-Headers")
+        .unwrap();
+    assert!(allowed.contains("Content-Range"));
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use common::{client, create, tus_header};
+use meteoritus::Meteoritus;
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+
+fn patch_as(client: &Client, content_type: &str) -> Status {
+    let location = create(client, 3);
+
+    client
+        .patch(location)
+        .header(tus_header())
+        .header(Header::new("Upload-Offset", "0"))
+        .header(Header::new("Content-Type", content_type.to_string()))
+        .body(b"abc")
+        .dispatch()
+        .status()
+}
+
+#[test]
+fn content_type_parameters_are_ignored() {
+    let client = client(Meteoritus::new());
+
+    assert_eq!(
+        patch_as(&client, "application/offset+octet-stream"),
+        Status::NoContent
+    );
+    assert_eq!(
+        patch_as(&client, "application/offset+octet-stream; charset=utf-8"),
+        Status::NoContent
+    );
+    assert_eq!(
+        patch_as(&client, "Application/Offset+Octet-Stream;foo=bar"),
+        Status::NoContent
+    );
+}
+
+#[test]
+fn other_media_types_are_unsupported() {
+    let client = client(Meteoritus::new());
+
+    assert_eq!(
+        patch_as(&client, "application/octet-stream"),
+        Status::UnsupportedMediaType
+    );
+    assert_eq!(
+        patch_as(&client, "text/offset+octet-stream; charset=utf-8"),
+        Status::UnsupportedMediaType
+    );
+}
+
+#[test]
+fn missing_content_type_is_rejected() {
+    let client = client(Meteoritus::new());
+    let location = create(&client, 3);
+
+    let response = client
+        .patch(location)
+        .header(tus_header())
+        .header(Header::new("Upload-Offset", "0"))
+        .body(b"abc")
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+}
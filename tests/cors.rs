@@ -0,0 +1,74 @@
+mod common;
+
+use common::{client, create, head, patch};
+use meteoritus::Meteoritus;
+use rocket::http::{Header, Status};
+
+#[test]
+fn plain_options_returns_the_capabilities() {
+    let client = client(Meteoritus::new());
+
+    let response = client.options("/meteoritus").dispatch();
+
+    assert_eq!(response.status(), Status::NoContent);
+    assert!(response.headers().contains("Tus-Version"));
+    assert!(response.headers().contains("Tus-Extension"));
+    assert!(!response.headers().contains("Access-Control-Allow-Methods"));
+}
+
+#[test]
+fn preflight_options_returns_the_cors_headers_only() {
+    let client = client(Meteoritus::new().with_cors_origin("https://a.b"));
+
+    let location = create(&client, 3);
+
+    for uri in ["/meteoritus".to_string(), location] {
+        let response = client
+            .options(uri)
+            .header(Header::new("Access-Control-Request-Method", "PATCH"))
+            .header(Header::new("Origin", "https://a.b"))
+            .dispatch();
+
+        assert_eq!(response.status(), Status::NoContent);
+        assert!(!response.headers().contains("Tus-Version"));
+        assert_eq!(
+            response.headers().get_one("Access-Control-Allow-Origin"),
+            Some("https://a.b")
+        );
+        assert!(response.headers().contains("Access-Control-Allow-Methods"));
+    }
+}
+
+#[test]
+fn every_response_exposes_the_tus_headers() {
+    let client = client(Meteoritus::new().with_cors_origin("https://a.b"));
+
+    let location = create(&client, 3);
+
+    for response in [
+        head(&client, &location),
+        patch(&client, &location, 0, b"ab"),
+    ] {
+        assert_eq!(
+            response.headers().get_one("Access-Control-Allow-Origin"),
+            Some("https://a.b")
+        );
+
+        let exposed = response
+            .headers()
+            .get_one("Access-Control-Expose-Headers")
+            .unwrap();
+        assert!(exposed.contains("Upload-Offset"));
+        assert!(exposed.contains("Location"));
+    }
+
+    let response = client
+        .post("/meteoritus")
+        .header(Header::new("Upload-Length", "3"))
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(
+        response.headers().get_one("Access-Control-Allow-Origin"),
+        Some("https://a.b")
+    );
+}
@@ -0,0 +1,115 @@
+mod common;
+
+use common::{
+    b64, client, create, create_with_metadata, patch, temp_path, tus_header,
+    upload_id,
+};
+use meteoritus::Meteoritus;
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+use std::{fs, path::Path};
+
+#[test]
+fn colliding_destinations_are_asked_again() {
+    let destination = temp_path();
+    fs::create_dir_all(destination).unwrap();
+    fs::write(Path::new(destination).join("f0"), "x").unwrap();
+    fs::write(Path::new(destination).join("f1"), "y").unwrap();
+
+    let client = client(Meteoritus::new().with_destination(|_, attempt| {
+        Path::new(destination).join(format!("f{}", attempt))
+    }));
+
+    let location = create(&client, 4);
+    assert_eq!(
+        patch(&client, &location, 0, b"abcd").status(),
+        Status::NoContent
+    );
+
+    let read = |name: &str| {
+        fs::read_to_string(Path::new(destination).join(name)).unwrap()
+    };
+    assert_eq!(read("f0"), "x");
+    assert_eq!(read("f1"), "y");
+    assert_eq!(read("f2"), "abcd");
+}
+
+#[test]
+fn upload_fails_once_the_attempts_are_exhausted() {
+    let destination = temp_path();
+    fs::create_dir_all(destination).unwrap();
+    fs::write(Path::new(destination).join("taken"), "x").unwrap();
+
+    let client = client(
+        Meteoritus::new()
+            .with_destination(|_, _| Path::new(destination).join("taken"))
+            .with_destination_attempts(2),
+    );
+
+    let location = create(&client, 4);
+    let response = patch(&client, &location, 0, b"abcd");
+    assert_eq!(response.status(), Status::InternalServerError);
+    assert_eq!(
+        fs::read_to_string(Path::new(destination).join("taken")).unwrap(),
+        "x"
+    );
+}
+
+/// Completes a single byte upload with the given `tenant` metadata, returning its id.
+fn complete_for(client: &Client, tenant: &str) -> String {
+    let response =
+        create_with_metadata(client, 1, &format!("tenant {}", b64(tenant)));
+    assert_eq!(response.status(), Status::Created);
+
+    let location = response.headers().get_one("Location").unwrap().to_string();
+    assert_eq!(
+        patch(client, &location, 0, b"x").status(),
+        Status::NoContent
+    );
+
+    upload_id(&location).to_string()
+}
+
+#[test]
+fn completed_uploads_are_routed_by_metadata() {
+    let (acme, other) = (temp_path(), temp_path());
+    let routes = Box::leak(Box::new([("acme", acme)]));
+
+    let client = client(Meteoritus::new().with_destination_routes(
+        "tenant",
+        routes,
+        Some(other),
+    ));
+
+    let routed = complete_for(&client, "acme");
+    let unknown = complete_for(&client, "globex");
+
+    assert!(Path::new(acme).join(&routed).exists());
+    assert!(Path::new(other).join(&unknown).exists());
+    assert!(!Path::new(acme).join(&unknown).exists());
+}
+
+#[test]
+fn unknown_values_are_rejected_without_a_fallback() {
+    let acme = temp_path();
+    let routes = Box::leak(Box::new([("acme", acme)]));
+
+    let client = client(
+        Meteoritus::new().with_destination_routes("tenant", routes, None),
+    );
+
+    let response =
+        create_with_metadata(&client, 1, &format!("tenant {}", b64("globex")));
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "1"))
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+
+    complete_for(&client, "acme");
+}
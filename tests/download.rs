@@ -0,0 +1,224 @@
+#![cfg(feature = "download")]
+
+mod common;
+
+use common::{
+    b64, client, create, create_with_metadata, head, patch, tus_header,
+};
+use meteoritus::Meteoritus;
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::blocking::{Client, LocalResponse},
+};
+
+fn completed(client: &Client, body: &[u8]) -> String {
+    let location = create(client, body.len() as u64);
+    assert_eq!(
+        patch(client, &location, 0, body).status(),
+        Status::NoContent
+    );
+
+    location
+}
+
+fn download<'c>(
+    client: &'c Client,
+    location: &str,
+    range: Option<&str>,
+) -> LocalResponse<'c> {
+    let mut request = client.get(location.to_string()).header(tus_header());
+
+    if let Some(range) = range {
+        request = request.header(Header::new("Range", range.to_string()));
+    }
+
+    request.dispatch()
+}
+
+#[test]
+fn completed_upload_is_served_as_binary_by_default() {
+    let client = client(Meteoritus::new());
+    let location = completed(&client, b"abcdefgh");
+
+    let response = download(&client, &location, None);
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::Binary));
+    assert_eq!(response.into_bytes().unwrap(), b"abcdefgh");
+}
+
+#[test]
+fn declared_type_is_served() {
+    let client = client(Meteoritus::new());
+
+    let metadata = format!("filetype {}", b64("text/plain"));
+    let response = create_with_metadata(&client, 2, &metadata);
+    let location = response.headers().get_one("Location").unwrap();
+    assert_eq!(
+        patch(&client, location, 0, b"hi").status(),
+        Status::NoContent
+    );
+
+    let response = download(&client, location, None);
+    assert_eq!(response.content_type(), Some(ContentType::Plain));
+}
+
+/// Completes an upload of `body` declaring the given `filetype`.
+fn completed_as(client: &Client, filetype: &str, body: &[u8]) -> String {
+    let metadata = format!("filetype {}", b64(filetype));
+    let response = create_with_metadata(client, body.len() as u64, &metadata);
+    let location = response.headers().get_one("Location").unwrap().to_string();
+
+    assert_eq!(
+        patch(client, &location, 0, body).status(),
+        Status::NoContent
+    );
+    location
+}
+
+#[test]
+fn unsafe_declared_types_are_served_as_binary_attachments() {
+    let client = client(Meteoritus::new());
+
+    for filetype in ["text/html", "image/svg+xml", "TEXT/HTML; charset=utf-8"] {
+        let location = completed_as(&client, filetype, b"<script>x</script>");
+
+        for range in [None, Some("bytes=0-3")] {
+            let response = download(&client, &location, range);
+            let headers = response.headers();
+
+            assert_eq!(response.content_type(), Some(ContentType::Binary));
+            assert_eq!(
+                headers.get_one("Content-Disposition"),
+                Some("attachment")
+            );
+            assert_eq!(
+                headers.get_one("X-Content-Type-Options"),
+                Some("nosniff")
+            );
+        }
+    }
+}
+
+#[test]
+fn safe_declared_types_are_served_inline() {
+    let client = client(Meteoritus::new());
+    let location = completed_as(&client, "image/png", b"png");
+
+    for range in [None, Some("bytes=1-")] {
+        let response = download(&client, &location, range);
+        let headers = response.headers();
+
+        assert_eq!(response.content_type(), Some(ContentType::PNG));
+        assert!(!headers.contains("Content-Disposition"));
+        assert_eq!(headers.get_one("X-Content-Type-Options"), Some("nosniff"));
+    }
+}
+
+#[test]
+fn ranges_are_read_from_their_start() {
+    let client = client(Meteoritus::new());
+    let location = completed(&client, b"abcdefgh");
+
+    let response = download(&client, &location, Some("bytes=2-4"));
+    assert_eq!(response.status(), Status::PartialContent);
+    assert_eq!(
+        response.headers().get_one("Content-Range"),
+        Some("bytes 2-4/8")
+    );
+    assert_eq!(response.into_bytes().unwrap(), b"cde");
+
+    let response = download(&client, &location, Some("bytes=-3"));
+    assert_eq!(response.into_bytes().unwrap(), b"fgh");
+
+    let response = download(&client, &location, Some("bytes=8-"));
+    assert_eq!(response.status(), Status::RangeNotSatisfiable);
+}
+
+#[test]
+fn incomplete_upload_is_not_served() {
+    let client = client(Meteoritus::new());
+    let location = create(&client, 8);
+    assert_eq!(
+        patch(&client, &location, 0, b"abcd").status(),
+        Status::NoContent
+    );
+
+    let response = download(&client, &location, None);
+    assert_eq!(response.status(), Status::Conflict);
+}
+
+#[test]
+fn preflight_allows_range_downloads() {
+    let client = client(Meteoritus::new().with_cors_origin("https://a.b"));
+
+    let response = client
+        .options("/meteoritus")
+        .header(Header::new("Access-Control-Request-Method", "GET"))
+        .header(Header::new("Origin", "https://a.b"))
+        .dispatch();
+
+    let headers = response.headers();
+    let methods = headers.get_one("Access-Control-Allow-Methods").unwrap();
+    assert!(methods.contains("GET"));
+
+    let allowed = headers.get_one("Access-Control-Allow-Headers").unwrap();
+    assert!(allowed.split(", ").any(|header| header == "Range"));
+}
+
+#[cfg(feature = "ring")]
+#[test]
+fn ring_buffer_fails_to_ignite() {
+    let meteoritus = Meteoritus::new()
+        .with_ring_buffer(rocket::data::ByteUnit::Byte(4))
+        .with_temp_path(common::temp_path())
+        .build();
+
+    let error =
+        Client::tracked(rocket::build().attach(meteoritus)).unwrap_err();
+    assert!(matches!(
+        error.kind(),
+        rocket::error::ErrorKind::FailedFairings(_)
+    ));
+}
+
+#[test]
+fn disabled_downloads_are_not_served_nor_advertised() {
+    let client = client(
+        Meteoritus::new()
+            .without_downloads()
+            .with_cors_origin("https://a.b"),
+    );
+    let location = completed(&client, b"hello");
+
+    let response = client.get(location.clone()).dispatch();
+    assert_eq!(response.status(), Status::MethodNotAllowed);
+    assert_eq!(
+        response.headers().get_one("Allow"),
+        Some("OPTIONS, HEAD, PATCH, DELETE")
+    );
+
+    let response = head(&client, &location);
+    assert!(!response.headers().contains("Accept-Ranges"));
+
+    let response = client
+        .options("/meteoritus")
+        .header(Header::new("Access-Control-Request-Method", "GET"))
+        .header(Header::new("Origin", "https://a.b"))
+        .dispatch();
+    let methods = response.headers().get_one("Access-Control-Allow-Methods");
+    assert!(!methods.unwrap().contains("GET"));
+}
+
+#[cfg(feature = "ring")]
+#[test]
+fn ring_buffer_ignites_without_downloads() {
+    let client = client(
+        Meteoritus::new()
+            .with_ring_buffer(rocket::data::ByteUnit::Byte(4))
+            .without_downloads(),
+    );
+    let location = completed(&client, b"hello");
+
+    let response = client.get(location).dispatch();
+    assert_eq!(response.status(), Status::MethodNotAllowed);
+}
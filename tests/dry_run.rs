@@ -0,0 +1,77 @@
+mod common;
+
+use common::{client, client_at, temp_path, tus_header};
+use meteoritus::Meteoritus;
+use rocket::{
+    data::ByteUnit,
+    http::{Header, Status},
+    local::blocking::{Client, LocalResponse},
+};
+use std::{
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+fn dry_run(client: &Client, length: u64) -> LocalResponse<'_> {
+    client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", length.to_string()))
+        .header(Header::new("Upload-Dry-Run", "1"))
+        .dispatch()
+}
+
+/// Counts the entries stored under `path`, which may not exist yet.
+fn stored(path: &str) -> usize {
+    match std::fs::read_dir(Path::new(path)) {
+        Ok(entries) => entries.count(),
+        Err(_) => 0,
+    }
+}
+
+#[test]
+fn dry_run_validates_without_reserving_storage() {
+    let path = temp_path();
+    let called = Arc::new(AtomicBool::new(false));
+    let on_creation = Arc::clone(&called);
+
+    let client = client_at(
+        Meteoritus::new().allow_dry_run().on_creation(move |_| {
+            on_creation.store(true, Ordering::SeqCst);
+            Ok(())
+        }),
+        path,
+    );
+
+    let response = dry_run(&client, 10);
+    assert_eq!(response.status(), Status::NoContent);
+    assert!(!response.headers().contains("Location"));
+    assert_eq!(stored(path), 0);
+    assert!(!called.load(Ordering::SeqCst));
+}
+
+#[test]
+fn dry_run_reports_the_usual_errors() {
+    let client = client(
+        Meteoritus::new()
+            .allow_dry_run()
+            .with_max_size(ByteUnit::Byte(5)),
+    );
+
+    assert_eq!(dry_run(&client, 5).status(), Status::NoContent);
+    assert_eq!(dry_run(&client, 6).status(), Status::PayloadTooLarge);
+}
+
+#[test]
+fn dry_run_header_is_ignored_unless_allowed() {
+    let path = temp_path();
+    let client = client_at(Meteoritus::new(), path);
+
+    let response = dry_run(&client, 10);
+    assert_eq!(response.status(), Status::Created);
+    assert!(response.headers().contains("Location"));
+    assert_eq!(stored(path), 1);
+}
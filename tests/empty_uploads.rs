@@ -0,0 +1,26 @@
+mod common;
+
+use common::{client, create, tus_header};
+use meteoritus::Meteoritus;
+use rocket::http::{Header, Status};
+
+#[test]
+fn empty_uploads_are_accepted_by_default() {
+    let client = client(Meteoritus::new());
+
+    create(&client, 0);
+}
+
+#[test]
+fn empty_uploads_are_rejected_when_configured() {
+    let client = client(Meteoritus::new().reject_empty_uploads());
+
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "0"))
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    create(&client, 1);
+}
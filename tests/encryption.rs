@@ -0,0 +1,174 @@
+mod common;
+
+use common::{b64, client_at, create, patch, temp_path, tus_header, upload_id};
+use meteoritus::{InMemoryKeyProvider, KeyProvider, Meteoritus};
+use rocket::{
+    http::{Header, Status},
+    local::blocking::{Client, LocalResponse},
+};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+const KEY: [u8; 32] = [7; 32];
+
+fn encrypting(path: &'static str) -> Client {
+    client_at(
+        Meteoritus::new()
+            .with_checksum_key("sha256")
+            .with_key_provider(InMemoryKeyProvider::new().with_key("k1", KEY)),
+        path,
+    )
+}
+
+fn create_encrypted<'c>(
+    client: &'c Client,
+    key_id: &str,
+    metadata: Option<String>,
+) -> LocalResponse<'c> {
+    let mut request = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "8"))
+        .header(Header::new("Upload-Key-Id", key_id.to_string()));
+
+    if let Some(metadata) = metadata {
+        request = request.header(Header::new("Upload-Metadata", metadata));
+    }
+
+    request.dispatch()
+}
+
+fn checksum_metadata(content: &[u8]) -> String {
+    format!("sha256 {}", b64(&format!("{:x}", Sha256::digest(content))))
+}
+
+#[test]
+fn unknown_key_ids_are_rejected() {
+    let client = encrypting(temp_path());
+
+    let response = create_encrypted(&client, "nope", None);
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[test]
+fn completed_upload_stays_encrypted_at_rest() {
+    let path = temp_path();
+    let client = encrypting(path);
+
+    let response = create_encrypted(&client, "k1", None);
+    assert_eq!(response.status(), Status::Created);
+    let location = response.headers().get_one("Location").unwrap();
+    let upload_dir = Path::new(path).join(upload_id(location));
+
+    assert_eq!(
+        patch(&client, location, 0, b"abcd").status(),
+        Status::NoContent
+    );
+    assert_eq!(
+        patch(&client, location, 4, b"efgh").status(),
+        Status::NoContent
+    );
+
+    let stored = fs::read(upload_dir.join("file")).unwrap();
+    assert_eq!(stored.len(), 8);
+    assert_ne!(stored, b"abcdefgh");
+
+    let manifest = fs::read_to_string(upload_dir.join("info.json")).unwrap();
+    assert!(manifest.contains("\"k1\""));
+
+    let location = create(&client, 4);
+    assert_eq!(
+        patch(&client, &location, 0, b"wxyz").status(),
+        Status::NoContent
+    );
+}
+
+#[test]
+fn checksum_of_an_encrypted_upload_covers_the_plaintext() {
+    let path = temp_path();
+    let metadata = checksum_metadata(b"abcdefgh");
+
+    let location = {
+        let client = encrypting(path);
+
+        let response = create_encrypted(&client, "k1", Some(metadata.clone()));
+        let location = response.headers().get_one("Location").unwrap();
+        assert_eq!(
+            patch(&client, location, 0, b"abcd").status(),
+            Status::NoContent
+        );
+
+        location.to_string()
+    };
+
+    /* After a restart the plaintext is hashed from the stored ciphertext */
+    let client = encrypting(path);
+    assert_eq!(
+        patch(&client, &location, 4, b"efgh").status(),
+        Status::NoContent
+    );
+
+    let response =
+        create_encrypted(&client, "k1", Some(checksum_metadata(b"wrong")));
+    let location = response.headers().get_one("Location").unwrap();
+    assert_eq!(patch(&client, location, 0, b"abcdefgh").status().code, 460);
+}
+
+#[test]
+fn provider_answering_another_key_fails_the_upload() {
+    struct Rotating(Arc<Mutex<[u8; 32]>>);
+
+    impl KeyProvider for Rotating {
+        fn key(&self, _key_id: &str) -> Option<[u8; 32]> {
+            Some(*self.0.lock().unwrap())
+        }
+    }
+
+    let key = Arc::new(Mutex::new([1; 32]));
+    let client = client_at(
+        Meteoritus::new().with_key_provider(Rotating(Arc::clone(&key))),
+        temp_path(),
+    );
+
+    let response = create_encrypted(&client, "k1", None);
+    let location = response.headers().get_one("Location").unwrap();
+    assert_eq!(
+        patch(&client, location, 0, b"abcd").status(),
+        Status::NoContent
+    );
+
+    *key.lock().unwrap() = [2; 32];
+    assert_eq!(
+        patch(&client, location, 4, b"efgh").status(),
+        Status::InternalServerError
+    );
+}
+
+#[cfg(feature = "download")]
+#[test]
+fn encrypted_upload_is_decrypted_on_download() {
+    let client = encrypting(temp_path());
+
+    let response = create_encrypted(&client, "k1", None);
+    let location = response.headers().get_one("Location").unwrap();
+    assert_eq!(
+        patch(&client, location, 0, b"abcdefgh").status(),
+        Status::NoContent
+    );
+
+    let response = client.get(location).header(tus_header()).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_bytes().unwrap(), b"abcdefgh");
+
+    let response = client
+        .get(location)
+        .header(tus_header())
+        .header(Header::new("Range", "bytes=3-5"))
+        .dispatch();
+    assert_eq!(response.status(), Status::PartialContent);
+    assert_eq!(response.into_bytes().unwrap(), b"def");
+}
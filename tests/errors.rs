@@ -0,0 +1,29 @@
+mod common;
+
+use common::{client, create, patch};
+use meteoritus::{Meteoritus, MeteoritusError};
+use rocket::http::Status;
+
+#[test]
+fn offset_mismatch_defaults_to_conflict() {
+    let client = client(Meteoritus::new());
+
+    let location = create(&client, 8);
+    assert_eq!(
+        patch(&client, &location, 3, b"abc").status(),
+        Status::Conflict
+    );
+}
+
+#[test]
+fn error_status_can_be_overridden() {
+    let client = client(Meteoritus::new().with_error_status(
+        MeteoritusError::OffsetMismatch,
+        Status::UnprocessableEntity,
+    ));
+
+    let location = create(&client, 8);
+    let response = patch(&client, &location, 3, b"abc");
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
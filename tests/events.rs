@@ -0,0 +1,175 @@
+mod common;
+
+use common::{temp_path, tus_header, MemorySink};
+use meteoritus::{EventKind, EventSink, Meteoritus, MeteoritusEvent};
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::asynchronous::Client,
+    tokio::time::sleep,
+};
+use std::time::Duration;
+
+async fn upload_and_terminate(client: &Client) {
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "2"))
+        .header(Header::new("Upload-Metadata", "name aGk="))
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::Created);
+    let location = response.headers().get_one("Location").unwrap().to_string();
+
+    let response = client
+        .patch(location.clone())
+        .header(tus_header())
+        .header(Header::new("Upload-Offset", "0"))
+        .header(ContentType::new("application", "offset+octet-stream"))
+        .body("ab")
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NoContent);
+
+    let response = client
+        .delete(location)
+        .header(tus_header())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NoContent);
+}
+
+#[rocket::async_test]
+async fn upload_lifecycle_events_are_published() {
+    let sink = MemorySink::default();
+    let meteoritus = Meteoritus::new()
+        .with_temp_path(temp_path())
+        .keep_on_disk()
+        .with_event_sink(sink.clone())
+        .build();
+    let client = Client::tracked(rocket::build().attach(meteoritus))
+        .await
+        .unwrap();
+
+    upload_and_terminate(&client).await;
+    sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(
+        sink.kinds(),
+        vec![
+            EventKind::Created,
+            EventKind::Completed,
+            EventKind::Terminated
+        ]
+    );
+
+    let events = sink.0.lock().unwrap();
+    assert!(events.iter().all(|event| event.id == events[0].id));
+    assert!(events.iter().all(|event| event.length == 2));
+    assert_eq!(
+        events[0]
+            .metadata
+            .as_ref()
+            .map(|m| m.get_raw("name").unwrap()),
+        Some(b"hi".to_vec())
+    );
+    assert!(events[0].created_at.is_some());
+    assert!(events[1].completed_at.is_some());
+}
+
+/// Event sink panicking on creation events, recording the others.
+#[derive(Clone, Default)]
+struct PanickingSink(MemorySink);
+
+#[rocket::async_trait]
+impl EventSink for PanickingSink {
+    async fn publish(&self, event: MeteoritusEvent) {
+        if event.kind == EventKind::Created {
+            panic!("sink unavailable");
+        }
+
+        self.0.publish(event).await;
+    }
+}
+
+#[rocket::async_test]
+async fn panicking_sink_only_loses_its_event() {
+    let sink = PanickingSink::default();
+    let meteoritus = Meteoritus::new()
+        .with_temp_path(temp_path())
+        .keep_on_disk()
+        .with_event_sink(sink.clone())
+        .build();
+    let client = Client::tracked(rocket::build().attach(meteoritus))
+        .await
+        .unwrap();
+
+    upload_and_terminate(&client).await;
+    sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(
+        sink.0.kinds(),
+        vec![EventKind::Completed, EventKind::Terminated]
+    );
+}
+
+#[cfg(feature = "nats")]
+#[rocket::async_test]
+async fn nats_sink_publishes_json_events() {
+    use meteoritus::NatsSink;
+    use rocket::tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::TcpListener,
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+
+    let server = rocket::tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        stream.write_all(b"INFO {}\r\n").await.unwrap();
+
+        let mut reader = BufReader::new(stream);
+        let mut published = Vec::new();
+        let mut line = String::new();
+
+        while published.len() < 3 {
+            line.clear();
+            reader.read_line(&mut line).await.unwrap();
+
+            let Some(header) = line.strip_prefix("PUB ") else {
+                continue;
+            };
+            let (subject, length) = header.trim_end().split_once(' ').unwrap();
+            assert_eq!(subject, "uploads");
+
+            let mut payload = vec![0; length.parse::<usize>().unwrap() + 2];
+            reader.read_exact(&mut payload).await.unwrap();
+            payload.truncate(payload.len() - 2);
+
+            let event: serde_json::Value =
+                serde_json::from_slice(&payload).unwrap();
+            published.push(event["kind"].as_str().unwrap().to_string());
+        }
+
+        published
+    });
+
+    let meteoritus = Meteoritus::new()
+        .with_temp_path(temp_path())
+        .keep_on_disk()
+        .with_event_sink(NatsSink::new(address, "uploads"))
+        .build();
+    let client = Client::tracked(rocket::build().attach(meteoritus))
+        .await
+        .unwrap();
+
+    upload_and_terminate(&client).await;
+
+    let published =
+        rocket::tokio::time::timeout(Duration::from_secs(5), server)
+            .await
+            .unwrap()
+            .unwrap();
+
+    assert_eq!(published, vec!["created", "completed", "terminated"]);
+}
@@ -0,0 +1,100 @@
+mod common;
+
+use common::{client_at, create, patch, temp_path, tus_header, upload_id};
+use meteoritus::Meteoritus;
+use rocket::{http::Status, local::blocking::Client};
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+    thread::sleep,
+    time::Duration,
+};
+
+/// Completes a single byte upload and returns its id.
+fn complete(client: &Client) -> String {
+    let location = create(client, 1);
+    assert_eq!(
+        patch(client, &location, 0, b"x").status(),
+        Status::NoContent
+    );
+
+    /* Completion times have to differ */
+    sleep(Duration::from_millis(5));
+
+    upload_id(&location).to_string()
+}
+
+fn evicting(
+    max_completed: usize,
+    evicted: &Arc<Mutex<Vec<String>>>,
+) -> Meteoritus<rocket::Build> {
+    let evicted = evicted.to_owned();
+
+    Meteoritus::new()
+        .with_max_completed(max_completed)
+        .on_eviction(move |ctx| {
+            evicted.lock().unwrap().push(ctx.file_info.id().to_string())
+        })
+}
+
+#[test]
+fn oldest_completed_uploads_are_evicted() {
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+    let client = client_at(evicting(2, &evicted), temp_path());
+
+    let ids: Vec<String> = (0..4).map(|_| complete(&client)).collect();
+
+    assert_eq!(*evicted.lock().unwrap(), ids[..2]);
+}
+
+#[test]
+fn uploads_completed_before_a_restart_are_evicted() {
+    let path = temp_path();
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+
+    let first = {
+        let client = client_at(evicting(2, &evicted), path);
+        [complete(&client), complete(&client)]
+    };
+
+    let client = client_at(evicting(2, &evicted), path);
+    complete(&client);
+
+    assert_eq!(*evicted.lock().unwrap(), first[..1]);
+}
+
+#[test]
+fn terminated_uploads_do_not_count() {
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+    let client = client_at(evicting(2, &evicted), temp_path());
+
+    let terminated = complete(&client);
+    complete(&client);
+
+    let response = client
+        .delete(format!("/meteoritus/{}", terminated))
+        .header(tus_header())
+        .dispatch();
+    assert_eq!(response.status(), Status::NoContent);
+
+    complete(&client);
+
+    assert!(evicted.lock().unwrap().is_empty());
+}
+
+#[test]
+fn moved_files_are_evicted() {
+    let completed = temp_path();
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+    let client = client_at(
+        evicting(1, &evicted).with_completed_path(completed),
+        temp_path(),
+    );
+
+    let oldest = complete(&client);
+    let newest = complete(&client);
+
+    assert!(!Path::new(completed).join(&oldest).exists());
+    assert!(Path::new(completed).join(newest).exists());
+    assert_eq!(*evicted.lock().unwrap(), [oldest]);
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use common::{client, client_at, create, head, patch, temp_path};
+use meteoritus::Meteoritus;
+use rocket::{http::Status, local::blocking::Client};
+use std::time::{Duration, SystemTime};
+
+fn upload_expires(client: &Client, location: &str) -> SystemTime {
+    let response = head(client, location);
+    let expires = response.headers().get_one("Upload-Expires").unwrap();
+
+    httpdate::parse_http_date(expires).unwrap()
+}
+
+#[test]
+fn idle_expiration_is_renewed_by_patch() {
+    let client =
+        client(Meteoritus::new().with_idle_expiration(Duration::from_secs(10)));
+
+    let location = create(&client, 10);
+    let created = upload_expires(&client, &location);
+
+    std::thread::sleep(Duration::from_millis(1100));
+
+    assert_eq!(
+        patch(&client, &location, 0, b"hello").status(),
+        Status::NoContent
+    );
+    assert!(upload_expires(&client, &location) > created);
+}
+
+#[test]
+fn options_report_the_configured_expiration_windows() {
+    let max_age = |meteoritus: Meteoritus<rocket::Build>| {
+        client(meteoritus)
+            .options("/meteoritus")
+            .dispatch()
+            .headers()
+            .get_one("X-Tus-Max-Age")
+            .map(str::to_string)
+    };
+
+    assert_eq!(max_age(Meteoritus::new()), None);
+    assert_eq!(
+        max_age(Meteoritus::new().with_expiration(Duration::from_secs(86400)))
+            .as_deref(),
+        Some("absolute=86400")
+    );
+    assert_eq!(
+        max_age(
+            Meteoritus::new()
+                .with_idle_expiration(Duration::from_secs(3600))
+                .with_expiration(Duration::from_secs(86400))
+        )
+        .as_deref(),
+        Some("idle=3600, absolute=86400")
+    );
+}
+
+#[test]
+fn idle_expiration_survives_a_restart() {
+    let path = temp_path();
+    let idle = |window| {
+        Meteoritus::new().with_idle_expiration(Duration::from_secs(window))
+    };
+
+    let (location, expires) = {
+        let client = client_at(idle(10), path);
+        let location = create(&client, 10);
+
+        assert_eq!(
+            patch(&client, &location, 0, b"hello").status(),
+            Status::NoContent
+        );
+        let expires = upload_expires(&client, &location);
+
+        (location, expires)
+    };
+
+    std::thread::sleep(Duration::from_millis(1100));
+
+    /* Counted from the persisted last activity, not from the restart */
+    let client = client_at(idle(10), path);
+    assert_eq!(upload_expires(&client, &location), expires);
+
+    drop(client);
+
+    /* A longer window applies to the same last activity */
+    let client = client_at(idle(20), path);
+    let renewed = upload_expires(&client, &location);
+    let extended = renewed.duration_since(expires).unwrap().as_secs();
+    assert!((9..=11).contains(&extended));
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::{client, head, patch};
+use meteoritus::Meteoritus;
+use rocket::http::{Header, Status};
+use std::time::Duration;
+
+fn extensions(meteoritus: Meteoritus<rocket::Build>) -> String {
+    let client = client(meteoritus);
+    let response = client.options("/meteoritus").dispatch();
+
+    response
+        .headers()
+        .get_one("Tus-Extension")
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn extensions_follow_the_configuration() {
+    assert_eq!(extensions(Meteoritus::new()), "creation,termination");
+
+    assert_eq!(
+        extensions(Meteoritus::new().with_expiration(Duration::from_secs(60))),
+        "creation,termination,expiration"
+    );
+
+    assert_eq!(
+        extensions(Meteoritus::new().with_chunk_checksums()),
+        "creation,termination,checksum"
+    );
+}
+
+#[test]
+fn expiring_uploads_report_upload_expires() {
+    let client =
+        client(Meteoritus::new().with_expiration(Duration::from_secs(60)));
+
+    let response = client
+        .post("/meteoritus")
+        .header(Header::new("Tus-Resumable", "1.0.0"))
+        .header(Header::new("Upload-Length", "4"))
+        .dispatch();
+    assert_eq!(response.status(), Status::Created);
+    assert!(response
+        .headers()
+        .get_one("Upload-Expires")
+        .unwrap()
+        .ends_with("GMT"));
+
+    let location = response.headers().get_one("Location").unwrap().to_string();
+
+    let response = patch(&client, &location, 0, b"ab");
+    assert!(response.headers().contains("Upload-Expires"));
+
+    let response = head(&client, &location);
+    assert!(response.headers().contains("Upload-Expires"));
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use common::{client, create, head, patch};
+use meteoritus::Meteoritus;
+use rocket::{http::Status, local::blocking::Client};
+use std::sync::{Arc, Mutex};
+
+fn limited(max_failures: u32, terminated: &Arc<Mutex<Vec<String>>>) -> Client {
+    let terminated = terminated.to_owned();
+
+    client(
+        Meteoritus::new()
+            .with_max_failed_chunks(max_failures)
+            .on_termination(move |ctx| {
+                terminated
+                    .lock()
+                    .unwrap()
+                    .push(ctx.file_info.id().to_string())
+            }),
+    )
+}
+
+#[test]
+fn upload_is_aborted_after_too_many_failed_chunks() {
+    let terminated = Arc::new(Mutex::new(Vec::new()));
+    let client = limited(2, &terminated);
+    let location = create(&client, 8);
+
+    for _ in 0..2 {
+        assert_eq!(
+            patch(&client, &location, 4, b"ab").status(),
+            Status::Conflict
+        );
+    }
+
+    assert!(terminated.lock().unwrap().is_empty());
+
+    patch(&client, &location, 4, b"ab");
+    assert_eq!(terminated.lock().unwrap().len(), 1);
+
+    assert_eq!(patch(&client, &location, 0, b"ab").status(), Status::Gone);
+    assert_eq!(head(&client, &location).status(), Status::Gone);
+}
+
+#[test]
+fn successful_chunk_resets_the_failures() {
+    let terminated = Arc::new(Mutex::new(Vec::new()));
+    let client = limited(1, &terminated);
+    let location = create(&client, 8);
+
+    assert_eq!(
+        patch(&client, &location, 4, b"ab").status(),
+        Status::Conflict
+    );
+    assert_eq!(
+        patch(&client, &location, 0, b"ab").status(),
+        Status::NoContent
+    );
+    assert_eq!(
+        patch(&client, &location, 4, b"ab").status(),
+        Status::Conflict
+    );
+    assert_eq!(
+        patch(&client, &location, 2, b"ab").status(),
+        Status::NoContent
+    );
+
+    assert!(terminated.lock().unwrap().is_empty());
+}
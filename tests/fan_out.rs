@@ -0,0 +1,94 @@
+mod common;
+
+use common::{client, create, patch, temp_path, upload_id};
+use meteoritus::Meteoritus;
+use rocket::http::Status;
+use std::{
+    fs,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+fn leak(paths: Vec<&'static str>) -> &'static [&'static str] {
+    Box::leak(paths.into_boxed_slice())
+}
+
+#[cfg(unix)]
+#[test]
+fn completed_file_is_hard_linked_into_every_directory() {
+    use std::os::unix::fs::MetadataExt;
+
+    let (first, second) = (temp_path(), temp_path());
+    let client =
+        client(Meteoritus::new().with_fan_out_paths(leak(vec![first, second])));
+
+    let location = create(&client, 4);
+    assert_eq!(
+        patch(&client, &location, 0, b"abcd").status(),
+        Status::NoContent
+    );
+
+    let inode = |dir: &str| {
+        fs::metadata(Path::new(dir).join(upload_id(&location)))
+            .unwrap()
+            .ino()
+    };
+    assert_eq!(inode(first), inode(second));
+}
+
+#[test]
+fn failed_fan_out_is_rolled_back_without_failing_the_upload() {
+    static COMPLETED: AtomicUsize = AtomicUsize::new(0);
+
+    let linked = temp_path();
+    let blocker = temp_path();
+    fs::write(blocker, b"not a directory").unwrap();
+
+    let client = client(
+        Meteoritus::new()
+            .with_fan_out_paths(leak(vec![linked, blocker]))
+            .on_completed(|_| {
+                COMPLETED.fetch_add(1, Ordering::SeqCst);
+            }),
+    );
+
+    let location = create(&client, 4);
+    assert_eq!(
+        patch(&client, &location, 0, b"abcd").status(),
+        Status::NoContent
+    );
+    assert_eq!(COMPLETED.load(Ordering::SeqCst), 1);
+
+    assert!(!Path::new(linked).join(upload_id(&location)).exists());
+}
+
+#[test]
+fn copies_across_filesystems_leave_no_partial_file() {
+    let shm = Path::new("/dev/shm");
+    if !shm.is_dir() {
+        return;
+    }
+
+    let other = shm.join(Path::new(temp_path()).file_name().unwrap());
+    let other: &'static str =
+        Box::leak(other.to_str().unwrap().to_string().into_boxed_str());
+
+    let client =
+        client(Meteoritus::new().with_fan_out_paths(leak(vec![other])));
+
+    let location = create(&client, 4);
+    assert_eq!(
+        patch(&client, &location, 0, b"abcd").status(),
+        Status::NoContent
+    );
+
+    let entries: Vec<_> = fs::read_dir(other)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(entries, [upload_id(&location)]);
+    assert_eq!(
+        fs::read(Path::new(other).join(upload_id(&location))).unwrap(),
+        b"abcd"
+    );
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::{client, client_at, create, head, patch, temp_path, upload_id};
+use meteoritus::Meteoritus;
+use rocket::http::Status;
+use std::{fs, path::Path};
+
+/// Returns a destination on another filesystem than the uploads, if there is one.
+fn other_filesystem() -> Option<&'static str> {
+    let shm = Path::new("/dev/shm");
+
+    if !shm.is_dir() {
+        return None;
+    }
+
+    let path = shm.join(Path::new(temp_path()).file_name()?);
+    Some(Box::leak(path.to_str()?.to_string().into_boxed_str()))
+}
+
+#[test]
+fn completed_file_is_copied_across_filesystems() {
+    let destinations = [Some(temp_path()), other_filesystem()];
+
+    for destination in destinations.into_iter().flatten() {
+        let client = client(Meteoritus::new().with_completed_path(destination));
+
+        let location = create(&client, 6);
+        assert_eq!(
+            patch(&client, &location, 0, b"abc").status(),
+            Status::NoContent
+        );
+        assert_eq!(
+            patch(&client, &location, 3, b"def").status(),
+            Status::NoContent
+        );
+
+        let moved = Path::new(destination).join(upload_id(&location));
+        assert_eq!(fs::read(moved).unwrap(), b"abcdef");
+    }
+}
+
+#[test]
+fn failed_move_leaves_the_upload_in_place() {
+    let path = temp_path();
+    let blocker = Path::new(temp_path()).to_path_buf();
+    fs::write(&blocker, b"not a directory").unwrap();
+
+    /* The destination is below a regular file, so it can never be created */
+    let destination = blocker.join("completed");
+    let destination =
+        Box::leak(destination.to_str().unwrap().to_string().into_boxed_str());
+
+    let client =
+        client_at(Meteoritus::new().with_completed_path(destination), path);
+
+    let location = create(&client, 3);
+    let response = patch(&client, &location, 0, b"abc");
+    assert_eq!(response.status(), Status::InternalServerError);
+
+    let upload_dir = Path::new(path).join(upload_id(&location));
+    assert_eq!(fs::read(upload_dir.join("file")).unwrap(), b"abc");
+    assert!(!upload_dir.join("info.json.tmp").exists());
+
+    let manifest = fs::read_to_string(upload_dir.join("info.json")).unwrap();
+    assert!(!manifest.contains(&*destination));
+
+    let response = head(&client, &location);
+    assert_eq!(response.status(), Status::NoContent);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("3"));
+}
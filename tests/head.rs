@@ -0,0 +1,43 @@
+mod common;
+
+use common::{client, client_at, create, head, patch, temp_path};
+use meteoritus::Meteoritus;
+use rocket::http::Status;
+
+#[test]
+fn completed_uploads_report_their_length_and_offset() {
+    let client = client(Meteoritus::new());
+    let location = create(&client, 3);
+
+    assert_eq!(
+        patch(&client, &location, 0, b"abc").status(),
+        Status::NoContent
+    );
+
+    let response = head(&client, &location);
+    assert_eq!(response.status(), Status::NoContent);
+    assert_eq!(response.headers().get_one("Upload-Length"), Some("3"));
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("3"));
+}
+
+#[test]
+fn completed_uploads_report_their_length_after_a_restart() {
+    let path = temp_path();
+
+    let location = {
+        let client = client_at(Meteoritus::new(), path);
+        let location = create(&client, 2);
+
+        assert_eq!(
+            patch(&client, &location, 0, b"ab").status(),
+            Status::NoContent
+        );
+        location
+    };
+
+    let client = client_at(Meteoritus::new(), path);
+
+    let response = head(&client, &location);
+    assert_eq!(response.headers().get_one("Upload-Length"), Some("2"));
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("2"));
+}
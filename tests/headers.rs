@@ -0,0 +1,62 @@
+mod common;
+
+use common::{client, create, tus_header};
+use meteoritus::{Meteoritus, MeteoritusError};
+use rocket::http::{ContentType, Header, Status};
+
+#[test]
+fn header_names_are_case_insensitive() {
+    let client = client(Meteoritus::new());
+
+    let response = client
+        .post("/meteoritus")
+        .header(Header::new("tus-resumable", "1.0.0"))
+        .header(Header::new("UPLOAD-LENGTH", "3"))
+        .dispatch();
+    assert_eq!(response.status(), Status::Created);
+}
+
+#[test]
+fn repeated_headers_with_the_same_value_are_accepted() {
+    let client = client(Meteoritus::new());
+
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "3"))
+        .header(Header::new("upload-length", "3"))
+        .dispatch();
+    assert_eq!(response.status(), Status::Created);
+}
+
+#[test]
+fn conflicting_creation_headers_are_rejected() {
+    let client = client(Meteoritus::new());
+
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "3"))
+        .header(Header::new("Upload-Length", "4"))
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[test]
+fn conflicting_upload_headers_are_rejected() {
+    let client = client(Meteoritus::new().with_error_status(
+        MeteoritusError::ConflictingHeaders,
+        Status::UnprocessableEntity,
+    ));
+    let location = create(&client, 3);
+
+    let response = client
+        .patch(location.clone())
+        .header(tus_header())
+        .header(Header::new("Upload-Offset", "0"))
+        .header(Header::new("Upload-Offset", "1"))
+        .header(ContentType::new("application", "offset+octet-stream"))
+        .body(b"abc")
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
@@ -0,0 +1,181 @@
+mod common;
+
+use common::{client, create, head, temp_path, tus_header};
+use meteoritus::Meteoritus;
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::blocking::{Client, LocalResponse},
+};
+use std::{
+    fs,
+    net::SocketAddr,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+fn keyed(meteoritus: Meteoritus<rocket::Build>) -> Client {
+    client(meteoritus.with_idempotency_keys(Duration::from_secs(60)))
+}
+
+fn keyed_patch<'c>(
+    client: &'c Client,
+    location: &str,
+    key: &str,
+    body: &[u8],
+) -> LocalResponse<'c> {
+    client
+        .patch(location.to_string())
+        .header(tus_header())
+        .header(Header::new("Upload-Offset", "0"))
+        .header(Header::new("Idempotency-Key", key.to_string()))
+        .header(ContentType::new("application", "offset+octet-stream"))
+        .body(body)
+        .dispatch()
+}
+
+fn keyed_creation<'c>(
+    client: &'c Client,
+    key: &str,
+    metadata: &str,
+    remote: &str,
+) -> LocalResponse<'c> {
+    client
+        .post("/meteoritus")
+        .remote(remote.parse::<SocketAddr>().unwrap())
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "8"))
+        .header(Header::new("Upload-Metadata", metadata.to_string()))
+        .header(Header::new("Idempotency-Key", key.to_string()))
+        .dispatch()
+}
+
+fn location<'c>(response: &LocalResponse<'c>) -> String {
+    response.headers().get_one("Location").unwrap().to_string()
+}
+
+const CLIENT: &str = "10.0.0.1:4000";
+
+#[test]
+fn retried_creation_gets_the_first_upload() {
+    let client = keyed(Meteoritus::new());
+
+    let first = keyed_creation(&client, "k1", "name YQ==", CLIENT);
+    assert_eq!(first.status(), Status::Created);
+
+    let retry = keyed_creation(&client, "k1", "name YQ==", CLIENT);
+    assert_eq!(retry.status(), Status::Created);
+    assert_eq!(location(&retry), location(&first));
+}
+
+#[test]
+fn creation_keys_are_scoped_per_client() {
+    let client = keyed(Meteoritus::new());
+
+    let first = keyed_creation(&client, "k1", "name YQ==", CLIENT);
+    let other = keyed_creation(&client, "k1", "name YQ==", "10.0.0.2:4000");
+
+    assert_eq!(other.status(), Status::Created);
+    assert_ne!(location(&other), location(&first));
+}
+
+#[test]
+fn creation_key_reused_with_other_metadata_is_rejected() {
+    let client = keyed(Meteoritus::new());
+
+    let first = keyed_creation(&client, "k1", "name YQ==", CLIENT);
+    assert_eq!(first.status(), Status::Created);
+
+    let reused = keyed_creation(&client, "k1", "name Yg==", CLIENT);
+    assert_eq!(reused.status(), Status::UnprocessableEntity);
+}
+
+#[test]
+fn failed_creation_releases_its_key() {
+    let rejected = AtomicBool::new(false);
+    let client = keyed(Meteoritus::new().on_creation(move |_| {
+        match rejected.swap(true, Ordering::SeqCst) {
+            false => Err("not yet".into()),
+            true => Ok(()),
+        }
+    }));
+
+    let first = keyed_creation(&client, "k1", "name YQ==", CLIENT);
+    assert_eq!(first.status(), Status::UnprocessableEntity);
+
+    let retry = keyed_creation(&client, "k1", "name YQ==", CLIENT);
+    assert_eq!(retry.status(), Status::Created);
+}
+
+#[test]
+fn retried_patch_is_applied_once() {
+    let client = keyed(Meteoritus::new());
+    let location = create(&client, 8);
+
+    for _ in 0..2 {
+        let response = keyed_patch(&client, &location, "k1", b"abcd");
+        assert_eq!(response.status(), Status::NoContent);
+        assert_eq!(response.headers().get_one("Upload-Offset"), Some("4"));
+    }
+
+    let response = head(&client, &location);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("4"));
+
+    let response = keyed_patch(&client, &location, "k2", b"abcd");
+    assert_eq!(response.status(), Status::Conflict);
+}
+
+#[test]
+fn retry_of_a_terminated_upload_is_not_acknowledged() {
+    let client = keyed(Meteoritus::new());
+    let location = create(&client, 8);
+
+    let response = keyed_patch(&client, &location, "k1", b"abcd");
+    assert_eq!(response.status(), Status::NoContent);
+
+    let response = client
+        .delete(location.clone())
+        .header(tus_header())
+        .dispatch();
+    assert_eq!(response.status(), Status::NoContent);
+
+    let response = keyed_patch(&client, &location, "k1", b"abcd");
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[test]
+fn failed_completion_is_not_acknowledged_to_a_retry() {
+    let blocker = Path::new(temp_path()).to_path_buf();
+    fs::write(&blocker, b"not a directory").unwrap();
+
+    /* The destination is below a regular file, so the move always fails */
+    let destination = blocker.join("completed");
+    let destination =
+        Box::leak(destination.to_str().unwrap().to_string().into_boxed_str());
+
+    let client = keyed(Meteoritus::new().with_completed_path(destination));
+    let location = create(&client, 3);
+
+    let response = keyed_patch(&client, &location, "k1", b"abc");
+    assert_eq!(response.status(), Status::InternalServerError);
+
+    let response = keyed_patch(&client, &location, "k1", b"abc");
+    assert_ne!(response.status(), Status::NoContent);
+}
+
+#[test]
+fn preflight_allows_the_idempotency_key() {
+    let client = client(Meteoritus::new().with_cors_origin("https://a.b"));
+
+    let response = client
+        .options("/meteoritus")
+        .header(Header::new("Access-Control-Request-Method", "PATCH"))
+        .header(Header::new("Origin", "https://a.b"))
+        .dispatch();
+
+    let allowed = response
+        .headers()
+        .get_one("Access-Control-Allow-Headers")
+        .unwrap();
+    assert!(allowed.contains("Idempotency-Key"));
+}
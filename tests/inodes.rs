@@ -0,0 +1,94 @@
+mod common;
+
+use common::{client_at, temp_path, tus_header};
+use meteoritus::{InodeStats, Meteoritus};
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// Reports a fixed amount of inodes, recording the inspected paths.
+struct FixedInodes {
+    available: u64,
+    inspected: Arc<Mutex<Vec<PathBuf>>>,
+}
+
+impl InodeStats for FixedInodes {
+    fn available_inodes(&self, path: &Path) -> Option<u64> {
+        self.inspected.lock().unwrap().push(path.to_path_buf());
+
+        match path.exists() {
+            true => Some(self.available),
+            false => None,
+        }
+    }
+}
+
+fn with_inodes(
+    available: u64,
+    path: &'static str,
+) -> (Client, Arc<Mutex<Vec<PathBuf>>>) {
+    let inspected = Arc::new(Mutex::new(Vec::new()));
+    let stats = FixedInodes {
+        available,
+        inspected: Arc::clone(&inspected),
+    };
+
+    let client = client_at(
+        Meteoritus::new()
+            .allow_dry_run()
+            .with_min_free_inodes(100)
+            .with_inode_stats(stats),
+        path,
+    );
+
+    (client, inspected)
+}
+
+fn creation(client: &Client, dry_run: bool) -> Status {
+    let mut request = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "1"));
+
+    if dry_run {
+        request = request.header(Header::new("Upload-Dry-Run", "1"));
+    }
+
+    request.dispatch().status()
+}
+
+#[test]
+fn creation_is_refused_when_too_few_inodes_are_left() {
+    let (client, _) = with_inodes(99, temp_path());
+
+    assert_eq!(creation(&client, false), Status::InsufficientStorage);
+    assert_eq!(creation(&client, true), Status::InsufficientStorage);
+}
+
+#[test]
+fn creation_is_accepted_with_enough_inodes() {
+    let (client, _) = with_inodes(100, temp_path());
+
+    assert_eq!(creation(&client, true), Status::NoContent);
+    assert_eq!(creation(&client, false), Status::Created);
+}
+
+#[test]
+fn nearest_existing_ancestor_is_inspected() {
+    let path = Path::new(temp_path()).join("not").join("created");
+    let path = Box::leak(path.to_str().unwrap().to_string().into_boxed_str());
+
+    let (client, inspected) = with_inodes(99, path);
+    assert!(!Path::new(path).exists());
+
+    assert_eq!(creation(&client, false), Status::InsufficientStorage);
+
+    let inspected = inspected.lock().unwrap();
+    assert_eq!(inspected[0], Path::new(path));
+    assert!(inspected.last().unwrap().exists());
+}
@@ -0,0 +1,59 @@
+mod common;
+
+use common::{client, create, patch, tus_header, upload_id};
+use meteoritus::{Meteoritus, Vault};
+use rocket::{http::Status, local::blocking::Client};
+use std::sync::Arc;
+
+fn incomplete(client: &Client) -> Vec<String> {
+    let vault = client.rocket().state::<Arc<dyn Vault>>().unwrap();
+
+    let mut ids: Vec<String> = vault
+        .list_incomplete()
+        .unwrap()
+        .map(|file| file.id().to_string())
+        .collect();
+    ids.sort();
+
+    ids
+}
+
+#[test]
+fn only_uploads_missing_bytes_are_listed() {
+    let client = client(Meteoritus::new());
+
+    let empty = create(&client, 2);
+    let partial = create(&client, 2);
+    let completed = create(&client, 2);
+
+    assert_eq!(
+        patch(&client, &partial, 0, b"a").status(),
+        Status::NoContent
+    );
+    assert_eq!(
+        patch(&client, &completed, 0, b"ab").status(),
+        Status::NoContent
+    );
+
+    let mut expected = vec![
+        upload_id(&empty).to_string(),
+        upload_id(&partial).to_string(),
+    ];
+    expected.sort();
+
+    assert_eq!(incomplete(&client), expected);
+}
+
+#[test]
+fn terminated_uploads_are_not_listed() {
+    let client = client(Meteoritus::new());
+    let location = create(&client, 2);
+
+    let response = client
+        .delete(location.clone())
+        .header(tus_header())
+        .dispatch();
+    assert_eq!(response.status(), Status::NoContent);
+
+    assert!(incomplete(&client).is_empty());
+}
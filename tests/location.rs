@@ -0,0 +1,63 @@
+mod common;
+
+use common::{client, tus_header};
+use meteoritus::{LocationFormat, Meteoritus};
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+
+fn location(client: &Client) -> String {
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "1"))
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Created);
+    response.headers().get_one("Location").unwrap().to_string()
+}
+
+/// Returns the upload id of a location, checking it follows `prefix`.
+fn id_after<'a>(location: &'a str, prefix: &str) -> &'a str {
+    let id = location.strip_prefix(prefix).unwrap();
+    assert!(!id.is_empty() && !id.contains('/'));
+
+    id
+}
+
+#[test]
+fn location_defaults_to_an_absolute_path() {
+    let client = client(Meteoritus::new());
+
+    id_after(&location(&client), "/meteoritus/");
+}
+
+#[test]
+fn location_can_omit_the_leading_slash() {
+    let client = client(
+        Meteoritus::new().with_location_format(LocationFormat::RelativePath),
+    );
+
+    id_after(&location(&client), "meteoritus/");
+}
+
+#[test]
+fn location_can_be_the_id_only() {
+    let client =
+        client(Meteoritus::new().with_location_format(LocationFormat::Id));
+
+    id_after(&location(&client), "");
+}
+
+#[test]
+fn location_can_be_an_absolute_url() {
+    let client = client(Meteoritus::new().with_location_format(
+        LocationFormat::Url("https://uploads.example.com/"),
+    ));
+
+    id_after(
+        &location(&client),
+        "https://uploads.example.com/meteoritus/",
+    );
+}
@@ -0,0 +1,24 @@
+mod common;
+
+use common::{client, create, head};
+use meteoritus::Meteoritus;
+use rocket::http::Status;
+
+#[test]
+fn lookups_above_the_limit_are_refused() {
+    let client = client(Meteoritus::new().with_max_lookups(0));
+    let location = create(&client, 8);
+
+    let response = head(&client, &location);
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+}
+
+#[test]
+fn finished_lookups_release_their_permit() {
+    let client = client(Meteoritus::new().with_max_lookups(1));
+    let location = create(&client, 8);
+
+    for _ in 0..3 {
+        assert_eq!(head(&client, &location).status(), Status::NoContent);
+    }
+}
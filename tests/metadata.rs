@@ -0,0 +1,267 @@
+mod common;
+
+use common::{b64, client, create_with_metadata};
+use meteoritus::{
+    KeyCollision, Metadata, MetadataError, MetadataOptions, Meteoritus,
+};
+use rocket::http::Status;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+#[test]
+fn oversized_value_is_rejected_among_valid_ones() {
+    let options = MetadataOptions::new().with_max_value_length(4);
+
+    let metadata =
+        format!("a {}, b {}, c {}", b64("ab"), b64("hello"), b64("c"));
+    assert_eq!(
+        Metadata::parse(&metadata, &options).err(),
+        Some(MetadataError::ValueTooLarge)
+    );
+
+    let metadata = format!("a {}, b {}", b64("ab"), b64("abcd"));
+    assert_eq!(Metadata::parse(&metadata, &options).map(|m| m.len()), Ok(2));
+}
+
+#[test]
+fn try_from_does_not_limit_value_length() {
+    let value = "x".repeat(64 * 1024);
+    let metadata = Metadata::try_from(format!("a {}", b64(&value)).as_str());
+
+    assert_eq!(metadata.map(|m| m.get_raw("a")), Ok(Ok(value.into_bytes())));
+}
+
+#[test]
+fn creation_rejects_oversized_values() {
+    let client = client(Meteoritus::new().with_metadata_options(
+        MetadataOptions::new().with_max_value_length(4),
+    ));
+
+    let metadata = format!("a {}, b {}", b64("a"), b64("hello"));
+    let response = create_with_metadata(&client, 1, &metadata);
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let metadata = format!("a {}, b {}", b64("a"), b64("hi"));
+    let response = create_with_metadata(&client, 1, &metadata);
+    assert_eq!(response.status(), Status::Created);
+
+    let response = create_with_metadata(&client, 1, "a *");
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[test]
+fn budget_aborts_pathologically_large_metadata() {
+    let metadata: String = (0..20000)
+        .map(|i| format!("k{} YQ==", i))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let budgeted = client(Meteoritus::new().with_metadata_options(
+        MetadataOptions::new().with_decode_budget(Duration::from_nanos(1)),
+    ));
+    let response = create_with_metadata(&budgeted, 1, &metadata);
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+
+    let bounded =
+        client(Meteoritus::new().with_metadata_options(
+            MetadataOptions::new().with_max_length(1000),
+        ));
+    let response = create_with_metadata(&bounded, 1, &metadata);
+    assert_eq!(response.status(), Status::PayloadTooLarge);
+}
+
+#[test]
+fn budget_is_checked_while_decoding_a_single_value() {
+    let options = MetadataOptions::new()
+        .with_max_value_length(usize::MAX)
+        .with_decode_budget(Duration::from_millis(1));
+
+    let metadata = format!("a {}", b64(&"x".repeat(32 * 1024 * 1024)));
+
+    assert_eq!(
+        Metadata::parse(&metadata, &options).err(),
+        Some(MetadataError::BudgetExceeded)
+    );
+}
+
+#[test]
+fn try_from_does_not_limit_key_length() {
+    let key = "k".repeat(1024);
+    let metadata = Metadata::try_from(format!("{} {}", key, b64("a")).as_str());
+
+    assert_eq!(metadata.map(|m| m.get_raw(&key)), Ok(Ok(b"a".to_vec())));
+}
+
+#[test]
+fn creation_rejects_long_keys() {
+    let defaults = client(Meteoritus::new());
+
+    let metadata = format!("{} {}", "k".repeat(257), b64("a"));
+    let response = create_with_metadata(&defaults, 1, &metadata);
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let client =
+        client(Meteoritus::new().with_metadata_options(
+            MetadataOptions::new().with_max_key_length(4),
+        ));
+
+    let response =
+        create_with_metadata(&client, 1, &format!("abcd {}", b64("a")));
+    assert_eq!(response.status(), Status::Created);
+
+    let response =
+        create_with_metadata(&client, 1, &format!("abcde {}", b64("a")));
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[test]
+fn keys_with_control_characters_are_invalid() {
+    for key in ["file\0name", "file\nname", "\u{7f}"] {
+        let metadata = format!("{} {}", key, b64("a"));
+        assert_eq!(
+            Metadata::try_from(metadata.as_str()).err(),
+            Some(MetadataError::InvalidKey)
+        );
+    }
+}
+
+#[test]
+fn duplicated_keys_are_rejected() {
+    let metadata = format!("a {}, b {}, a {}", b64("x"), b64("y"), b64("z"));
+
+    assert_eq!(
+        Metadata::try_from(metadata.as_str()).err(),
+        Some(MetadataError::InvalidMetadataFormat)
+    );
+}
+
+#[test]
+fn values_must_be_base64() {
+    for value in ["not*base64", "YQ", "YQ=", "Y"] {
+        let metadata = format!("a {}", value);
+        assert!(matches!(
+            Metadata::try_from(metadata.as_str()).err(),
+            Some(MetadataError::DecodeError(_))
+        ));
+    }
+}
+
+#[test]
+fn multibyte_keys_are_kept() {
+    let metadata = format!("näme {}, € {}", b64("a"), b64("b"));
+    let metadata = Metadata::try_from(metadata.as_str()).unwrap();
+
+    assert_eq!(metadata.get_raw("näme"), Ok(b"a".to_vec()));
+    assert_eq!(metadata.get_raw("€"), Ok(b"b".to_vec()));
+}
+
+#[test]
+fn fuzz_corpus_parses_without_panicking() {
+    let corpus = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fuzz/corpus/metadata");
+
+    let mut inputs = 0;
+    for entry in std::fs::read_dir(corpus).unwrap() {
+        let data = std::fs::read(entry.unwrap().path()).unwrap();
+
+        /* Every prefix must produce either a valid Metadata or a MetadataError */
+        for end in 0..=data.len() {
+            if let Ok(value) = std::str::from_utf8(&data[..end]) {
+                let _ = Metadata::try_from(value).map(|m| m.len());
+                inputs += 1;
+            }
+        }
+    }
+
+    assert!(inputs > 0);
+}
+
+#[test]
+fn keys_outside_the_allowlist_are_rejected() {
+    let options = MetadataOptions::new().with_allowed_keys(&["filename"]);
+
+    let metadata = format!("filename {}", b64("a.txt"));
+    assert_eq!(Metadata::parse(&metadata, &options).map(|m| m.len()), Ok(1));
+
+    let metadata = format!("filename {}, owner {}", b64("a.txt"), b64("me"));
+    assert_eq!(
+        Metadata::parse(&metadata, &options).err(),
+        Some(MetadataError::KeyNotAllowed)
+    );
+
+    let client = client(Meteoritus::new().with_metadata_options(options));
+    let response = create_with_metadata(&client, 1, &metadata);
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[test]
+fn keys_outside_the_allowlist_can_be_dropped() {
+    let stored = Arc::new(Mutex::new(Vec::new()));
+    let created = Arc::clone(&stored);
+
+    let client = client(
+        Meteoritus::new()
+            .with_metadata_options(
+                MetadataOptions::new()
+                    .with_allowed_keys(&["filename"])
+                    .drop_disallowed_keys(),
+            )
+            .on_created(move |ctx| {
+                let metadata = ctx.file_info.metadata().as_ref().unwrap();
+                created.lock().unwrap().push(metadata.canonical());
+            }),
+    );
+
+    let metadata = format!("filename {}, owner {}", b64("a.txt"), b64("me"));
+    let response = create_with_metadata(&client, 1, &metadata);
+    assert_eq!(response.status(), Status::Created);
+
+    assert_eq!(
+        *stored.lock().unwrap(),
+        [format!("filename {}", b64("a.txt"))]
+    );
+}
+
+#[test]
+fn canonical_keys_are_lowercased() {
+    let options = MetadataOptions::new()
+        .with_canonical_keys(KeyCollision::Reject)
+        .with_allowed_keys(&["filename"]);
+
+    let metadata = format!("FileName {}", b64("a.txt"));
+    let metadata = Metadata::parse(&metadata, &options).unwrap();
+
+    assert_eq!(metadata.get_raw("filename"), Ok(b"a.txt".to_vec()));
+    assert!(metadata.get_raw("FileName").is_err());
+}
+
+#[test]
+fn colliding_canonical_keys_follow_the_policy() {
+    let metadata = format!("Filename {}, filename {}", b64("a"), b64("b"));
+
+    let reject =
+        MetadataOptions::new().with_canonical_keys(KeyCollision::Reject);
+    assert_eq!(
+        Metadata::parse(&metadata, &reject).err(),
+        Some(MetadataError::KeyCollision)
+    );
+
+    let last_wins =
+        MetadataOptions::new().with_canonical_keys(KeyCollision::LastWins);
+    assert_eq!(
+        Metadata::parse(&metadata, &last_wins).map(|m| m.get_raw("filename")),
+        Ok(Ok(b"b".to_vec()))
+    );
+
+    /* Without canonicalization both keys are kept as sent */
+    assert_eq!(
+        Metadata::parse(&metadata, &MetadataOptions::new()).map(|m| m.len()),
+        Ok(2)
+    );
+
+    let client = client(Meteoritus::new().with_metadata_options(reject));
+    let response = create_with_metadata(&client, 1, &metadata);
+    assert_eq!(response.status(), Status::BadRequest);
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::{b64, client, create_with_metadata, tus_header};
+use meteoritus::Meteoritus;
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+use sha2::{Digest, Sha256};
+
+fn head_with_hash(client: &Client, location: &str, hash: &str) -> Status {
+    client
+        .head(location.to_string())
+        .header(tus_header())
+        .header(Header::new("Upload-Metadata-Hash", hash.to_string()))
+        .dispatch()
+        .status()
+}
+
+#[test]
+fn metadata_hash_is_computed_over_the_canonical_metadata() {
+    let client = client(Meteoritus::new());
+
+    let metadata = format!("type {}, name {}", b64("b"), b64("a"));
+    let response = create_with_metadata(&client, 4, &metadata);
+    let location = response.headers().get_one("Location").unwrap();
+
+    /* Keys are sorted, so the order sent on creation does not matter */
+    let canonical = format!("name {},type {}", b64("a"), b64("b"));
+    let hash: String = Sha256::digest(canonical.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect();
+
+    assert_eq!(head_with_hash(&client, location, &hash), Status::NoContent);
+}
+
+#[test]
+fn mismatching_metadata_hash_is_rejected() {
+    let client = client(Meteoritus::new());
+
+    let metadata = format!("name {}", b64("a"));
+    let response = create_with_metadata(&client, 4, &metadata);
+    let location = response.headers().get_one("Location").unwrap();
+
+    let hash = format!("{:x}", Sha256::digest(b"name Yg=="));
+    assert_eq!(
+        head_with_hash(&client, location, &hash),
+        Status::PreconditionFailed
+    );
+}
+
+#[test]
+fn preflight_allows_the_metadata_hash() {
+    let client = client(Meteoritus::new().with_cors_origin("https://a.b"));
+
+    let response = client
+        .options("/meteoritus")
+        .header(Header::new("Access-Control-Request-Method", "HEAD"))
+        .header(Header::new("Origin", "https://a.b"))
+        .dispatch();
+
+    let allowed = response
+        .headers()
+        .get_one("Access-Control-Allow-Headers")
+        .unwrap();
+    assert!(allowed.contains("Upload-Metadata-Hash"));
+}
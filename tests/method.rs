@@ -0,0 +1,63 @@
+mod common;
+
+use common::{client, create, temp_path};
+use meteoritus::Meteoritus;
+use rocket::{http::Status, local::blocking::Client};
+
+#[cfg(not(feature = "download"))]
+const UPLOAD_ALLOW: &str = "OPTIONS, HEAD, PATCH, DELETE";
+
+#[cfg(feature = "download")]
+const UPLOAD_ALLOW: &str = "OPTIONS, HEAD, GET, PATCH, DELETE";
+
+#[test]
+fn unsupported_methods_on_the_creation_url_are_not_allowed() {
+    let client = client(Meteoritus::new());
+
+    for response in [
+        client.put("/meteoritus").dispatch(),
+        client.get("/meteoritus").dispatch(),
+        client.delete("/meteoritus").dispatch(),
+    ] {
+        assert_eq!(response.status(), Status::MethodNotAllowed);
+        assert_eq!(response.headers().get_one("Allow"), Some("OPTIONS, POST"));
+        assert_eq!(response.headers().get_one("Tus-Resumable"), Some("1.0.0"));
+    }
+}
+
+#[test]
+fn unsupported_methods_on_an_upload_url_are_not_allowed() {
+    let client = client(Meteoritus::new());
+    let location = create(&client, 1);
+
+    for response in [
+        client.put(location.clone()).dispatch(),
+        client.post(location.clone()).dispatch(),
+    ] {
+        assert_eq!(response.status(), Status::MethodNotAllowed);
+        assert_eq!(response.headers().get_one("Allow"), Some(UPLOAD_ALLOW));
+    }
+}
+
+#[rocket::put("/meteoritus/<_>")]
+fn replace() -> &'static str {
+    "replaced"
+}
+
+#[test]
+fn application_routes_sharing_the_path_still_match() {
+    let meteoritus = Meteoritus::new()
+        .with_temp_path(temp_path())
+        .keep_on_disk()
+        .build();
+    let client = Client::tracked(
+        rocket::build()
+            .attach(meteoritus)
+            .mount("/", rocket::routes![replace]),
+    )
+    .unwrap();
+
+    let response = client.put("/meteoritus/some-upload").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().as_deref(), Some("replaced"));
+}
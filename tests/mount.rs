@@ -0,0 +1,51 @@
+mod common;
+
+use common::{client, head, tus_header};
+use meteoritus::Meteoritus;
+use rocket::{
+    http::{Header, Status},
+    local::blocking::{Client, LocalResponse},
+};
+
+fn create_at<'c>(client: &'c Client, url: &'static str) -> LocalResponse<'c> {
+    client
+        .post(url)
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "3"))
+        .dispatch()
+}
+
+#[test]
+fn creation_matches_with_and_without_a_trailing_slash() {
+    for route in ["/files", "/files/", "/files//"] {
+        let client =
+            client(Meteoritus::new().mount_to(route).with_node_id("node"));
+
+        for url in ["/files", "/files/"] {
+            let response = create_at(&client, url);
+            assert_eq!(response.status(), Status::Created);
+            assert!(response.headers().contains("X-Upload-Node"));
+
+            let location = response.headers().get_one("Location").unwrap();
+            assert!(
+                location.starts_with("/files/") && !location.contains("//"),
+                "{} mounted at {}",
+                location,
+                route
+            );
+            assert_eq!(head(&client, location).status(), Status::NoContent);
+        }
+    }
+}
+
+#[test]
+fn creation_can_be_mounted_at_the_root() {
+    let client = client(Meteoritus::new().mount_to("/"));
+
+    let response = create_at(&client, "/");
+    assert_eq!(response.status(), Status::Created);
+
+    let location = response.headers().get_one("Location").unwrap();
+    assert!(!location.contains("//"), "{}", location);
+    assert_eq!(head(&client, location).status(), Status::NoContent);
+}
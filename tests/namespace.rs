@@ -0,0 +1,78 @@
+mod common;
+
+use common::{
+    b64, client_at, create, create_with_metadata, head, temp_path, tus_header,
+    upload_id,
+};
+use meteoritus::Meteoritus;
+use rocket::{http::Status, local::blocking::Client, Orbit};
+use std::{fs, path::Path};
+
+fn namespaced(path: &'static str) -> Client {
+    client_at(Meteoritus::new().with_namespace_key("tenant"), path)
+}
+
+fn create_in(client: &Client, namespace: &str) -> String {
+    let metadata = format!("tenant {}", b64(namespace));
+    let response = create_with_metadata(client, 4, &metadata);
+    assert_eq!(response.status(), Status::Created);
+
+    response.headers().get_one("Location").unwrap().to_string()
+}
+
+fn meteoritus(client: &Client) -> &Meteoritus<Orbit> {
+    client.rocket().state::<Meteoritus<Orbit>>().unwrap()
+}
+
+#[test]
+fn uploads_are_stored_and_purged_per_namespace() {
+    let path = temp_path();
+    let client = namespaced(path);
+
+    let acme = create_in(&client, "acme");
+    let other = create_in(&client, "other");
+    let plain = create(&client, 4);
+
+    let id = upload_id(&acme);
+    assert!(id.starts_with("acme."));
+    assert!(Path::new(path).join("acme").join(&id[5..]).is_dir());
+
+    let files = meteoritus(&client).namespace_files("acme");
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].id(), id);
+
+    assert_eq!(meteoritus(&client).purge_namespace("acme"), 1);
+
+    assert_eq!(head(&client, &acme).status(), Status::NotFound);
+    assert_eq!(head(&client, &other).status(), Status::NoContent);
+    assert_eq!(head(&client, &plain).status(), Status::NoContent);
+}
+
+#[test]
+fn invalid_namespaces_list_nothing() {
+    let path = temp_path();
+    let client = namespaced(path);
+    create_in(&client, "acme");
+
+    assert!(meteoritus(&client).namespace_files("..").is_empty());
+    assert!(meteoritus(&client).namespace_files("acme/..").is_empty());
+    assert_eq!(meteoritus(&client).purge_namespace(".."), 0);
+}
+
+#[test]
+fn ids_can_not_escape_their_namespace() {
+    let path = temp_path();
+    let client = namespaced(path);
+
+    create_in(&client, "acme");
+    let plain = create(&client, 4);
+    assert!(fs::metadata(Path::new(path).join(upload_id(&plain))).is_ok());
+
+    /* An id resolving to the root through the namespace directory */
+    let escaping = format!("/meteoritus/acme.%2E%2E%2F{}", upload_id(&plain));
+    assert_eq!(head(&client, &escaping).status(), Status::NotFound);
+
+    let response = client.delete(escaping).header(tus_header()).dispatch();
+    assert_ne!(response.status(), Status::NoContent);
+    assert_eq!(head(&client, &plain).status(), Status::NoContent);
+}
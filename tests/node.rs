@@ -0,0 +1,66 @@
+#[macro_use]
+extern crate rocket;
+
+mod common;
+
+use common::{client, create, head, tus_header};
+use meteoritus::Meteoritus;
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+
+#[get("/meteoritus-admin")]
+fn admin() -> &'static str {
+    "admin"
+}
+
+fn create_response_node(client: &Client) -> Option<String> {
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "3"))
+        .dispatch();
+    assert_eq!(response.status(), Status::Created);
+
+    response
+        .headers()
+        .get_one("X-Upload-Node")
+        .map(String::from)
+}
+
+#[test]
+fn responses_name_the_serving_node() {
+    let client = client(Meteoritus::new().with_node_id("node-7"));
+
+    let node = create_response_node(&client).unwrap();
+    assert!(node.starts_with("node-7 meteoritus/"));
+
+    let location = create(&client, 3);
+    assert!(head(&client, &location).headers().contains("X-Upload-Node"));
+}
+
+#[test]
+fn node_is_not_named_by_default() {
+    let client = client(Meteoritus::new());
+
+    assert_eq!(create_response_node(&client), None);
+}
+
+#[test]
+fn routes_sharing_the_base_prefix_are_left_alone() {
+    let meteoritus = Meteoritus::new()
+        .with_temp_path(common::temp_path())
+        .with_node_id("node-7")
+        .with_cors_origin("https://a.b")
+        .build();
+    let rocket = rocket::build()
+        .attach(meteoritus)
+        .mount("/", routes![admin]);
+    let client = Client::tracked(rocket).unwrap();
+
+    let response = client.get("/meteoritus-admin").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert!(!response.headers().contains("X-Upload-Node"));
+    assert!(!response.headers().contains("Access-Control-Allow-Origin"));
+}
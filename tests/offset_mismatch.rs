@@ -0,0 +1,72 @@
+mod common;
+
+use common::{client, create, patch, upload_id};
+use meteoritus::Meteoritus;
+use rocket::http::Status;
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn callback_receives_the_expected_and_sent_offsets() {
+    let mismatches = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&mismatches);
+
+    let client =
+        client(Meteoritus::new().on_offset_mismatch(move |ctx, offset| {
+            recorded.lock().unwrap().push((
+                ctx.file_info.id().to_string(),
+                *ctx.file_info.offset(),
+                offset,
+            ));
+        }));
+    let location = create(&client, 4);
+
+    assert_eq!(
+        patch(&client, &location, 0, b"ab").status(),
+        Status::NoContent
+    );
+    assert_eq!(
+        patch(&client, &location, 3, b"c").status(),
+        Status::Conflict
+    );
+
+    assert_eq!(
+        *mismatches.lock().unwrap(),
+        [(upload_id(&location).to_string(), 2, 3)]
+    );
+}
+
+#[test]
+fn callback_is_not_invoked_for_matching_offsets() {
+    let mismatches = Arc::new(Mutex::new(0));
+    let recorded = Arc::clone(&mismatches);
+
+    let client = client(
+        Meteoritus::new()
+            .on_offset_mismatch(move |_, _| *recorded.lock().unwrap() += 1),
+    );
+    let location = create(&client, 2);
+
+    assert_eq!(
+        patch(&client, &location, 0, b"a").status(),
+        Status::NoContent
+    );
+    assert_eq!(
+        patch(&client, &location, 1, b"b").status(),
+        Status::NoContent
+    );
+
+    assert_eq!(*mismatches.lock().unwrap(), 0);
+}
+
+#[test]
+fn panicking_callback_still_answers_the_conflict() {
+    let client = client(
+        Meteoritus::new().on_offset_mismatch(|_, _| panic!("diagnostics down")),
+    );
+    let location = create(&client, 2);
+
+    assert_eq!(
+        patch(&client, &location, 1, b"b").status(),
+        Status::Conflict
+    );
+}
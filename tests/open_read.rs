@@ -0,0 +1,83 @@
+mod common;
+
+use common::{temp_path, tus_header};
+use meteoritus::{Meteoritus, Vault};
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::asynchronous::Client,
+    tokio::io::AsyncReadExt,
+    Build,
+};
+use std::sync::Arc;
+
+async fn client(meteoritus: Meteoritus<Build>) -> Client {
+    let meteoritus = meteoritus.with_temp_path(temp_path()).keep_on_disk();
+
+    Client::tracked(rocket::build().attach(meteoritus.build()))
+        .await
+        .unwrap()
+}
+
+/// Creates an upload of `length` bytes, sends `body` and returns its id.
+async fn upload(client: &Client, length: u64, body: &[u8]) -> String {
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", length.to_string()))
+        .dispatch()
+        .await;
+    let location = response.headers().get_one("Location").unwrap().to_string();
+
+    let response = client
+        .patch(location.clone())
+        .header(tus_header())
+        .header(Header::new("Upload-Offset", "0"))
+        .header(ContentType::new("application", "offset+octet-stream"))
+        .body(body)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NoContent);
+
+    location.rsplit('/').next().unwrap().to_string()
+}
+
+async fn read(client: &Client, id: &str, offset: u64) -> Vec<u8> {
+    let vault = client.rocket().state::<Arc<dyn Vault>>().unwrap();
+
+    let mut content = Vec::new();
+    vault
+        .open_read(id, offset)
+        .unwrap()
+        .read_to_end(&mut content)
+        .await
+        .unwrap();
+
+    content
+}
+
+#[rocket::async_test]
+async fn completed_uploads_are_read_from_the_offset() {
+    let client = client(Meteoritus::new()).await;
+    let id = upload(&client, 5, b"hello").await;
+
+    assert_eq!(read(&client, &id, 0).await, b"hello");
+    assert_eq!(read(&client, &id, 3).await, b"lo");
+}
+
+#[rocket::async_test]
+async fn moved_uploads_are_read_from_their_destination() {
+    let client =
+        client(Meteoritus::new().with_completed_path(temp_path())).await;
+    let id = upload(&client, 5, b"hello").await;
+
+    assert_eq!(read(&client, &id, 1).await, b"ello");
+}
+
+#[rocket::async_test]
+async fn incomplete_uploads_can_not_be_read() {
+    let client = client(Meteoritus::new()).await;
+    let id = upload(&client, 10, b"hello").await;
+
+    let vault = client.rocket().state::<Arc<dyn Vault>>().unwrap();
+    assert!(vault.open_read(&id, 0).is_err());
+}
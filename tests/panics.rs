@@ -0,0 +1,69 @@
+mod common;
+
+use common::{client, create, head, patch, temp_path, tus_header};
+use meteoritus::Meteoritus;
+use rocket::{
+    http::{Header, Status},
+    local::blocking::Client,
+};
+
+#[test]
+fn panicking_creation_callback_rejects_the_upload() {
+    let client =
+        client(Meteoritus::new().on_creation(|_| panic!("validator down")));
+
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "1"))
+        .dispatch();
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+#[test]
+fn panicking_notification_callbacks_do_not_fail_requests() {
+    let client = client(
+        Meteoritus::new()
+            .on_created(|_| panic!("created"))
+            .on_termination(|_| panic!("terminated")),
+    );
+
+    let location = create(&client, 1);
+    assert_eq!(head(&client, &location).status(), Status::NoContent);
+
+    let response = client.delete(location).header(tus_header()).dispatch();
+    assert_eq!(response.status(), Status::NoContent);
+}
+
+#[test]
+fn panicking_completion_callback_keeps_the_upload() {
+    let meteoritus = Meteoritus::new()
+        .with_temp_path(temp_path())
+        .on_completed(|_| panic!("completed"))
+        .build();
+    let client = Client::tracked(rocket::build().attach(meteoritus)).unwrap();
+
+    let location = create(&client, 1);
+    let response = patch(&client, &location, 0, b"a");
+    assert_eq!(response.status(), Status::NoContent);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("1"));
+
+    /* Not auto-terminated, the callback may not have saved it */
+    assert_eq!(head(&client, &location).status(), Status::NoContent);
+}
+
+#[test]
+fn completed_uploads_are_auto_terminated_otherwise() {
+    let meteoritus = Meteoritus::new()
+        .with_temp_path(temp_path())
+        .on_completed(|_| ())
+        .build();
+    let client = Client::tracked(rocket::build().attach(meteoritus)).unwrap();
+
+    let location = create(&client, 1);
+    assert_eq!(
+        patch(&client, &location, 0, b"a").status(),
+        Status::NoContent
+    );
+    assert_eq!(head(&client, &location).status(), Status::NotFound);
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use common::{client, create, head, patch};
+use meteoritus::Meteoritus;
+use rocket::http::Status;
+use std::time::Duration;
+
+#[test]
+fn streams_above_the_limit_are_refused() {
+    let client = client(
+        Meteoritus::new()
+            .with_max_patch_streams(0, Duration::from_millis(1500)),
+    );
+    let location = create(&client, 3);
+
+    let response = patch(&client, &location, 0, b"abc");
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    assert_eq!(response.headers().get_one("Retry-After"), Some("2"));
+
+    let response = head(&client, &location);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("0"));
+}
+
+#[test]
+fn retry_after_is_at_least_a_second() {
+    let client =
+        client(Meteoritus::new().with_max_patch_streams(0, Duration::ZERO));
+    let location = create(&client, 3);
+
+    let response = patch(&client, &location, 0, b"abc");
+    assert_eq!(response.headers().get_one("Retry-After"), Some("1"));
+}
+
+#[test]
+fn written_chunks_release_their_stream() {
+    let client = client(
+        Meteoritus::new().with_max_patch_streams(1, Duration::from_secs(1)),
+    );
+    let location = create(&client, 3);
+
+    for offset in 0..3 {
+        assert_eq!(
+            patch(&client, &location, offset, b"x").status(),
+            Status::NoContent
+        );
+    }
+}
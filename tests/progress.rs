@@ -0,0 +1,51 @@
+mod common;
+
+use common::{client, create, head, patch};
+use meteoritus::Meteoritus;
+use rocket::http::Status;
+
+#[test]
+fn head_reports_the_progress_percentage_when_enabled() {
+    let client = client(Meteoritus::new().with_progress_header());
+    let location = create(&client, 3);
+
+    let progress = |client| {
+        head(client, &location)
+            .headers()
+            .get_one("X-Upload-Progress")
+            .map(str::to_string)
+    };
+
+    assert_eq!(progress(&client).as_deref(), Some("0"));
+
+    assert_eq!(
+        patch(&client, &location, 0, b"x").status(),
+        Status::NoContent
+    );
+    assert_eq!(progress(&client).as_deref(), Some("33"));
+
+    assert_eq!(
+        patch(&client, &location, 1, b"xy").status(),
+        Status::NoContent
+    );
+    assert_eq!(progress(&client).as_deref(), Some("100"));
+}
+
+#[test]
+fn empty_uploads_are_complete() {
+    let client = client(Meteoritus::new().with_progress_header());
+    let location = create(&client, 0);
+
+    let response = head(&client, &location);
+    assert_eq!(response.headers().get_one("X-Upload-Progress"), Some("100"));
+}
+
+#[test]
+fn head_omits_the_progress_by_default() {
+    let client = client(Meteoritus::new());
+    let location = create(&client, 3);
+
+    let response = head(&client, &location);
+    assert_eq!(response.status(), Status::NoContent);
+    assert!(!response.headers().contains("X-Upload-Progress"));
+}
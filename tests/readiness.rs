@@ -0,0 +1,77 @@
+mod common;
+
+use common::{client_at, create, patch, temp_path};
+use meteoritus::Meteoritus;
+use rocket::{
+    http::Status,
+    local::blocking::{Client, LocalResponse},
+    Orbit,
+};
+use std::fs;
+
+fn probed(path: &'static str) -> Client {
+    client_at(Meteoritus::new().with_readiness_probe("/ready"), path)
+}
+
+fn probe(client: &Client) -> LocalResponse<'_> {
+    client.get("/ready").dispatch()
+}
+
+fn drain(client: &Client) {
+    client
+        .rocket()
+        .state::<Meteoritus<Orbit>>()
+        .unwrap()
+        .drain();
+}
+
+#[test]
+fn draining_node_is_drained_once_its_uploads_complete() {
+    let client = probed(temp_path());
+
+    let location = create(&client, 2);
+
+    let response = probe(&client);
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.into_string().unwrap(),
+        r#"{"status":"ready","in_progress":1}"#
+    );
+
+    drain(&client);
+
+    let response = probe(&client);
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    assert_eq!(
+        response.into_string().unwrap(),
+        r#"{"status":"draining","in_progress":1}"#
+    );
+
+    assert_eq!(
+        patch(&client, &location, 0, b"ab").status(),
+        Status::NoContent
+    );
+
+    let response = probe(&client);
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    assert_eq!(
+        response.into_string().unwrap(),
+        r#"{"status":"drained","in_progress":0}"#
+    );
+}
+
+#[test]
+fn unlistable_storage_is_never_reported_drained() {
+    let path = temp_path();
+    fs::write(path, b"not a directory").unwrap();
+
+    let client = probed(path);
+    drain(&client);
+
+    let response = probe(&client);
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    assert_eq!(
+        response.into_string().unwrap(),
+        r#"{"status":"unavailable","in_progress":null}"#
+    );
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::{client, patch, tus_header};
+use meteoritus::Meteoritus;
+use rocket::http::{Header, Status};
+use std::{
+    net::IpAddr,
+    sync::{Arc, Mutex},
+};
+
+/// Uploads through a client at 127.0.0.1 behind two proxies, returning the recorded address.
+fn recorded_address(trusted_proxies: usize) -> Option<IpAddr> {
+    let recorded = Arc::new(Mutex::new(None));
+    let completed = Arc::clone(&recorded);
+
+    let client = client(
+        Meteoritus::new()
+            .with_trusted_proxies(trusted_proxies)
+            .on_completed(move |ctx| {
+                *completed.lock().unwrap() = *ctx.file_info.remote_addr();
+            }),
+    );
+
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "4"))
+        .header(Header::new("X-Forwarded-For", "6.6.6.6, 1.2.3.4"))
+        .remote("127.0.0.1:9999".parse().unwrap())
+        .dispatch();
+    assert_eq!(response.status(), Status::Created);
+
+    let location = response.headers().get_one("Location").unwrap();
+    assert_eq!(
+        patch(&client, location, 0, b"abcd").status(),
+        Status::NoContent
+    );
+
+    let address = *recorded.lock().unwrap();
+    address
+}
+
+#[test]
+fn forwarded_addresses_are_trusted_up_to_the_proxy_depth() {
+    for (trusted_proxies, expected) in [
+        (0, "127.0.0.1"),
+        (1, "1.2.3.4"),
+        (2, "6.6.6.6"),
+        (3, "127.0.0.1"),
+    ] {
+        assert_eq!(
+            recorded_address(trusted_proxies),
+            Some(expected.parse().unwrap()),
+            "{} trusted proxies",
+            trusted_proxies
+        );
+    }
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::{client, create, patch, upload_id};
+use meteoritus::Meteoritus;
+use rocket::http::Status;
+
+#[test]
+fn completing_patch_reports_the_resource_location() {
+    let client = client(Meteoritus::new().with_resource_location(|ctx| {
+        Some(format!("https://cdn.example.com/{}", ctx.file_info.id()))
+    }));
+    let location = create(&client, 4);
+
+    let response = patch(&client, &location, 0, b"ab");
+    assert_eq!(response.status(), Status::NoContent);
+    assert!(!response.headers().contains("X-Resource-Location"));
+
+    let response = patch(&client, &location, 2, b"cd");
+    assert_eq!(response.status(), Status::NoContent);
+    assert_eq!(
+        response.headers().get_one("X-Resource-Location"),
+        Some(
+            format!("https://cdn.example.com/{}", upload_id(&location))
+                .as_str()
+        )
+    );
+}
+
+#[test]
+fn no_header_when_the_callback_returns_none() {
+    let client = client(Meteoritus::new().with_resource_location(|_| None));
+    let location = create(&client, 1);
+
+    let response = patch(&client, &location, 0, b"a");
+    assert_eq!(response.status(), Status::NoContent);
+    assert!(!response.headers().contains("X-Resource-Location"));
+}
+
+#[test]
+fn panicking_callback_still_completes_the_upload() {
+    let client = client(
+        Meteoritus::new().with_resource_location(|_| panic!("unavailable")),
+    );
+    let location = create(&client, 1);
+
+    let response = patch(&client, &location, 0, b"a");
+    assert_eq!(response.status(), Status::NoContent);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("1"));
+    assert!(!response.headers().contains("X-Resource-Location"));
+}
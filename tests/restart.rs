@@ -0,0 +1,72 @@
+mod common;
+
+use common::{client_at, create, head, patch, temp_path, upload_id};
+use meteoritus::Meteoritus;
+use rocket::http::Status;
+use std::{fs, path::Path};
+
+#[test]
+fn offset_is_checked_against_the_manifest_after_a_restart() {
+    for meteoritus in
+        [Meteoritus::new, || Meteoritus::new().with_durable_writes()]
+    {
+        let path = temp_path();
+
+        let location = {
+            let client = client_at(meteoritus(), path);
+            let location = create(&client, 4);
+
+            assert_eq!(
+                patch(&client, &location, 0, b"ab").status(),
+                Status::NoContent
+            );
+            location
+        };
+
+        let client = client_at(meteoritus(), path);
+
+        let response = head(&client, &location);
+        assert_eq!(response.headers().get_one("Upload-Offset"), Some("2"));
+
+        assert_eq!(
+            patch(&client, &location, 0, b"ab").status(),
+            Status::Conflict
+        );
+        assert_eq!(
+            patch(&client, &location, 2, b"cd").status(),
+            Status::NoContent
+        );
+    }
+}
+
+#[test]
+fn manifest_is_replaced_without_leaving_a_staged_copy() {
+    let path = temp_path();
+
+    let location = {
+        let client = client_at(Meteoritus::new().with_durable_writes(), path);
+        let location = create(&client, 4);
+
+        assert_eq!(
+            patch(&client, &location, 0, b"ab").status(),
+            Status::NoContent
+        );
+        location
+    };
+
+    let dir = Path::new(path).join(upload_id(&location));
+    assert!(dir.join("info.json").exists());
+    assert!(!dir.join("info.json.tmp").exists());
+
+    /* A crash while staging leaves the previous manifest in place */
+    fs::write(dir.join("info.json.tmp"), b"{\"trunc").unwrap();
+
+    let client = client_at(Meteoritus::new().with_durable_writes(), path);
+
+    let response = head(&client, &location);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("2"));
+    assert_eq!(
+        patch(&client, &location, 2, b"cd").status(),
+        Status::NoContent
+    );
+}
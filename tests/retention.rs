@@ -0,0 +1,91 @@
+mod common;
+
+use common::{temp_path, tus_header, MemorySink};
+use meteoritus::{EventKind, Meteoritus};
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::asynchronous::Client,
+    tokio::time::sleep,
+};
+use std::{fs, time::Duration};
+
+async fn create(client: &Client) -> String {
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "3"))
+        .dispatch()
+        .await;
+
+    response.headers().get_one("Location").unwrap().to_string()
+}
+
+async fn complete(client: &Client, location: &str) {
+    let response = client
+        .patch(location.to_string())
+        .header(tus_header())
+        .header(Header::new("Upload-Offset", "0"))
+        .header(ContentType::new("application", "offset+octet-stream"))
+        .body("abc")
+        .dispatch()
+        .await;
+
+    assert_eq!(response.status(), Status::NoContent);
+}
+
+#[rocket::async_test]
+async fn sweeper_reclaims_unreleased_uploads() {
+    let path = temp_path();
+    let sink = MemorySink::default();
+
+    let meteoritus = Meteoritus::new()
+        .with_temp_path(path)
+        .keep_on_disk()
+        .with_completed_retention(Duration::from_millis(500))
+        .with_idle_expiration(Duration::from_millis(800))
+        .with_sweep_interval(Duration::from_millis(100))
+        .with_event_sink(sink.clone())
+        .build();
+
+    let client = Client::tracked(rocket::build().attach(meteoritus))
+        .await
+        .unwrap();
+
+    let completed = create(&client).await;
+    let _pending = create(&client).await;
+    complete(&client, &completed).await;
+
+    sleep(Duration::from_millis(650)).await;
+    assert_eq!(fs::read_dir(path).unwrap().count(), 1);
+
+    sleep(Duration::from_millis(400)).await;
+    assert_eq!(fs::read_dir(path).unwrap().count(), 0);
+
+    let expired = sink.kinds();
+    let expired = expired.iter().filter(|kind| **kind == EventKind::Expired);
+    assert_eq!(expired.count(), 2);
+}
+
+#[rocket::async_test]
+async fn sweeper_keeps_moved_files() {
+    let (path, completed_path) = (temp_path(), temp_path());
+
+    let meteoritus = Meteoritus::new()
+        .with_temp_path(path)
+        .keep_on_disk()
+        .with_completed_path(completed_path)
+        .with_completed_retention(Duration::from_millis(200))
+        .with_sweep_interval(Duration::from_millis(100))
+        .build();
+
+    let client = Client::tracked(rocket::build().attach(meteoritus))
+        .await
+        .unwrap();
+
+    let location = create(&client).await;
+    complete(&client, &location).await;
+
+    sleep(Duration::from_millis(500)).await;
+    assert_eq!(fs::read_dir(path).unwrap().count(), 0);
+    assert_eq!(fs::read_dir(completed_path).unwrap().count(), 1);
+}
@@ -0,0 +1,60 @@
+/* A ring buffer fails to ignite along with downloads, see tests/download.rs */
+#![cfg(all(feature = "ring", not(feature = "download")))]
+
+mod common;
+
+use common::{client_at, create, patch, temp_path, upload_id};
+use meteoritus::{InMemoryKeyProvider, Meteoritus};
+use rocket::{
+    data::ByteUnit, error::ErrorKind, http::Status, local::blocking::Client,
+    Build,
+};
+use std::{fs, path::Path};
+
+fn ring(capacity: u64) -> Meteoritus<Build> {
+    Meteoritus::new().with_ring_buffer(ByteUnit::Byte(capacity))
+}
+
+fn ignites(meteoritus: Meteoritus<Build>) -> bool {
+    let meteoritus = meteoritus
+        .with_temp_path(temp_path())
+        .keep_on_disk()
+        .build();
+
+    match Client::tracked(rocket::build().attach(meteoritus)) {
+        Ok(_) => true,
+        Err(error) => {
+            assert!(matches!(error.kind(), ErrorKind::FailedFairings(_)));
+            false
+        }
+    }
+}
+
+#[test]
+fn completed_ring_holds_the_tail_in_order() {
+    let path = temp_path();
+    let client = client_at(ring(4), path);
+
+    let location = create(&client, 6);
+    assert_eq!(
+        patch(&client, &location, 0, b"abc").status(),
+        Status::NoContent
+    );
+
+    let response = patch(&client, &location, 3, b"def");
+    assert_eq!(response.status(), Status::NoContent);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("6"));
+
+    let data = Path::new(path).join(upload_id(&location)).join("file");
+    assert_eq!(fs::read(data).unwrap(), b"cdef");
+}
+
+#[test]
+fn ring_refuses_encryption_and_checksums() {
+    let keys = InMemoryKeyProvider::new().with_key("k1", [1; 32]);
+
+    assert!(ignites(ring(4)));
+    assert!(!ignites(ring(4).with_key_provider(keys)));
+    assert!(!ignites(ring(4).with_checksum_key("sha256")));
+    assert!(!ignites(ring(4).with_chunk_checksums()));
+}
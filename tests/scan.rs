@@ -0,0 +1,156 @@
+mod common;
+
+use common::{temp_path, tus_header, upload_id, MemorySink};
+use meteoritus::{
+    Completed, EventKind, FileInfo, Meteoritus, ScanVerdict, UploadScanner,
+};
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::asynchronous::Client,
+    tokio::time::sleep,
+};
+use std::{path::Path, time::Duration};
+
+/// Quarantines files containing `virus`, after a short delay.
+struct MockScanner;
+
+#[rocket::async_trait]
+impl UploadScanner for MockScanner {
+    async fn scan(&self, file: &FileInfo<Completed>) -> ScanVerdict {
+        sleep(Duration::from_millis(300)).await;
+
+        match rocket::tokio::fs::read(file.file_name()).await {
+            Ok(bytes) if bytes.windows(5).any(|w| w == b"virus") => {
+                ScanVerdict::Infected
+            }
+            _ => ScanVerdict::Clean,
+        }
+    }
+}
+
+async fn upload(client: &Client, body: &'static str) -> String {
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", body.len().to_string()))
+        .dispatch()
+        .await;
+    let location = response.headers().get_one("Location").unwrap().to_string();
+
+    let response = client
+        .patch(location.clone())
+        .header(tus_header())
+        .header(Header::new("Upload-Offset", "0"))
+        .header(ContentType::new("application", "offset+octet-stream"))
+        .body(body)
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NoContent);
+
+    location
+}
+
+async fn scan_state(client: &Client, location: &str) -> Option<String> {
+    let response = client
+        .head(location.to_string())
+        .header(tus_header())
+        .dispatch()
+        .await;
+
+    response
+        .headers()
+        .get_one("X-Upload-Scan")
+        .map(String::from)
+}
+
+#[rocket::async_test]
+async fn scanner_gates_completed_uploads() {
+    let sink = MemorySink::default();
+
+    let meteoritus = Meteoritus::new()
+        .with_temp_path(temp_path())
+        .keep_on_disk()
+        .with_scanner(MockScanner)
+        .with_scan_timeout(Duration::from_millis(50))
+        .with_event_sink(sink.clone())
+        .build();
+
+    let client = Client::tracked(rocket::build().attach(meteoritus))
+        .await
+        .unwrap();
+
+    let clean = upload(&client, "hello").await;
+    let infected = upload(&client, "a virus").await;
+
+    assert_eq!(scan_state(&client, &clean).await.unwrap(), "scanning");
+    #[cfg(feature = "download")]
+    {
+        let response = client.get(infected.clone()).dispatch().await;
+        assert_eq!(response.status(), Status::Locked);
+    }
+
+    sleep(Duration::from_millis(600)).await;
+
+    assert_eq!(scan_state(&client, &clean).await.unwrap(), "clean");
+    assert_eq!(scan_state(&client, &infected).await.unwrap(), "infected");
+    #[cfg(feature = "download")]
+    {
+        let response = client.get(infected).dispatch().await;
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client.get(clean).dispatch().await;
+        assert_eq!(response.into_string().await.unwrap(), "hello");
+    }
+
+    let kinds = sink.kinds();
+    assert!(kinds.contains(&EventKind::Released));
+    assert!(kinds.contains(&EventKind::Quarantined));
+}
+
+async fn scanning_client(meteoritus: Meteoritus<rocket::Build>) -> Client {
+    let meteoritus = meteoritus
+        .with_temp_path(temp_path())
+        .keep_on_disk()
+        .with_scanner(MockScanner)
+        .build();
+
+    Client::tracked(rocket::build().attach(meteoritus))
+        .await
+        .unwrap()
+}
+
+#[cfg(feature = "download")]
+#[rocket::async_test]
+async fn downloads_wait_for_the_scan_verdict() {
+    let client = scanning_client(Meteoritus::new()).await;
+
+    let clean = upload(&client, "hello").await;
+    let response = client.get(clean).dispatch().await;
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_string().await.unwrap(), "hello");
+
+    let infected = upload(&client, "a virus").await;
+    let response = client.get(infected).dispatch().await;
+    assert_eq!(response.status(), Status::Forbidden);
+}
+
+#[rocket::async_test]
+async fn uploads_are_fanned_out_only_once_released() {
+    let fan_out: &'static [&'static str] = Box::leak(Box::new([temp_path()]));
+    let client =
+        scanning_client(Meteoritus::new().with_fan_out_paths(fan_out)).await;
+
+    let linked = |location: &str| {
+        Path::new(fan_out[0]).join(upload_id(location)).exists()
+    };
+
+    let clean = upload(&client, "hello").await;
+    let infected = upload(&client, "a virus").await;
+    assert!(!linked(&clean));
+    assert!(!linked(&infected));
+
+    sleep(Duration::from_millis(600)).await;
+
+    assert!(linked(&clean));
+    assert!(!linked(&infected));
+}
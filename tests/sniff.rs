@@ -0,0 +1,108 @@
+#![cfg(feature = "sniff")]
+
+mod common;
+
+use common::{b64, client, create_with_metadata, head, patch};
+use meteoritus::Meteoritus;
+use rocket::http::Status;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[test]
+fn mislabeled_content_is_rejected_and_terminated() {
+    static TERMINATED: AtomicUsize = AtomicUsize::new(0);
+
+    let client = client(
+        Meteoritus::new()
+            .reject_mismatched_content()
+            .on_termination(|_| {
+                TERMINATED.fetch_add(1, Ordering::SeqCst);
+            }),
+    );
+    let metadata = format!("filetype {}", b64("image/png"));
+
+    let response = create_with_metadata(&client, 8, &metadata);
+    let location = response.headers().get_one("Location").unwrap();
+
+    let response = patch(&client, location, 0, b"MZ\x90\x00\x03\x00\x00\x00");
+    assert_eq!(response.status(), Status::UnsupportedMediaType);
+    assert_eq!(head(&client, location).status(), Status::NotFound);
+    assert_eq!(TERMINATED.load(Ordering::SeqCst), 1);
+
+    let response = create_with_metadata(&client, 8, &metadata);
+    let location = response.headers().get_one("Location").unwrap();
+
+    let response = patch(&client, location, 0, b"\x89PNG\r\n\x1a\n");
+    assert_eq!(response.status(), Status::NoContent);
+    assert_eq!(TERMINATED.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn stale_first_chunk_conflicts_instead_of_terminating() {
+    let client = client(Meteoritus::new().reject_mismatched_content());
+    let metadata = format!("filetype {}", b64("image/png"));
+
+    let response = create_with_metadata(&client, 16, &metadata);
+    let location = response.headers().get_one("Location").unwrap();
+
+    let response = patch(&client, location, 0, b"\x89PNG\r\n\x1a\n");
+    assert_eq!(response.status(), Status::NoContent);
+
+    /* A replayed first chunk with other bytes, sent after the upload progressed */
+    let response = patch(&client, location, 0, b"MZ\x90\x00\x03\x00\x00\x00");
+    assert_eq!(response.status(), Status::Conflict);
+
+    let response = head(&client, location);
+    assert_eq!(response.status(), Status::NoContent);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("8"));
+}
+
+#[cfg(feature = "download")]
+#[test]
+fn download_is_served_with_the_detected_type() {
+    use common::{create, tus_header};
+
+    let client = client(Meteoritus::new().detect_content_type());
+
+    let location = create(&client, 8);
+    let response = patch(&client, &location, 0, b"\x89PNG\r\n\x1a\n");
+    assert_eq!(response.status(), Status::NoContent);
+
+    let response = client.get(location).header(tus_header()).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(
+        response.headers().get_one("Content-Type"),
+        Some("image/png")
+    );
+
+    let metadata = format!("filetype {}", b64("text/plain"));
+    let response = create_with_metadata(&client, 4, &metadata);
+    let location = response.headers().get_one("Location").unwrap();
+    assert_eq!(
+        patch(&client, location, 0, b"abcd").status(),
+        Status::NoContent
+    );
+
+    let response = client.get(location).header(tus_header()).dispatch();
+    assert_eq!(
+        response.headers().get_one("Content-Type"),
+        Some("text/plain")
+    );
+}
+
+#[cfg(feature = "download")]
+#[test]
+fn detected_types_are_attachments_unless_displayed_safely() {
+    use common::{create, tus_header};
+
+    let client = client(Meteoritus::new().detect_content_type());
+
+    let location = create(&client, 4);
+    let response = patch(&client, &location, 0, b"PK\x03\x04");
+    assert_eq!(response.status(), Status::NoContent);
+
+    let response = client.get(location).header(tus_header()).dispatch();
+    let headers = response.headers();
+    assert_eq!(headers.get_one("Content-Type"), Some("application/zip"));
+    assert_eq!(headers.get_one("Content-Disposition"), Some("attachment"));
+    assert_eq!(headers.get_one("X-Content-Type-Options"), Some("nosniff"));
+}
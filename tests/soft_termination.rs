@@ -0,0 +1,91 @@
+mod common;
+
+use common::{client, create, head, patch, temp_path, tus_header};
+use meteoritus::Meteoritus;
+use rocket::{
+    http::{Header, Status},
+    local::asynchronous,
+    tokio::time::sleep,
+    Orbit,
+};
+use std::time::Duration;
+
+#[test]
+fn hard_termination_removes_the_upload() {
+    let client = client(Meteoritus::new());
+    let location = create(&client, 3);
+
+    let response = client
+        .delete(location.clone())
+        .header(tus_header())
+        .dispatch();
+    assert_eq!(response.status(), Status::NoContent);
+
+    assert_eq!(head(&client, &location).status(), Status::NotFound);
+    assert_eq!(
+        patch(&client, &location, 0, b"abc").status(),
+        Status::NotFound
+    );
+}
+
+#[test]
+fn soft_terminated_upload_is_gone_until_restored() {
+    let client = client(
+        Meteoritus::new().with_soft_termination(Duration::from_secs(60)),
+    );
+    let location = create(&client, 3);
+
+    let response = client
+        .delete(location.clone())
+        .header(tus_header())
+        .dispatch();
+    assert_eq!(response.status(), Status::NoContent);
+
+    assert_eq!(head(&client, &location).status(), Status::Gone);
+    assert_eq!(patch(&client, &location, 0, b"abc").status(), Status::Gone);
+
+    let meteoritus = client.rocket().state::<Meteoritus<Orbit>>().unwrap();
+    let id = location.rsplit('/').next().unwrap();
+    assert!(meteoritus.restore(id).is_some());
+
+    assert_eq!(head(&client, &location).status(), Status::NoContent);
+    assert_eq!(
+        patch(&client, &location, 0, b"abc").status(),
+        Status::NoContent
+    );
+}
+
+#[rocket::async_test]
+async fn soft_terminated_upload_is_purged_after_the_window() {
+    let meteoritus = Meteoritus::new()
+        .with_temp_path(temp_path())
+        .keep_on_disk()
+        .with_soft_termination(Duration::from_millis(200))
+        .with_sweep_interval(Duration::from_millis(50))
+        .build();
+
+    let client =
+        asynchronous::Client::tracked(rocket::build().attach(meteoritus))
+            .await
+            .unwrap();
+
+    let response = client
+        .post("/meteoritus")
+        .header(tus_header())
+        .header(Header::new("Upload-Length", "3"))
+        .dispatch()
+        .await;
+    let location = response.headers().get_one("Location").unwrap().to_string();
+
+    let response = client
+        .delete(location.clone())
+        .header(tus_header())
+        .dispatch()
+        .await;
+    assert_eq!(response.status(), Status::NoContent);
+
+    sleep(Duration::from_millis(600)).await;
+
+    let response = client.head(location).header(tus_header()).dispatch().await;
+    assert_eq!(response.status(), Status::NotFound);
+}
@@ -0,0 +1,76 @@
+mod common;
+
+use common::{client, client_at, create, patch, temp_path};
+use meteoritus::{Meteoritus, UploadStats};
+use rocket::{http::Status, Build};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+type Recorded = Arc<Mutex<Vec<(UploadStats, Option<Duration>)>>>;
+
+fn recording(recorded: &Recorded) -> Meteoritus<Build> {
+    let recorded = Arc::clone(recorded);
+
+    Meteoritus::new().on_completed(move |ctx| {
+        recorded
+            .lock()
+            .unwrap()
+            .push((*ctx.file_info.stats(), ctx.file_info.upload_duration()));
+    })
+}
+
+#[test]
+fn completion_callback_receives_the_patch_stats() {
+    let recorded = Recorded::default();
+    let client = client(recording(&recorded));
+    let location = create(&client, 6);
+
+    for (offset, chunk) in [(0, &b"a"[..]), (1, b"bcd"), (4, b"ef")] {
+        assert_eq!(
+            patch(&client, &location, offset, chunk).status(),
+            Status::NoContent
+        );
+    }
+
+    let recorded = recorded.lock().unwrap();
+    let (stats, duration) = recorded[0];
+
+    assert_eq!(stats.patch_requests(), 3);
+    assert_eq!(stats.peak_chunk_size(), 3);
+    assert_eq!(stats.average_chunk_size(), 2);
+    assert!(duration.is_some());
+}
+
+#[test]
+fn stats_survive_a_restart() {
+    let path = temp_path();
+    let recorded = Recorded::default();
+
+    let location = {
+        let client = client_at(recording(&recorded), path);
+        let location = create(&client, 5);
+
+        assert_eq!(
+            patch(&client, &location, 0, b"abcd").status(),
+            Status::NoContent
+        );
+        location
+    };
+
+    let client = client_at(recording(&recorded), path);
+    assert_eq!(
+        patch(&client, &location, 4, b"e").status(),
+        Status::NoContent
+    );
+
+    let (stats, _) = recorded.lock().unwrap()[0];
+    assert_eq!(stats.patch_requests(), 2);
+    assert_eq!(stats.peak_chunk_size(), 4);
+}
+
+#[test]
+fn empty_stats_average_to_zero() {
+    assert_eq!(UploadStats::default().average_chunk_size(), 0);
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use common::{
+    client_at, create, head, patch, temp_path, tus_header, upload_id,
+};
+use meteoritus::Meteoritus;
+use rocket::http::Status;
+use std::{fs, path::Path};
+
+#[test]
+fn random_file_names_do_not_reveal_the_upload_id() {
+    let path = temp_path();
+    let client = client_at(Meteoritus::new().with_random_file_names(), path);
+
+    let location = create(&client, 4);
+    let id = upload_id(&location).to_string();
+
+    let upload_dir = Path::new(path).join(&id);
+    let entries: Vec<_> = fs::read_dir(&upload_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(entries, vec!["info.json".to_string()]);
+
+    let partial: Vec<_> = fs::read_dir(Path::new(path).join(".partial"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    assert_eq!(partial.len(), 1);
+    assert_ne!(partial[0], id);
+
+    assert_eq!(
+        patch(&client, &location, 0, b"ab").status(),
+        Status::NoContent
+    );
+    assert_eq!(head(&client, &location).status(), Status::NoContent);
+    assert_eq!(
+        patch(&client, &location, 2, b"cd").status(),
+        Status::NoContent
+    );
+
+    assert_eq!(fs::read(upload_dir.join("file")).unwrap(), b"abcd");
+    assert!(fs::read_dir(Path::new(path).join(".partial"))
+        .unwrap()
+        .next()
+        .is_none());
+}
+
+#[test]
+fn termination_removes_randomly_named_data() {
+    let path = temp_path();
+    let client = client_at(Meteoritus::new().with_random_file_names(), path);
+
+    let location = create(&client, 4);
+    assert_eq!(
+        patch(&client, &location, 0, b"ab").status(),
+        Status::NoContent
+    );
+
+    let response = client
+        .delete(location.clone())
+        .header(tus_header())
+        .dispatch();
+    assert_eq!(response.status(), Status::NoContent);
+
+    assert!(fs::read_dir(Path::new(path).join(".partial"))
+        .unwrap()
+        .next()
+        .is_none());
+    assert_eq!(head(&client, &location).status(), Status::NotFound);
+}
+
+#[test]
+fn local_vault_options_keep_each_other() {
+    let path = temp_path();
+    let client = client_at(
+        Meteoritus::new()
+            .with_random_file_names()
+            .with_durable_writes(),
+        path,
+    );
+
+    let location = create(&client, 2);
+
+    assert!(!Path::new(path)
+        .join(upload_id(&location))
+        .join("file")
+        .exists());
+}
+
+#[test]
+fn uploads_resume_after_moving_the_storage_root() {
+    let old_root = temp_path();
+    let location = {
+        let client = client_at(Meteoritus::new(), old_root);
+        let location = create(&client, 4);
+        assert_eq!(
+            patch(&client, &location, 0, b"ab").status(),
+            Status::NoContent
+        );
+        location
+    };
+
+    let new_root = temp_path();
+    fs::rename(old_root, new_root).unwrap();
+
+    let client = client_at(Meteoritus::new(), new_root);
+    let response = head(&client, &location);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("2"));
+    assert_eq!(
+        patch(&client, &location, 2, b"cd").status(),
+        Status::NoContent
+    );
+
+    let upload_dir = Path::new(new_root).join(upload_id(&location));
+    assert_eq!(fs::read(upload_dir.join("file")).unwrap(), b"abcd");
+}
+
+#[test]
+fn legacy_root_prefixed_locations_are_read_unchanged() {
+    /* A relative root, as locations were stored prefixed with it */
+    let root =
+        Path::new("target").join(Path::new(temp_path()).file_name().unwrap());
+    let root: &'static str =
+        Box::leak(root.to_str().unwrap().to_string().into_boxed_str());
+
+    let client = client_at(Meteoritus::new(), root);
+    let location = create(&client, 4);
+
+    let manifest_path =
+        Path::new(root).join(upload_id(&location)).join("info.json");
+    let mut manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap())
+            .unwrap();
+    let legacy_name = format!("{}/{}/file", root, upload_id(&location));
+    manifest["file_name"] = legacy_name.into();
+    manifest.as_object_mut().unwrap().remove("root_relative");
+    fs::write(&manifest_path, manifest.to_string()).unwrap();
+
+    assert_eq!(
+        patch(&client, &location, 0, b"abcd").status(),
+        Status::NoContent
+    );
+    assert_eq!(
+        fs::read(Path::new(root).join(upload_id(&location)).join("file"))
+            .unwrap(),
+        b"abcd"
+    );
+
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn relative_completed_paths_are_not_joined_to_the_root() {
+    let completed =
+        Path::new("target").join(Path::new(temp_path()).file_name().unwrap());
+    let completed: &'static str =
+        Box::leak(completed.to_str().unwrap().to_string().into_boxed_str());
+
+    let path = temp_path();
+    let client =
+        client_at(Meteoritus::new().with_completed_path(completed), path);
+
+    let location = create(&client, 2);
+    assert_eq!(
+        patch(&client, &location, 0, b"ab").status(),
+        Status::NoContent
+    );
+
+    let moved = Path::new(completed).join(upload_id(&location));
+    assert_eq!(fs::read(&moved).unwrap(), b"ab");
+
+    let manifest = Path::new(path).join(upload_id(&location)).join("info.json");
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(manifest).unwrap()).unwrap();
+    assert_eq!(manifest["file_name"], moved.to_str().unwrap());
+    assert_ne!(manifest["root_relative"], true);
+
+    #[cfg(feature = "download")]
+    {
+        let response = client.get(location.clone()).dispatch();
+        assert_eq!(response.into_string().unwrap(), "ab");
+    }
+
+    fs::remove_dir_all(completed).unwrap();
+}
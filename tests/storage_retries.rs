@@ -0,0 +1,183 @@
+mod common;
+
+use common::{client, create, head, patch, temp_path};
+use meteoritus::{
+    Built, Completed, Created, Expiration, FileInfo, LocalVault, Metadata,
+    Meteoritus, PatchOption, ScanState, Terminated, Vault, VaultError,
+};
+use rocket::{http::Status, tokio::io::AsyncRead};
+use std::{
+    io,
+    path::Path,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+/// A local vault whose first `failures` writes time out.
+struct FlakyVault {
+    inner: LocalVault,
+    failures: AtomicU32,
+    writes: Arc<AtomicU32>,
+}
+
+impl Vault for FlakyVault {
+    fn build_file(
+        &self,
+        length: u64,
+        metadata: Option<Metadata>,
+        expiration: &Expiration,
+    ) -> Result<FileInfo<Built>, VaultError> {
+        self.inner.build_file(length, metadata, expiration)
+    }
+
+    fn create_file(
+        &self,
+        file: FileInfo<Built>,
+    ) -> Result<FileInfo<Created>, VaultError> {
+        self.inner.create_file(file)
+    }
+
+    fn exists(&self, file_id: &str) -> bool {
+        self.inner.exists(file_id)
+    }
+
+    fn get_file(&self, file_id: &str) -> Result<FileInfo<Created>, VaultError> {
+        self.inner.get_file(file_id)
+    }
+
+    fn list_files(
+        &self,
+    ) -> Result<Box<dyn Iterator<Item = FileInfo<Created>> + '_>, VaultError>
+    {
+        self.inner.list_files()
+    }
+
+    fn open_read(
+        &self,
+        file_id: &str,
+        offset: u64,
+    ) -> Result<Pin<Box<dyn AsyncRead + Send>>, VaultError> {
+        self.inner.open_read(file_id, offset)
+    }
+
+    fn patch_file(
+        &self,
+        file_id: &str,
+        buf: &mut [u8],
+        offset: u64,
+        expiration: &Expiration,
+    ) -> Result<PatchOption, VaultError> {
+        self.writes.fetch_add(1, Ordering::SeqCst);
+
+        let failing = self
+            .failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                left.checked_sub(1)
+            })
+            .is_ok();
+
+        match failing {
+            true => Err(VaultError::Transient(
+                io::Error::from(io::ErrorKind::TimedOut).into(),
+            )),
+            false => self.inner.patch_file(file_id, buf, offset, expiration),
+        }
+    }
+
+    fn finalize_file(
+        &self,
+        file_id: &str,
+        destination: &Path,
+        progress: &mut dyn FnMut(u64),
+    ) -> Result<FileInfo<Completed>, VaultError> {
+        self.inner.finalize_file(file_id, destination, progress)
+    }
+
+    fn terminate_file(
+        &self,
+        file_id: &str,
+    ) -> Result<FileInfo<Terminated>, VaultError> {
+        self.inner.terminate_file(file_id)
+    }
+
+    fn tombstone_file(
+        &self,
+        file_id: &str,
+    ) -> Result<FileInfo<Terminated>, VaultError> {
+        self.inner.tombstone_file(file_id)
+    }
+
+    fn restore_file(
+        &self,
+        file_id: &str,
+    ) -> Result<FileInfo<Created>, VaultError> {
+        self.inner.restore_file(file_id)
+    }
+
+    fn list_tombstones(
+        &self,
+    ) -> Result<Box<dyn Iterator<Item = FileInfo<Terminated>> + '_>, VaultError>
+    {
+        self.inner.list_tombstones()
+    }
+
+    fn set_scan_state(
+        &self,
+        file_id: &str,
+        state: ScanState,
+    ) -> Result<FileInfo<Completed>, VaultError> {
+        self.inner.set_scan_state(file_id, state)
+    }
+}
+
+/// Serves uploads from a vault failing its first `failures` writes, retried `attempts` times.
+fn flaky(
+    failures: u32,
+    attempts: u32,
+) -> (rocket::local::blocking::Client, Arc<AtomicU32>) {
+    let writes = Arc::new(AtomicU32::new(0));
+    let vault = FlakyVault {
+        inner: LocalVault::new(temp_path()),
+        failures: AtomicU32::new(failures),
+        writes: Arc::clone(&writes),
+    };
+
+    let client = client(
+        Meteoritus::new()
+            .with_vault(vault)
+            .with_storage_retries(attempts, Duration::from_millis(1)),
+    );
+
+    (client, writes)
+}
+
+#[test]
+fn transient_failures_are_retried_within_the_budget() {
+    let (client, writes) = flaky(2, 3);
+    let location = create(&client, 5);
+
+    let response = patch(&client, &location, 0, b"hello");
+    assert_eq!(response.status(), Status::NoContent);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("5"));
+    assert_eq!(writes.load(Ordering::SeqCst), 3);
+
+    let response = head(&client, &location);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("5"));
+}
+
+#[test]
+fn exhausted_retries_leave_the_offset_unchanged() {
+    let (client, writes) = flaky(3, 2);
+    let location = create(&client, 5);
+
+    let response = patch(&client, &location, 0, b"hello");
+    assert_eq!(response.status(), Status::ServiceUnavailable);
+    assert_eq!(writes.load(Ordering::SeqCst), 3);
+
+    let response = head(&client, &location);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("0"));
+}
@@ -0,0 +1,53 @@
+mod common;
+
+use common::{client, create, head, tus_header};
+use meteoritus::Meteoritus;
+use rocket::http::Status;
+
+#[test]
+fn lenient_termination_drains_small_bodies() {
+    let client = client(Meteoritus::new());
+
+    let location = create(&client, 10);
+    let response = client
+        .delete(location.clone())
+        .header(tus_header())
+        .body("garbage body")
+        .dispatch();
+
+    assert_eq!(response.status(), Status::NoContent);
+    assert_eq!(head(&client, &location).status(), Status::NotFound);
+}
+
+#[test]
+fn lenient_termination_rejects_oversized_bodies() {
+    let client = client(Meteoritus::new());
+
+    let location = create(&client, 10);
+    let response = client
+        .delete(location.clone())
+        .header(tus_header())
+        .body(vec![0; 64 * 1024 + 1])
+        .dispatch();
+
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(response.headers().get_one("Connection"), Some("close"));
+    assert_eq!(head(&client, &location).status(), Status::NoContent);
+}
+
+#[test]
+fn strict_termination_rejects_any_body() {
+    let client = client(Meteoritus::new().strict_termination());
+
+    let location = create(&client, 10);
+    let response = client
+        .delete(location.clone())
+        .header(tus_header())
+        .body("garbage body")
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(response.headers().get_one("Connection"), Some("close"));
+
+    let response = client.delete(location).header(tus_header()).dispatch();
+    assert_eq!(response.status(), Status::NoContent);
+}
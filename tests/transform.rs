@@ -0,0 +1,96 @@
+mod common;
+
+use common::{client_at, create, patch, temp_path, upload_id};
+use meteoritus::{BodyTransform, Meteoritus};
+use rocket::{http::Status, local::blocking::Client};
+use std::{
+    fs,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+/// Uppercases chunks, recording the offset of every transformed chunk.
+struct Recording(Arc<Mutex<Vec<u64>>>);
+
+#[rocket::async_trait]
+impl BodyTransform for Recording {
+    async fn transform(&self, offset: u64, chunk: Vec<u8>) -> Vec<u8> {
+        self.0.lock().unwrap().push(offset);
+        chunk.to_ascii_uppercase()
+    }
+}
+
+struct Truncating;
+
+#[rocket::async_trait]
+impl BodyTransform for Truncating {
+    async fn transform(&self, _offset: u64, chunk: Vec<u8>) -> Vec<u8> {
+        chunk[1..].to_vec()
+    }
+}
+
+fn recording(path: &'static str) -> (Client, Arc<Mutex<Vec<u64>>>) {
+    let offsets = Arc::new(Mutex::new(Vec::new()));
+    let client = client_at(
+        Meteoritus::new().with_body_transform(Recording(Arc::clone(&offsets))),
+        path,
+    );
+
+    (client, offsets)
+}
+
+#[test]
+fn transformed_chunks_are_written() {
+    let path = temp_path();
+    let (client, offsets) = recording(path);
+    let location = create(&client, 6);
+
+    assert_eq!(
+        patch(&client, &location, 0, b"abc").status(),
+        Status::NoContent
+    );
+    assert_eq!(
+        patch(&client, &location, 3, b"def").status(),
+        Status::NoContent
+    );
+
+    let stored =
+        fs::read(Path::new(path).join(upload_id(&location)).join("file"));
+    assert_eq!(stored.unwrap(), b"ABCDEF");
+    assert_eq!(*offsets.lock().unwrap(), [0, 3]);
+}
+
+#[test]
+fn retried_and_mismatched_chunks_are_not_transformed() {
+    let (client, offsets) = recording(temp_path());
+    let location = create(&client, 6);
+
+    assert_eq!(
+        patch(&client, &location, 0, b"abc").status(),
+        Status::NoContent
+    );
+
+    /* A retry of the applied chunk, then a chunk ahead of the offset */
+    assert_eq!(
+        patch(&client, &location, 0, b"abc").status(),
+        Status::Conflict
+    );
+    assert_eq!(
+        patch(&client, &location, 4, b"ef").status(),
+        Status::Conflict
+    );
+
+    assert_eq!(*offsets.lock().unwrap(), [0]);
+}
+
+#[test]
+fn transform_changing_the_length_is_rejected() {
+    let client = client_at(
+        Meteoritus::new().with_body_transform(Truncating),
+        temp_path(),
+    );
+    let location = create(&client, 3);
+
+    let response = patch(&client, &location, 0, b"abc");
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
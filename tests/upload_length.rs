@@ -0,0 +1,55 @@
+mod common;
+
+use common::{client, create, head, tus_header};
+use meteoritus::Meteoritus;
+use rocket::{
+    http::{ContentType, Header, Status},
+    local::blocking::{Client, LocalResponse},
+};
+
+fn patch_with_length<'c>(
+    client: &'c Client,
+    location: &str,
+    upload_length: &str,
+) -> LocalResponse<'c> {
+    client
+        .patch(location.to_string())
+        .header(tus_header())
+        .header(Header::new("Upload-Offset", "0"))
+        .header(Header::new("Upload-Length", upload_length.to_string()))
+        .header(ContentType::new("application", "offset+octet-stream"))
+        .body(b"ab")
+        .dispatch()
+}
+
+#[test]
+fn matching_upload_length_is_tolerated() {
+    let client = client(Meteoritus::new());
+    let location = create(&client, 4);
+
+    let response = patch_with_length(&client, &location, "4");
+    assert_eq!(response.status(), Status::NoContent);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("2"));
+}
+
+#[test]
+fn different_upload_length_is_rejected() {
+    let client = client(Meteoritus::new());
+    let location = create(&client, 4);
+
+    let response = patch_with_length(&client, &location, "5");
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let response = head(&client, &location);
+    assert_eq!(response.headers().get_one("Upload-Offset"), Some("0"));
+    assert_eq!(response.headers().get_one("Upload-Length"), Some("4"));
+}
+
+#[test]
+fn invalid_upload_length_is_rejected() {
+    let client = client(Meteoritus::new());
+    let location = create(&client, 4);
+
+    let response = patch_with_length(&client, &location, "four");
+    assert_eq!(response.status(), Status::BadRequest);
+}
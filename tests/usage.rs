@@ -0,0 +1,82 @@
+mod common;
+
+use common::{client, create_with_metadata, patch};
+use meteoritus::{Meteoritus, UploadUsage};
+use rocket::{http::Status, local::blocking::Client, Orbit};
+
+fn upload(client: &Client, metadata: &str, length: u64) {
+    let response = create_with_metadata(client, length, metadata);
+    assert_eq!(response.status(), Status::Created);
+    let location = response.headers().get_one("Location").unwrap().to_string();
+
+    let body = vec![b'x'; length as usize];
+    assert_eq!(
+        patch(client, &location, 0, &body).status(),
+        Status::NoContent
+    );
+}
+
+#[test]
+fn usage_is_accounted_by_metadata_value() {
+    let client = client(Meteoritus::new().with_usage_accounting("tenant", 2));
+
+    upload(&client, "tenant YQ==", 3);
+    upload(&client, "tenant YQ==", 2);
+    upload(&client, "tenant Yg==", 4);
+
+    let usage = client
+        .rocket()
+        .state::<Meteoritus<Orbit>>()
+        .unwrap()
+        .usage();
+
+    assert_eq!(usage.values.len(), 2);
+    assert_eq!(
+        usage.values["a"],
+        UploadUsage {
+            uploads: 2,
+            bytes: 5
+        }
+    );
+    assert_eq!(
+        usage.values["b"],
+        UploadUsage {
+            uploads: 1,
+            bytes: 4
+        }
+    );
+    assert_eq!(usage.other, UploadUsage::default());
+}
+
+#[test]
+fn usage_beyond_the_cardinality_is_accounted_apart() {
+    let client = client(Meteoritus::new().with_usage_accounting("tenant", 2));
+
+    upload(&client, "tenant YQ==", 3);
+    /* A real `_other` value is not merged with the overflow */
+    upload(&client, "tenant X290aGVy", 2);
+    upload(&client, "tenant Yg==", 1);
+    upload(&client, "name YQ==", 4);
+
+    let usage = client
+        .rocket()
+        .state::<Meteoritus<Orbit>>()
+        .unwrap()
+        .usage();
+
+    assert_eq!(usage.values.len(), 2);
+    assert_eq!(
+        usage.values["_other"],
+        UploadUsage {
+            uploads: 1,
+            bytes: 2
+        }
+    );
+    assert_eq!(
+        usage.other,
+        UploadUsage {
+            uploads: 2,
+            bytes: 5
+        }
+    );
+}